@@ -0,0 +1,79 @@
+//! Fetching and decoding program accounts.
+//!
+//! Fetching goes through `AccountSource`, which a backend implements over
+//! its `RpcClient` (`get_account_data`, mapping "account not found" to
+//! `Ok(None)`), a cache, or a test validator.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use simple_oracle_quest::{Bet, Config, ErrorCode, OracleEvent, PlayerProfile};
+use thiserror::Error;
+
+use crate::pda::{bet_address, config_address, event_address, player_address};
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("account data could not be decoded: {0}")]
+    Decode(String),
+    #[error("rpc request failed: {0}")]
+    Rpc(String),
+    #[error("program error {code}: {0}", code = u32::from(*.0))]
+    Program(ErrorCode),
+}
+
+impl From<ErrorCode> for ClientError {
+    fn from(error: ErrorCode) -> Self {
+        Self::Program(error)
+    }
+}
+
+/// Read access to account data, as offered by an `RpcClient`.
+pub trait AccountSource {
+    /// Data of the account at `address`, or `None` when it does not exist.
+    fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError>;
+}
+
+/// Decodes an account, checking its discriminator.
+pub fn decode<T: AccountDeserialize>(mut data: &[u8]) -> Result<T, ClientError> {
+    T::try_deserialize(&mut data).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+/// Decodes what a view instruction or `place_bet` set as return data.
+pub fn decode_return_data<T: AnchorDeserialize>(data: &[u8]) -> Result<T, ClientError> {
+    T::try_from_slice(data).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+pub fn fetch<T: AccountDeserialize>(
+    source: &impl AccountSource,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    let data = source
+        .get_account_data(address)?
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    decode(&data)
+}
+
+pub fn fetch_event(source: &impl AccountSource, event_id: u64) -> Result<OracleEvent, ClientError> {
+    fetch(source, &event_address(event_id))
+}
+
+pub fn fetch_player(
+    source: &impl AccountSource,
+    player: &Pubkey,
+) -> Result<PlayerProfile, ClientError> {
+    fetch(source, &player_address(player))
+}
+
+pub fn fetch_bet(
+    source: &impl AccountSource,
+    player: &Pubkey,
+    event_id: u64,
+) -> Result<Bet, ClientError> {
+    fetch(source, &bet_address(player, event_id))
+}
+
+pub fn fetch_config(source: &impl AccountSource) -> Result<Config, ClientError> {
+    fetch(source, &config_address())
+}
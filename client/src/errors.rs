@@ -0,0 +1,102 @@
+//! Mapping custom error numbers back to the program's `ErrorCode`.
+
+use anchor_lang::solana_program::instruction::error::InstructionError;
+use simple_oracle_quest::ErrorCode;
+
+macro_rules! error_codes {
+    ($($variant:ident),* $(,)?) => {
+        /// Every `ErrorCode` variant.
+        pub const ERROR_CODES: &[ErrorCode] = &[$(ErrorCode::$variant),*];
+
+        // Stops compiling when a variant is added without listing it above.
+        const _: fn(ErrorCode) = |code| match code {
+            $(ErrorCode::$variant)|* => {}
+        };
+    };
+}
+
+error_codes!(
+    AlreadyResolved,
+    EventNotResolved,
+    AlreadyClaimed,
+    BetLost,
+    DescriptionTooLong,
+    NotBetOwner,
+    InvalidAmount,
+    FeeTooHigh,
+    InsufficientLiquidity,
+    SlippageExceeded,
+    InsufficientShares,
+    MathOverflow,
+    InvalidPrice,
+    OrderBookFull,
+    OrderNotFound,
+    NotOrderOwner,
+    NotYesPosition,
+    MissingFillAccounts,
+    FillAccountMismatch,
+    NothingToMatch,
+    EventCancelled,
+    EventNotCancelled,
+    EventMismatch,
+    InvalidParlayLegs,
+    HouseExposureExceeded,
+    InvalidStakeLimits,
+    StakeBelowMinimum,
+    StakeAboveMaximum,
+    ExposureLimitExceeded,
+    AlreadySettled,
+    BetWon,
+    AlreadyMigrated,
+    InvalidAccountLayout,
+    InvalidSeason,
+    SeasonAccountRequired,
+    SeasonFinalized,
+    SeasonNotFinalized,
+    SeasonNotEnded,
+    InvalidMerkleProof,
+    SelfReferral,
+    ReferrerNotRegistered,
+    EventHasBets,
+    MetadataUriTooLong,
+    DuplicateTag,
+    ExplicitEventIdsDisabled,
+    ExplicitEventIdOutOfRange,
+    ClaimsOutstanding,
+    BetNotSettled,
+    InvalidClaimAccounts,
+    InvalidCrankAccounts,
+    UnauthorizedSigner,
+    InvalidSession,
+    SessionExpired,
+    SessionScopeDenied,
+    SessionBudgetExceeded,
+    NotGuildMember,
+    GuildRoleRequired,
+    GuildFull,
+    InvalidGuildRole,
+    OrderTooSmall,
+    EvictedOrderAccountMismatch,
+    NotUpgradeAuthority,
+    InvalidLegOdds,
+    LegOddsNotSet,
+    SeasonNotActive,
+    GuildBetsOpen,
+    ParlayLegsOpen,
+);
+
+/// The `ErrorCode` behind a custom error number, if it is one of ours.
+pub fn program_error(code: u32) -> Option<ErrorCode> {
+    ERROR_CODES
+        .iter()
+        .copied()
+        .find(|error| u32::from(*error) == code)
+}
+
+/// The `ErrorCode` a failed instruction returned, if any.
+pub fn program_error_from_instruction(error: &InstructionError) -> Option<ErrorCode> {
+    match error {
+        InstructionError::Custom(code) => program_error(*code),
+        _ => None,
+    }
+}
@@ -0,0 +1,238 @@
+//! Typed builders for the program's instructions.
+//!
+//! Each builder derives every PDA the instruction needs, so callers only
+//! supply wallets and arguments. Signers are noted per builder.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use simple_oracle_quest::{accounts, instruction, EventParams, ID};
+
+use crate::pda::{
+    bet_address, category_counter_address, config_address, event_address, player_address,
+    referral_address, season_address, season_score_address, session_address,
+};
+
+/// Signed by `player`.
+pub fn initialize_player(player: &Pubkey, referrer: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitializePlayer {
+            player_profile: player_address(player),
+            referrer_rewards: referrer.as_ref().map(referral_address),
+            signer: *player,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializePlayer { referrer }.data(),
+    }
+}
+
+/// Signed by `authority` and `payer`. `event_id` is the id the category
+/// counter will allocate, i.e. `CategoryCounter::next_event_id`.
+pub fn create_oracle_event(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    event_id: u64,
+    params: EventParams,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::CreateOracleEvent {
+            category_counter: category_counter_address(params.category),
+            oracle_event: event_address(event_id),
+            season: None,
+            authority: *authority,
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateOracleEvent { params }.data(),
+    }
+}
+
+/// Signed by `player`.
+pub fn place_bet(player: &Pubkey, event_id: u64, chosen_outcome: bool, amount: u64) -> Instruction {
+    place_bet_as(player, player, None, event_id, chosen_outcome, amount)
+}
+
+/// Signed by `session_key`, staking from the session's escrowed budget.
+pub fn place_bet_with_session(
+    player: &Pubkey,
+    session_key: &Pubkey,
+    event_id: u64,
+    chosen_outcome: bool,
+    amount: u64,
+) -> Instruction {
+    let session = session_address(player, session_key);
+    place_bet_as(
+        player,
+        session_key,
+        Some(session),
+        event_id,
+        chosen_outcome,
+        amount,
+    )
+}
+
+fn place_bet_as(
+    player: &Pubkey,
+    signer: &Pubkey,
+    session_token: Option<Pubkey>,
+    event_id: u64,
+    chosen_outcome: bool,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::PlaceBet {
+            bet: bet_address(player, event_id),
+            player_profile: player_address(player),
+            oracle_event: event_address(event_id),
+            config: config_address(),
+            player: *player,
+            session_token,
+            signer: *signer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::PlaceBet {
+            event_id,
+            chosen_outcome,
+            amount,
+        }
+        .data(),
+    }
+}
+
+/// Signed by the event's `authority`.
+pub fn resolve_event(authority: &Pubkey, event_id: u64, outcome: bool) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::ResolveEvent {
+            oracle_event: event_address(event_id),
+            authority: *authority,
+        }
+        .to_account_metas(None),
+        data: instruction::ResolveEvent { outcome }.data(),
+    }
+}
+
+/// Accounts shared by the claim and settle instructions of `player`'s bet.
+/// `season_id` is the event's season, `0` for none; `referrer` is the
+/// player's `PlayerProfile::referrer`.
+fn claim_accounts(
+    player: &Pubkey,
+    event_id: u64,
+    season_id: u64,
+    referrer: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    accounts::ClaimWinnings {
+        bet: bet_address(player, event_id),
+        oracle_event: event_address(event_id),
+        player_profile: player_address(player),
+        season_score: (season_id != 0).then(|| season_score_address(season_id, player)),
+        config: config_address(),
+        referrer_rewards: referrer.as_ref().map(referral_address),
+        player: *player,
+        session_token: None,
+        signer: *player,
+        season: (season_id != 0).then(|| season_address(season_id)),
+    }
+    .to_account_metas(None)
+}
+
+/// Signed by `player`.
+pub fn claim_winnings(
+    player: &Pubkey,
+    event_id: u64,
+    season_id: u64,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: claim_accounts(player, event_id, season_id, referrer),
+        data: instruction::ClaimWinnings {}.data(),
+    }
+}
+
+/// Signed by `player`. Settles a win, loss or refund alike.
+pub fn settle_bet(
+    player: &Pubkey,
+    event_id: u64,
+    season_id: u64,
+    referrer: Option<Pubkey>,
+    close_bet: bool,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: claim_accounts(player, event_id, season_id, referrer),
+        data: instruction::SettleBet { close_bet }.data(),
+    }
+}
+
+/// Signed by `player`, once the bet is settled or its event closed.
+pub fn close_bet(player: &Pubkey, event_id: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::CloseBet {
+            bet: bet_address(player, event_id),
+            player_profile: player_address(player),
+            oracle_event: event_address(event_id),
+            signer: *player,
+        }
+        .to_account_metas(None),
+        data: instruction::CloseBet {}.data(),
+    }
+}
+
+/// View; decode the return data as a `PayoutQuote`.
+pub fn quote_payout(event_id: u64, chosen_outcome: bool, amount: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::QuotePayout {
+            oracle_event: event_address(event_id),
+            config: config_address(),
+        }
+        .to_account_metas(None),
+        data: instruction::QuotePayout {
+            chosen_outcome,
+            amount,
+        }
+        .data(),
+    }
+}
+
+/// View; decode the return data as an `EventSummary`.
+pub fn get_event_summary(event_id: u64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::GetEventSummary {
+            oracle_event: event_address(event_id),
+        }
+        .to_account_metas(None),
+        data: instruction::GetEventSummary {}.data(),
+    }
+}
+
+/// View; decode the return data as a `PlayerSummary`. Claimable amounts are
+/// counted over `player`'s bets on `event_ids`.
+pub fn get_player_summary(player: &Pubkey, event_ids: &[u64]) -> Instruction {
+    let mut metas = accounts::GetPlayerSummary {
+        player_profile: player_address(player),
+        config: config_address(),
+    }
+    .to_account_metas(None);
+    for &event_id in event_ids {
+        metas.push(AccountMeta::new_readonly(
+            bet_address(player, event_id),
+            false,
+        ));
+        metas.push(AccountMeta::new_readonly(event_address(event_id), false));
+    }
+    Instruction {
+        program_id: ID,
+        accounts: metas,
+        data: instruction::GetPlayerSummary {}.data(),
+    }
+}
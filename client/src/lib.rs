@@ -0,0 +1,12 @@
+//! Off-chain helpers for Oracle Quest clients.
+
+pub mod accounts;
+pub mod errors;
+pub mod instructions;
+pub mod metadata;
+pub mod pda;
+
+pub use accounts::{AccountSource, ClientError};
+pub use errors::{program_error, program_error_from_instruction};
+pub use metadata::{metadata_hash, EventMetadata, MetadataError, ResolutionSource};
+pub use simple_oracle_quest::{ErrorCode, ID as PROGRAM_ID};
@@ -0,0 +1,135 @@
+//! Off-chain event metadata referenced by `OracleEvent::metadata_uri`.
+//!
+//! The on-chain account only stores the URI and the sha256 of the JSON
+//! document, so clients fetch the document themselves and check it against
+//! `OracleEvent::metadata_hash` before showing it.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Schema version written by this crate.
+pub const METADATA_SCHEMA_VERSION: u32 = 1;
+/// Longest title shown in the quest log.
+pub const MAX_TITLE_LEN: usize = 120;
+/// URI schemes accepted for sources and artwork.
+pub const ALLOWED_URI_SCHEMES: [&str; 3] = ["https://", "ipfs://", "ar://"];
+
+#[derive(Debug, Error)]
+pub enum MetadataError {
+    #[error("metadata hash does not match the on-chain hash")]
+    HashMismatch,
+    #[error("metadata is not valid JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("unsupported metadata schema version {0}")]
+    UnsupportedVersion(u32),
+    #[error("metadata field `{0}` is missing or empty")]
+    MissingField(&'static str),
+    #[error("metadata title is longer than {MAX_TITLE_LEN} bytes")]
+    TitleTooLong,
+    #[error("metadata URI `{0}` must use https, ipfs or ar")]
+    InvalidUri(String),
+    #[error("event id {found} does not match on-chain event {expected}")]
+    EventMismatch { expected: u64, found: u64 },
+}
+
+/// Where the outcome of an event will be read from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolutionSource {
+    pub name: String,
+    pub url: String,
+}
+
+/// JSON document stored at an event's `metadata_uri`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventMetadata {
+    pub schema_version: u32,
+    pub event_id: u64,
+    pub title: String,
+    /// Long-form quest text, free of the on-chain description limit.
+    pub description: String,
+    pub rules: Vec<String>,
+    pub resolution_sources: Vec<ResolutionSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+/// sha256 of a metadata document, as stored in `OracleEvent::metadata_hash`.
+pub fn metadata_hash(json: &[u8]) -> [u8; 32] {
+    Sha256::digest(json).into()
+}
+
+impl EventMetadata {
+    /// Parses `json` fetched from an event's URI, checking it against the
+    /// on-chain hash before trusting any of its contents.
+    pub fn from_json_verified(
+        json: &[u8],
+        event_id: u64,
+        expected_hash: &[u8; 32],
+    ) -> Result<Self, MetadataError> {
+        if metadata_hash(json) != *expected_hash {
+            return Err(MetadataError::HashMismatch);
+        }
+        let metadata: Self = serde_json::from_slice(json)?;
+        if metadata.event_id != event_id {
+            return Err(MetadataError::EventMismatch {
+                expected: event_id,
+                found: metadata.event_id,
+            });
+        }
+        metadata.validate()?;
+        Ok(metadata)
+    }
+
+    /// Checks the document against the schema rules serde cannot express.
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.schema_version != METADATA_SCHEMA_VERSION {
+            return Err(MetadataError::UnsupportedVersion(self.schema_version));
+        }
+        if self.title.trim().is_empty() {
+            return Err(MetadataError::MissingField("title"));
+        }
+        if self.title.len() > MAX_TITLE_LEN {
+            return Err(MetadataError::TitleTooLong);
+        }
+        if self.description.trim().is_empty() {
+            return Err(MetadataError::MissingField("description"));
+        }
+        if self.rules.iter().all(|rule| rule.trim().is_empty()) {
+            return Err(MetadataError::MissingField("rules"));
+        }
+        if self.resolution_sources.is_empty() {
+            return Err(MetadataError::MissingField("resolution_sources"));
+        }
+        for source in &self.resolution_sources {
+            if source.name.trim().is_empty() {
+                return Err(MetadataError::MissingField("resolution_sources.name"));
+            }
+            check_uri(&source.url)?;
+        }
+        if let Some(image) = &self.image {
+            check_uri(image)?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the document and returns it with the hash to store on-chain.
+    pub fn to_json_with_hash(&self) -> Result<(Vec<u8>, [u8; 32]), MetadataError> {
+        self.validate()?;
+        let json = serde_json::to_vec(self)?;
+        let hash = metadata_hash(&json);
+        Ok((json, hash))
+    }
+}
+
+fn check_uri(uri: &str) -> Result<(), MetadataError> {
+    let valid = ALLOWED_URI_SCHEMES
+        .iter()
+        .any(|scheme| uri.len() > scheme.len() && uri.starts_with(scheme));
+    if valid {
+        Ok(())
+    } else {
+        Err(MetadataError::InvalidUri(uri.to_string()))
+    }
+}
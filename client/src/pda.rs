@@ -0,0 +1,35 @@
+//! Addresses of the program's accounts.
+//!
+//! The player, bet, event and config seeds are the program's own
+//! `interface` helpers, so they cannot drift from the on-chain derivation.
+
+use anchor_lang::prelude::Pubkey;
+use simple_oracle_quest::{EventCategory, ID};
+
+pub use simple_oracle_quest::interface::{
+    bet_address, config_address, event_address, player_address,
+};
+
+pub fn category_counter_address(category: EventCategory) -> Pubkey {
+    Pubkey::find_program_address(&[b"category", &[category as u8]], &ID).0
+}
+
+pub fn referral_address(referrer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"referral", referrer.as_ref()], &ID).0
+}
+
+pub fn season_address(season_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"season", &season_id.to_le_bytes()], &ID).0
+}
+
+pub fn season_score_address(season_id: u64, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"season_score", &season_id.to_le_bytes(), player.as_ref()],
+        &ID,
+    )
+    .0
+}
+
+pub fn session_address(owner: &Pubkey, session_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"session", owner.as_ref(), session_key.as_ref()], &ID).0
+}
@@ -0,0 +1,270 @@
+#![cfg(feature = "test-sbf")]
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use litesvm::LiteSVM;
+use oracle_quest_client::accounts::{
+    decode_return_data, fetch, fetch_bet, fetch_config, fetch_event, fetch_player,
+};
+use oracle_quest_client::{
+    instructions, pda, program_error_from_instruction, AccountSource, ClientError, ErrorCode,
+    PROGRAM_ID,
+};
+use simple_oracle_quest::{
+    BetPlaced, CategoryCounter, ConfigParams, EventCategory, EventParams, EventSummary,
+    PlayerSummary,
+};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const PROGRAM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../target/deploy/simple_oracle_quest.so"
+);
+
+/// Reads accounts straight from the test validator.
+struct SvmSource<'a>(&'a LiteSVM);
+
+impl AccountSource for SvmSource<'_> {
+    fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self
+            .0
+            .get_account(address)
+            .filter(|account| account.lamports > 0)
+            .map(|account| account.data))
+    }
+}
+
+fn transaction(svm: &LiteSVM, ix: Instruction, signer: &Keypair) -> Transaction {
+    Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    )
+}
+
+/// Sends `ix`, mapping a program failure back to its `ErrorCode`.
+fn try_send(svm: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> Result<Vec<u8>, ErrorCode> {
+    let result = svm.send_transaction(transaction(svm, ix, signer));
+    svm.expire_blockhash();
+    result
+        .map(|meta| meta.return_data.data)
+        .map_err(|failed| match failed.err {
+            TransactionError::InstructionError(_, error) => program_error_from_instruction(&error)
+                .unwrap_or_else(|| panic!("not a program error: {error:?}")),
+            error => panic!("transaction failed: {error:?}"),
+        })
+}
+
+fn send(svm: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> Vec<u8> {
+    try_send(svm, ix, signer).unwrap_or_else(|error| panic!("program error: {error}"))
+}
+
+fn simulate(svm: &LiteSVM, ix: Instruction, signer: &Keypair) -> Vec<u8> {
+    svm.simulate_transaction(transaction(svm, ix, signer))
+        .unwrap_or_else(|e| panic!("simulation failed: {:?}", e.meta.logs))
+        .meta
+        .return_data
+        .data
+}
+
+fn program_data_pda() -> Pubkey {
+    bpf_loader_upgradeable::get_program_data_address(&PROGRAM_ID)
+}
+
+/// Deploys the program under the upgradeable loader, as on a
+/// cluster, so `initialize_config` has an upgrade authority to check.
+fn deploy_upgradeable(svm: &mut LiteSVM) {
+    let elf = std::fs::read(PROGRAM_PATH).unwrap();
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let mut program_data = Account::new_data_with_space(
+        svm.minimum_balance_for_rent_exemption(metadata_len + elf.len()),
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(Pubkey::default()),
+        },
+        metadata_len + elf.len(),
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program_data.data[metadata_len..].copy_from_slice(&elf);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+
+    let mut program = Account::new_data(
+        svm.minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+        &UpgradeableLoaderState::Program {
+            programdata_address: program_data_pda(),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program.executable = true;
+    svm.set_account(PROGRAM_ID, program).unwrap();
+}
+
+/// Hands the program's upgrade authority to `authority`.
+fn set_upgrade_authority(svm: &mut LiteSVM, authority: &Pubkey) {
+    let mut program_data = svm.get_account(&program_data_pda()).unwrap();
+    let metadata = Account::new_data(
+        0,
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(*authority),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap()
+    .data;
+    program_data.data[..metadata.len()].copy_from_slice(&metadata);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+}
+
+fn funded_keypair(svm: &mut LiteSVM) -> Keypair {
+    let keypair = Keypair::new();
+    svm.airdrop(&keypair.pubkey(), 100_000_000_000).unwrap();
+    keypair
+}
+
+/// Sets up the config and the `General` category counter. There are no
+/// builders for admin setup, so these use the program's account structs.
+fn setup(svm: &mut LiteSVM, admin: &Keypair) {
+    set_upgrade_authority(svm, &admin.pubkey());
+    let ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: simple_oracle_quest::accounts::InitializeConfig {
+            config: pda::config_address(),
+            program: PROGRAM_ID,
+            program_data: program_data_pda(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeConfig {
+            params: ConfigParams {
+                treasury: admin.pubkey(),
+                cash_out_fee_bps: 100,
+                max_house_exposure: 1_000_000_000,
+                max_player_exposure: 10_000_000_000,
+                protocol_fee_bps: 200,
+                referral_share_bps: 2_500,
+                allow_explicit_event_ids: false,
+                crank_bounty: 0,
+            },
+        }
+        .data(),
+    };
+    send(svm, ix, admin);
+
+    let ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: simple_oracle_quest::accounts::InitializeCategoryCounter {
+            category_counter: pda::category_counter_address(EventCategory::General),
+            config: pda::config_address(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeCategoryCounter {
+            category: EventCategory::General,
+            start_seq: 0,
+        }
+        .data(),
+    };
+    send(svm, ix, admin);
+}
+
+#[test]
+fn test_client_drives_a_full_event_lifecycle() {
+    let mut svm = LiteSVM::new();
+    deploy_upgradeable(&mut svm);
+    let admin = funded_keypair(&mut svm);
+    let player = funded_keypair(&mut svm);
+    let rival = funded_keypair(&mut svm);
+    setup(&mut svm, &admin);
+    assert_eq!(
+        fetch_config(&SvmSource(&svm)).unwrap().admin,
+        admin.pubkey()
+    );
+
+    let counter = pda::category_counter_address(EventCategory::General);
+    let event_id = fetch::<CategoryCounter>(&SvmSource(&svm), &counter)
+        .unwrap()
+        .next_event_id()
+        .unwrap();
+    let params = EventParams {
+        category: EventCategory::General,
+        tags: [0; 4],
+        description: "Will the caravan arrive?".to_string(),
+        min_stake: 1_000,
+        max_stake: 1_000_000_000,
+        season_id: 0,
+    };
+    let ix = instructions::create_oracle_event(&admin.pubkey(), &admin.pubkey(), event_id, params);
+    send(&mut svm, ix, &admin);
+
+    for (wallet, outcome, amount) in [(&player, true, 10_000), (&rival, false, 30_000)] {
+        send(
+            &mut svm,
+            instructions::initialize_player(&wallet.pubkey(), None),
+            wallet,
+        );
+        let ix = instructions::place_bet(&wallet.pubkey(), event_id, outcome, amount);
+        let placed: BetPlaced = decode_return_data(&send(&mut svm, ix, wallet)).unwrap();
+        assert_eq!(placed.bet, pda::bet_address(&wallet.pubkey(), event_id));
+    }
+
+    let source = SvmSource(&svm);
+    assert_eq!(fetch_event(&source, event_id).unwrap().total_amount, 40_000);
+    assert_eq!(
+        fetch_bet(&source, &player.pubkey(), event_id)
+            .unwrap()
+            .amount,
+        10_000
+    );
+    let summary: EventSummary = decode_return_data(&simulate(
+        &svm,
+        instructions::get_event_summary(event_id),
+        &player,
+    ))
+    .unwrap();
+    assert_eq!(summary.yes_probability_bps, 2_500);
+
+    // Program failures come back as typed error codes
+    let ix = instructions::close_bet(&player.pubkey(), event_id);
+    assert!(matches!(
+        try_send(&mut svm, ix, &player),
+        Err(ErrorCode::BetNotSettled)
+    ));
+
+    send(
+        &mut svm,
+        instructions::resolve_event(&admin.pubkey(), event_id, true),
+        &admin,
+    );
+    let ix = instructions::get_player_summary(&player.pubkey(), &[event_id]);
+    let summary: PlayerSummary = decode_return_data(&simulate(&svm, ix, &player)).unwrap();
+    assert_eq!(summary.claimable, 40_000 - 600);
+
+    let ix = instructions::settle_bet(&player.pubkey(), event_id, 0, None, true);
+    send(&mut svm, ix, &player);
+    let source = SvmSource(&svm);
+    assert_eq!(fetch_player(&source, &player.pubkey()).unwrap().bets_won, 1);
+    assert!(matches!(
+        fetch_bet(&source, &player.pubkey(), event_id),
+        Err(ClientError::AccountNotFound(_))
+    ));
+
+    let ix = instructions::settle_bet(&rival.pubkey(), event_id, 0, None, false);
+    send(&mut svm, ix, &rival);
+    let ix = instructions::claim_winnings(&rival.pubkey(), event_id, 0, None);
+    assert!(matches!(
+        try_send(&mut svm, ix, &rival),
+        Err(ErrorCode::BetLost)
+    ));
+}
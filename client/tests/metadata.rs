@@ -0,0 +1,84 @@
+use oracle_quest_client::metadata::METADATA_SCHEMA_VERSION;
+use oracle_quest_client::{metadata_hash, EventMetadata, MetadataError, ResolutionSource};
+
+fn dragon_quest() -> EventMetadata {
+    EventMetadata {
+        schema_version: METADATA_SCHEMA_VERSION,
+        event_id: 1,
+        title: "Will the dragon be slain?".to_string(),
+        description: "The knights ride out at dawn.".to_string(),
+        rules: vec!["Resolves YES if the dragon is slain before the full moon.".to_string()],
+        resolution_sources: vec![ResolutionSource {
+            name: "Royal Herald".to_string(),
+            url: "https://herald.example/dragon".to_string(),
+        }],
+        image: Some("ipfs://bafydragon".to_string()),
+    }
+}
+
+#[test]
+fn test_metadata_round_trips_against_hash() {
+    let metadata = dragon_quest();
+    let (json, hash) = metadata.to_json_with_hash().unwrap();
+
+    assert_eq!(hash, metadata_hash(&json));
+    let parsed = EventMetadata::from_json_verified(&json, 1, &hash).unwrap();
+    assert_eq!(parsed, metadata);
+
+    println!("✅ Metadata round trip test passed");
+}
+
+#[test]
+fn test_metadata_rejects_tampered_documents() {
+    let (json, hash) = dragon_quest().to_json_with_hash().unwrap();
+    let tampered = String::from_utf8(json.clone())
+        .unwrap()
+        .replace("full moon", "new moon");
+
+    assert!(matches!(
+        EventMetadata::from_json_verified(tampered.as_bytes(), 1, &hash),
+        Err(MetadataError::HashMismatch)
+    ));
+    assert!(matches!(
+        EventMetadata::from_json_verified(&json, 2, &hash),
+        Err(MetadataError::EventMismatch {
+            expected: 2,
+            found: 1
+        })
+    ));
+
+    println!("✅ Metadata tamper test passed");
+}
+
+#[test]
+fn test_metadata_schema_validation() {
+    let mut metadata = dragon_quest();
+    metadata.resolution_sources[0].url = "http://herald.example".to_string();
+    assert!(matches!(
+        metadata.validate(),
+        Err(MetadataError::InvalidUri(_))
+    ));
+
+    let mut metadata = dragon_quest();
+    metadata.rules = vec![" ".to_string()];
+    assert!(matches!(
+        metadata.validate(),
+        Err(MetadataError::MissingField("rules"))
+    ));
+
+    let mut metadata = dragon_quest();
+    metadata.schema_version = 99;
+    assert!(matches!(
+        metadata.validate(),
+        Err(MetadataError::UnsupportedVersion(99))
+    ));
+
+    // Unknown fields are rejected rather than silently dropped
+    let json = br#"{"schema_version":1,"event_id":1,"title":"t","description":"d","rules":["r"],"resolution_sources":[],"extra":true}"#;
+    assert!(matches!(
+        EventMetadata::from_json_verified(json, 1, &metadata_hash(json)),
+        Err(MetadataError::InvalidJson(_))
+    ));
+
+    println!("✅ Metadata schema test passed");
+}
@@ -0,0 +1,133 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::error::InstructionError;
+use anchor_lang::{AccountSerialize, AnchorSerialize};
+use oracle_quest_client::accounts::{decode, decode_return_data, fetch_bet, fetch_player};
+use oracle_quest_client::errors::ERROR_CODES;
+use oracle_quest_client::{
+    instructions, pda, program_error, program_error_from_instruction, AccountSource, ClientError,
+    ErrorCode, PROGRAM_ID,
+};
+use simple_oracle_quest::{Bet, BetPlaced, OracleEvent, BET_VERSION};
+use std::collections::HashMap;
+
+struct MemorySource(HashMap<Pubkey, Vec<u8>>);
+
+impl AccountSource for MemorySource {
+    fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self.0.get(address).cloned())
+    }
+}
+
+#[test]
+fn test_error_codes_map_back_to_error_code() {
+    for (index, error) in ERROR_CODES.iter().enumerate() {
+        let code = u32::from(*error);
+        assert_eq!(code, 6_000 + index as u32);
+        assert_eq!(program_error(code).map(|e| e.name()), Some(error.name()));
+    }
+    assert!(program_error(5_999).is_none());
+    assert!(program_error(6_000 + ERROR_CODES.len() as u32).is_none());
+
+    let not_owner = u32::from(ErrorCode::NotBetOwner);
+    assert!(matches!(
+        program_error_from_instruction(&InstructionError::Custom(not_owner)),
+        Some(ErrorCode::NotBetOwner)
+    ));
+    assert!(program_error_from_instruction(&InstructionError::MissingRequiredSignature).is_none());
+
+    println!("✅ Error code mapping test passed");
+}
+
+#[test]
+fn test_instruction_builders_derive_program_addresses() {
+    let player = Pubkey::new_unique();
+    let ix = instructions::place_bet(&player, 7, true, 1_000);
+    assert_eq!(ix.program_id, PROGRAM_ID);
+    let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(
+        keys[..5],
+        [
+            pda::bet_address(&player, 7),
+            pda::player_address(&player),
+            pda::event_address(7),
+            pda::config_address(),
+            player,
+        ]
+    );
+    // No session: the optional slot is filled with the program id
+    assert_eq!(keys[5], PROGRAM_ID);
+    assert!(ix.accounts[6].is_signer);
+
+    let session_key = Pubkey::new_unique();
+    let ix = instructions::place_bet_with_session(&player, &session_key, 7, true, 1_000);
+    assert_eq!(
+        ix.accounts[5].pubkey,
+        pda::session_address(&player, &session_key)
+    );
+    assert_eq!(ix.accounts[6].pubkey, session_key);
+
+    // Season and referral accounts are only included when they apply
+    let referrer = Pubkey::new_unique();
+    let ix = instructions::settle_bet(&player, 7, 3, Some(referrer), true);
+    assert_eq!(ix.accounts[3].pubkey, pda::season_score_address(3, &player));
+    assert_eq!(ix.accounts[5].pubkey, pda::referral_address(&referrer));
+    assert_eq!(ix.accounts[9].pubkey, pda::season_address(3));
+    let ix = instructions::claim_winnings(&player, 7, 0, None);
+    assert_eq!(ix.accounts[3].pubkey, PROGRAM_ID);
+    assert_eq!(ix.accounts[5].pubkey, PROGRAM_ID);
+    assert_eq!(ix.accounts[9].pubkey, PROGRAM_ID);
+
+    let ix = instructions::get_player_summary(&player, &[1, 2]);
+    assert_eq!(ix.accounts.len(), 2 + 4);
+    assert_eq!(ix.accounts[4].pubkey, pda::bet_address(&player, 2));
+
+    println!("✅ Instruction builder test passed");
+}
+
+#[test]
+fn test_fetch_decodes_accounts_from_source() {
+    let player = Pubkey::new_unique();
+    let bet = Bet {
+        player,
+        event_id: 7,
+        chosen_outcome: true,
+        amount: 1_000,
+        claimed: false,
+        settled: false,
+        version: BET_VERSION,
+    };
+    let mut data = vec![];
+    bet.try_serialize(&mut data).unwrap();
+    let source = MemorySource(HashMap::from([(
+        pda::bet_address(&player, 7),
+        data.clone(),
+    )]));
+
+    assert_eq!(fetch_bet(&source, &player, 7).unwrap().amount, 1_000);
+    assert!(matches!(
+        fetch_bet(&source, &player, 8),
+        Err(ClientError::AccountNotFound(_))
+    ));
+    assert!(matches!(
+        fetch_player(&source, &player),
+        Err(ClientError::AccountNotFound(_))
+    ));
+    // The discriminator is checked
+    assert!(matches!(
+        decode::<OracleEvent>(&data),
+        Err(ClientError::Decode(_))
+    ));
+
+    let placed = BetPlaced {
+        bet: pda::bet_address(&player, 7),
+        event_id: 7,
+        total_bets: 1,
+        total_amount: 1_000,
+        yes_amount: 1_000,
+        no_amount: 0,
+    };
+    let decoded: BetPlaced = decode_return_data(&placed.try_to_vec().unwrap()).unwrap();
+    assert_eq!(decoded, placed);
+
+    println!("✅ Account fetch test passed");
+}
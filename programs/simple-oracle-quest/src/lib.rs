@@ -1,247 +1,4956 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use solana_sha256_hasher::hashv;
 
 declare_id!("9tSP8kXEUif9doAPhAbUKZm3qKNphpcHGKc35jLr1xEA");
 
+/// Basis-point denominator used for fees and probabilities.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+/// Highest swap fee a pool may charge (10%).
+pub const MAX_POOL_FEE_BPS: u16 = 1_000;
+/// Fixed-point scale for the per-LP-share fee accumulator.
+pub const FEE_PER_SHARE_SCALE: u128 = 1_000_000_000_000;
+/// Resting orders kept per side of an `OrderBook`.
+pub const ORDER_BOOK_DEPTH: usize = 32;
+/// Smallest order notional in lamports, so filling a side of the book ties
+/// up real stake.
+pub const MIN_ORDER_NOTIONAL: u64 = 1_000_000;
+/// Highest fee the protocol may take on a cash-out (20%).
+pub const MAX_CASH_OUT_FEE_BPS: u16 = 2_000;
+/// Most events a single parlay may combine.
+pub const MAX_PARLAY_LEGS: usize = 5;
+/// Odds shown for a side with no stake yet (2x).
+pub const DEFAULT_LEG_ODDS_BPS: u64 = 20_000;
+/// Upper bound on a single leg's odds (10x).
+pub const MAX_LEG_ODDS_BPS: u64 = 100_000;
+/// Highest fee the protocol may take on winnings (10%).
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+/// Current `PlayerProfile` layout version.
+pub const PLAYER_PROFILE_VERSION: u8 = 1;
+/// Current `Bet` layout version.
+pub const BET_VERSION: u8 = 1;
+/// Current `OracleEvent` layout version.
+pub const ORACLE_EVENT_VERSION: u8 = 1;
+/// Time winners have to claim before `close_event` may sweep their stake
+/// to the treasury (30 days).
+pub const EVENT_CLOSE_GRACE_PERIOD: i64 = 30 * 24 * 60 * 60;
+/// Tag slots on an `OracleEvent`. Tag `0` marks an empty slot.
+pub const MAX_EVENT_TAGS: usize = 4;
+/// Byte offsets of `OracleEvent` fields for `getProgramAccounts` memcmp
+/// filters, including the discriminator.
+pub const EVENT_CATEGORY_OFFSET: usize = 8 + 8;
+pub const EVENT_TAGS_OFFSET: usize = EVENT_CATEGORY_OFFSET + 1;
+pub const EVENT_RESOLVED_OFFSET: usize = EVENT_TAGS_OFFSET + MAX_EVENT_TAGS;
+pub const EVENT_AUTHORITY_OFFSET: usize = EVENT_RESOLVED_OFFSET + 2;
+/// Bits of an event id below the category byte.
+pub const CATEGORY_SEQ_BITS: u32 = 56;
+/// XP for placing a bet.
+pub const XP_PER_BET: u64 = 10;
+/// XP for a winning bet, before the streak bonus.
+pub const XP_PER_WIN: u64 = 50;
+/// XP for settling a losing bet.
+pub const XP_PER_LOSS: u64 = 5;
+/// Extra XP per consecutive win, counted up to `MAX_STREAK_BONUS_WINS`.
+pub const XP_STREAK_BONUS: u64 = 10;
+pub const MAX_STREAK_BONUS_WINS: u64 = 10;
+/// Level `n` is reached at `XP_LEVEL_BASE * n^2` XP.
+pub const XP_LEVEL_BASE: u64 = 100;
+/// Longest `OracleEvent` description, in bytes.
+pub const MAX_DESCRIPTION_LEN: usize = 200;
+/// Longest `OracleEvent` metadata URI, in bytes.
+pub const MAX_METADATA_URI_LEN: usize = 200;
+/// Deepest Merkle proof accepted by `claim_season_prize`.
+pub const MAX_MERKLE_PROOF_LEN: usize = 24;
+/// `SessionToken` scope letting the key sign `place_bet`.
+pub const SESSION_SCOPE_PLACE_BET: u8 = 1 << 0;
+/// `SessionToken` scope letting the key claim and settle bets.
+pub const SESSION_SCOPE_CLAIM: u8 = 1 << 1;
+/// Longest a session key may stay valid (7 days).
+pub const MAX_SESSION_DURATION: i64 = 7 * 24 * 60 * 60;
+/// Most members, founder included, a `Guild` may hold.
+pub const MAX_GUILD_MEMBERS: usize = 16;
+
 #[program]
 pub mod simple_oracle_quest {
     use super::*;
 
-    pub fn initialize_player(ctx: Context<InitializePlayer>) -> Result<()> {
+    pub fn initialize_player(
+        ctx: Context<InitializePlayer>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let owner = ctx.accounts.signer.key();
+        if let Some(referrer) = referrer {
+            require_keys_neq!(referrer, owner, ErrorCode::SelfReferral);
+            // Rewards accounts can only be registered by existing players and
+            // the referrer is fixed at creation, so no referral cycle can form.
+            let rewards = ctx
+                .accounts
+                .referrer_rewards
+                .as_mut()
+                .ok_or(ErrorCode::ReferrerNotRegistered)?;
+            require_keys_eq!(rewards.referrer, referrer, ErrorCode::ReferrerNotRegistered);
+            rewards.referred_players = rewards
+                .referred_players
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
         let player = &mut ctx.accounts.player_profile;
-        player.owner = ctx.accounts.signer.key();
+        player.owner = owner;
         player.balance = 0;
         player.total_bets = 0;
         player.bets_won = 0;
+        player.open_exposure = 0;
+        player.version = PLAYER_PROFILE_VERSION;
+        player.referrer = referrer;
         msg!("Player initialized: {}", player.owner);
         Ok(())
     }
 
+    /// Stakes `amount` on `chosen_outcome`. The bet address and the event's
+    /// new totals are returned through `set_return_data` as a `BetPlaced`.
     pub fn place_bet(
         ctx: Context<PlaceBet>,
         event_id: u64,
         chosen_outcome: bool,
         amount: u64,
-    ) -> Result<()> {
+    ) -> Result<BetPlaced> {
+        authorize_player(
+            &ctx.accounts.player,
+            &ctx.accounts.signer,
+            ctx.accounts.session_token.as_deref(),
+            SESSION_SCOPE_PLACE_BET,
+        )?;
+        ctx.accounts.oracle_event.check_stake(amount)?;
+        ctx.accounts
+            .player_profile
+            .open_exposure(amount, ctx.accounts.config.max_player_exposure)?;
+
+        match ctx.accounts.session_token.as_mut() {
+            // Session keys stake from the budget the owner escrowed
+            Some(session) if session.session_key == ctx.accounts.signer.key() => {
+                session.spend(amount)?;
+                withdraw_lamports(
+                    &session.to_account_info(),
+                    &ctx.accounts.oracle_event.to_account_info(),
+                    amount,
+                )?;
+            }
+            _ => deposit_lamports(
+                &ctx.accounts.signer,
+                ctx.accounts.oracle_event.to_account_info(),
+                &ctx.accounts.system_program,
+                amount,
+            )?,
+        }
+
         let bet = &mut ctx.accounts.bet;
-        bet.player = ctx.accounts.signer.key();
+        bet.player = ctx.accounts.player.key();
         bet.event_id = event_id;
         bet.chosen_outcome = chosen_outcome;
         bet.amount = amount;
         bet.claimed = false;
+        bet.version = BET_VERSION;
 
         let player = &mut ctx.accounts.player_profile;
-        player.total_bets += 1;
+        player.total_bets = player
+            .total_bets
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        player.add_xp(XP_PER_BET)?;
 
         let oracle_event = &mut ctx.accounts.oracle_event;
-        oracle_event.total_bets += 1;
-        oracle_event.total_amount += amount;
-        if chosen_outcome {
-            oracle_event.yes_votes += 1;
-        } else {
-            oracle_event.no_votes += 1;
-        }
+        oracle_event.record_bet(chosen_outcome, amount)?;
 
         msg!(
             "Bet placed: {} lamports on {}",
             amount,
             if chosen_outcome { "YES" } else { "NO" }
         );
+        Ok(BetPlaced {
+            bet: ctx.accounts.bet.key(),
+            event_id,
+            total_bets: oracle_event.total_bets,
+            total_amount: oracle_event.total_amount,
+            yes_amount: oracle_event.yes_amount,
+            no_amount: oracle_event.no_amount,
+        })
+    }
+
+    /// Opens the id allocator for `category`. Ids below `start_seq` are left
+    /// to `create_oracle_event_with_id` for events created before allocation.
+    pub fn initialize_category_counter(
+        ctx: Context<InitializeCategoryCounter>,
+        category: EventCategory,
+        start_seq: u64,
+    ) -> Result<()> {
+        category_event_id(category, start_seq)?;
+
+        let counter = &mut ctx.accounts.category_counter;
+        counter.category = category;
+        counter.start_seq = start_seq;
+        counter.next_seq = start_seq;
+        counter.bump = ctx.bumps.category_counter;
+
+        msg!(
+            "Category counter initialized: {:?} from {}",
+            category,
+            start_seq
+        );
         Ok(())
     }
 
-    pub fn create_oracle_event(
-        ctx: Context<CreateOracleEvent>,
+    /// Creates an event under the next id of its category's counter.
+    pub fn create_oracle_event(ctx: Context<CreateOracleEvent>, params: EventParams) -> Result<()> {
+        let event_id = ctx.accounts.category_counter.allocate()?;
+        init_event(
+            &mut ctx.accounts.oracle_event,
+            event_id,
+            ctx.accounts.authority.key(),
+            ctx.accounts.season.as_deref(),
+            params,
+        )
+    }
+
+    /// Legacy path for admins to create an event under an explicit id, e.g.
+    /// to recreate one that predates the allocator. Only ids the category's
+    /// counter will never hand out are accepted.
+    pub fn create_oracle_event_with_id(
+        ctx: Context<CreateOracleEventWithId>,
         event_id: u64,
-        description: String,
+        params: EventParams,
     ) -> Result<()> {
-        require!(description.len() <= 200, ErrorCode::DescriptionTooLong);
+        require!(
+            ctx.accounts.config.allow_explicit_event_ids,
+            ErrorCode::ExplicitEventIdsDisabled
+        );
+        ctx.accounts.category_counter.check_explicit(event_id)?;
+        init_event(
+            &mut ctx.accounts.oracle_event,
+            event_id,
+            ctx.accounts.admin.key(),
+            ctx.accounts.season.as_deref(),
+            params,
+        )
+    }
 
+    /// Rewrites the description of an event nobody has bet on yet, resizing
+    /// the account to fit.
+    pub fn update_event_description(ctx: Context<UpdateEvent>, description: String) -> Result<()> {
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            ErrorCode::DescriptionTooLong
+        );
         let event = &mut ctx.accounts.oracle_event;
-        event.event_id = event_id;
+        require!(!event.resolved, ErrorCode::AlreadyResolved);
+        require!(event.total_bets == 0, ErrorCode::EventHasBets);
+
+        resize_account(
+            &event.to_account_info(),
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            OracleEvent::space(&description, &event.metadata_uri),
+        )?;
         event.description = description;
-        event.resolved = false;
-        event.authority = ctx.accounts.authority.key();
-        event.total_bets = 0;
-        event.yes_votes = 0;
-        event.no_votes = 0;
-        event.total_amount = 0;
 
-        msg!("Event created: {}", event.description);
+        msg!("Event {} description updated", event.event_id);
+        Ok(())
+    }
+
+    /// Points an event nobody has bet on yet at an off-chain JSON document
+    /// and records the document's sha256 so clients can verify it.
+    pub fn update_event_metadata(
+        ctx: Context<UpdateEvent>,
+        metadata_uri: String,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            metadata_uri.len() <= MAX_METADATA_URI_LEN,
+            ErrorCode::MetadataUriTooLong
+        );
+        let event = &mut ctx.accounts.oracle_event;
+        require!(!event.resolved, ErrorCode::AlreadyResolved);
+        require!(event.total_bets == 0, ErrorCode::EventHasBets);
+
+        resize_account(
+            &event.to_account_info(),
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            OracleEvent::space(&event.description, &metadata_uri),
+        )?;
+        event.metadata_uri = metadata_uri;
+        event.metadata_hash = metadata_hash;
+
+        msg!("Event {} metadata: {}", event.event_id, event.metadata_uri);
         Ok(())
     }
 
     pub fn resolve_event(ctx: Context<ResolveEvent>, outcome: bool) -> Result<()> {
         let event = &mut ctx.accounts.oracle_event;
         require!(!event.resolved, ErrorCode::AlreadyResolved);
+        require!(!event.cancelled, ErrorCode::EventCancelled);
 
         event.resolved = true;
         event.outcome = Some(outcome);
+        event.resolved_at = Clock::get()?.unix_timestamp;
+        // Only winning bets are owed anything from the pot
+        event.outstanding_claims = if outcome {
+            event.yes_votes
+        } else {
+            event.no_votes
+        };
         msg!("Event resolved: {}", if outcome { "YES" } else { "NO" });
         Ok(())
     }
 
     pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
-        let bet = &mut ctx.accounts.bet;
-        let event = &ctx.accounts.oracle_event;
+        authorize_claim(ctx.accounts)?;
+        pay_winnings(ctx.accounts)
+    }
 
-        require!(event.resolved, ErrorCode::EventNotResolved);
-        require!(!bet.claimed, ErrorCode::AlreadyClaimed);
+    pub fn settle_losing_bet(ctx: Context<ClaimWinnings>) -> Result<()> {
+        authorize_claim(ctx.accounts)?;
+        settle_loss(ctx.accounts)
+    }
+
+    /// Authorizes `session_key` to sign `scopes` for the owner until
+    /// `expires_at`. `max_stake` is escrowed in the session account as the
+    /// key's whole betting budget, and `fee_budget` is sent to the key so it
+    /// can pay transaction fees without wallet approvals.
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        scopes: u8,
+        max_stake: u64,
+        expires_at: i64,
+        fee_budget: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
         require!(
-            bet.chosen_outcome == event.outcome.unwrap(),
-            ErrorCode::BetLost
+            scopes != 0 && scopes & !(SESSION_SCOPE_PLACE_BET | SESSION_SCOPE_CLAIM) == 0,
+            ErrorCode::InvalidSession
         );
         require!(
-            bet.player == ctx.accounts.signer.key(),
-            ErrorCode::NotBetOwner
+            expires_at > now && expires_at <= now.saturating_add(MAX_SESSION_DURATION),
+            ErrorCode::InvalidSession
+        );
+        require_keys_neq!(
+            session_key,
+            ctx.accounts.owner.key(),
+            ErrorCode::InvalidSession
+        );
+
+        deposit_lamports(
+            &ctx.accounts.owner,
+            ctx.accounts.session_token.to_account_info(),
+            &ctx.accounts.system_program,
+            max_stake,
+        )?;
+        deposit_lamports(
+            &ctx.accounts.owner,
+            ctx.accounts.session_signer.to_account_info(),
+            &ctx.accounts.system_program,
+            fee_budget,
+        )?;
+
+        let session = &mut ctx.accounts.session_token;
+        session.owner = ctx.accounts.owner.key();
+        session.session_key = session_key;
+        session.scopes = scopes;
+        session.max_stake = max_stake;
+        session.staked = 0;
+        session.expires_at = expires_at;
+        session.bump = ctx.bumps.session_token;
+
+        msg!(
+            "Session {} authorized until {} for {} lamports",
+            session_key,
+            expires_at,
+            max_stake
         );
+        Ok(())
+    }
+
+    /// Ends a session at any time, returning the unspent budget and rent to
+    /// the owner.
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        msg!("Session {} revoked", ctx.accounts.session_token.session_key);
+        Ok(())
+    }
 
-        bet.claimed = true;
+    /// Claims every winning bet among the `(Bet, OracleEvent)` pairs passed
+    /// through `remaining_accounts`, writing the profile, fees and referral
+    /// rewards once. Bets that are not claimable winners, or that belong to
+    /// a season (whose score needs `claim_winnings`), are skipped and
+    /// reported as such in the emitted `BetsClaimed`.
+    pub fn claim_many<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+        let pairs = ctx.remaining_accounts;
+        require!(
+            !pairs.is_empty() && pairs.len().is_multiple_of(2),
+            ErrorCode::InvalidClaimAccounts
+        );
 
-        let winnings = bet.amount * 2;
+        let signer = ctx.accounts.signer.key();
+        let signer_info = ctx.accounts.signer.to_account_info();
+        let config = &mut ctx.accounts.config;
         let player = &mut ctx.accounts.player_profile;
-        player.bets_won += 1;
-        player.balance += winnings;
+        let mut rewards = match player.referrer {
+            Some(referrer) => {
+                let rewards = ctx
+                    .accounts
+                    .referrer_rewards
+                    .as_mut()
+                    .ok_or(ErrorCode::ReferrerNotRegistered)?;
+                require_keys_eq!(rewards.referrer, referrer, ErrorCode::ReferrerNotRegistered);
+                Some(rewards)
+            }
+            None => None,
+        };
+
+        let mut results = Vec::with_capacity(pairs.len() / 2);
+        let mut total_winnings = 0u64;
+        let mut total_fees = 0u64;
+        let mut total_referral = 0u64;
+        let mut released = 0u64;
+        for pair in pairs.chunks_exact(2) {
+            let (bet_info, event_info) = (&pair[0], &pair[1]);
+            let mut bet = Account::<Bet>::try_from(bet_info)?;
+            let event_seed = bet.event_id.to_le_bytes();
+            let (expected_bet, _) = Pubkey::find_program_address(
+                &[b"bet", signer.as_ref(), event_seed.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                bet_info.key(),
+                expected_bet,
+                ErrorCode::InvalidClaimAccounts
+            );
+            let (expected_event, _) =
+                Pubkey::find_program_address(&[b"event", event_seed.as_ref()], &crate::ID);
+            require_keys_eq!(
+                event_info.key(),
+                expected_event,
+                ErrorCode::InvalidClaimAccounts
+            );
+            let mut event = Account::<OracleEvent>::try_from(event_info)?;
+
+            let claimable = event.resolved
+                && !bet.claimed
+                && event.outcome == Some(bet.chosen_outcome)
+                && event.season_id == 0;
+            if !claimable {
+                results.push(ClaimResult {
+                    event_id: bet.event_id,
+                    claimed: false,
+                    winnings: 0,
+                });
+                continue;
+            }
+
+            let (winnings, fee, referral_cut) =
+                winning_payout(&event, bet.amount, config, rewards.is_some())?;
+            withdraw_lamports(event_info, &signer_info, winnings)?;
+            withdraw_lamports(event_info, &config.to_account_info(), fee)?;
+            if let Some(rewards) = rewards.as_ref() {
+                withdraw_lamports(event_info, &rewards.to_account_info(), referral_cut)?;
+            }
+
+            bet.claimed = true;
+            bet.settled = true;
+            event.outstanding_claims = event.outstanding_claims.saturating_sub(1);
+            bet.exit(&crate::ID)?;
+            event.exit(&crate::ID)?;
+
+            player.record_win()?;
+            released = released.saturating_add(bet.amount);
+            total_winnings = total_winnings
+                .checked_add(winnings)
+                .ok_or(ErrorCode::MathOverflow)?;
+            total_fees = total_fees.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+            total_referral = total_referral
+                .checked_add(referral_cut)
+                .ok_or(ErrorCode::MathOverflow)?;
+            results.push(ClaimResult {
+                event_id: bet.event_id,
+                claimed: true,
+                winnings,
+            });
+        }
+
+        player.release_exposure(released);
+        player.balance = player
+            .balance
+            .checked_add(total_winnings)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.fees_accrued = config
+            .fees_accrued
+            .checked_add(total_fees)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if let Some(rewards) = rewards.as_mut() {
+            rewards.credit(total_referral)?;
+        }
 
-        msg!("Winnings claimed: {} lamports", winnings);
+        msg!(
+            "Claimed {} lamports across {} bets",
+            total_winnings,
+            results.len()
+        );
+        emit!(BetsClaimed {
+            player: signer,
+            total_winnings,
+            results,
+        });
         Ok(())
     }
 
-    pub fn close_bet(_ctx: Context<CloseBet>) -> Result<()> {
+    /// Permissionless payout of a finished event. Each bet is passed through
+    /// `remaining_accounts` as `[bet, owner wallet, owner profile, referrer
+    /// rewards]`, with the program id in place of the rewards account for
+    /// players without a referrer. Unsettled bets are paid, refunded or
+    /// recorded as lost exactly as their owner would, then every bet is
+    /// closed to its owner. The cranker earns `crank_bounty` per bet settled
+    /// in this call, capped by the fees those settlements collected. Season
+    /// scoring is best-effort, as with claims: wins settled here on a
+    /// seasonal event are paid but not scored, since the players' season
+    /// accounts are not passed.
+    pub fn crank_payouts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrankPayouts<'info>>,
+        event_id: u64,
+    ) -> Result<()> {
+        let groups = ctx.remaining_accounts;
+        require!(
+            !groups.is_empty() && groups.len().is_multiple_of(4),
+            ErrorCode::InvalidCrankAccounts
+        );
+        let event = &mut ctx.accounts.oracle_event;
+        require!(
+            event.resolved || event.cancelled,
+            ErrorCode::EventNotResolved
+        );
+        let config = &mut ctx.accounts.config;
+
+        let mut closed = 0u64;
+        let mut settled = 0u64;
+        let mut fees_collected = 0u64;
+        for group in groups.chunks_exact(4) {
+            let (bet_info, owner_info, profile_info, rewards_info) =
+                (&group[0], &group[1], &group[2], &group[3]);
+            // Already closed earlier in this crank
+            if bet_info.data_is_empty() {
+                continue;
+            }
+
+            let mut bet = Account::<Bet>::try_from(bet_info)?;
+            let (expected_bet, _) = Pubkey::find_program_address(
+                &[b"bet", bet.player.as_ref(), event_id.to_le_bytes().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                bet_info.key(),
+                expected_bet,
+                ErrorCode::InvalidCrankAccounts
+            );
+            require_keys_eq!(
+                owner_info.key(),
+                bet.player,
+                ErrorCode::InvalidCrankAccounts
+            );
+
+            if !bet.settled {
+                let (expected_profile, _) =
+                    Pubkey::find_program_address(&[b"player", bet.player.as_ref()], &crate::ID);
+                require_keys_eq!(
+                    profile_info.key(),
+                    expected_profile,
+                    ErrorCode::InvalidCrankAccounts
+                );
+                let mut profile = Account::<PlayerProfile>::try_from(profile_info)?;
+
+                if event.cancelled {
+                    refund_stake(&mut bet, event, &mut profile, owner_info)?;
+                } else if event.outcome == Some(bet.chosen_outcome) {
+                    let referrer = profile.referrer;
+                    let (winnings, fee, referral_cut) =
+                        winning_payout(event, bet.amount, config, referrer.is_some())?;
+                    let event_info = event.to_account_info();
+                    withdraw_lamports(&event_info, owner_info, winnings)?;
+                    withdraw_lamports(&event_info, &config.to_account_info(), fee)?;
+                    config.fees_accrued = config
+                        .fees_accrued
+                        .checked_add(fee)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                    fees_collected = fees_collected
+                        .checked_add(fee)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                    if let Some(referrer) = referrer {
+                        let mut rewards = Account::<ReferrerRewards>::try_from(rewards_info)?;
+                        require_keys_eq!(
+                            rewards.referrer,
+                            referrer,
+                            ErrorCode::ReferrerNotRegistered
+                        );
+                        withdraw_lamports(&event_info, rewards_info, referral_cut)?;
+                        rewards.credit(referral_cut)?;
+                        rewards.exit(&crate::ID)?;
+                    }
+
+                    bet.claimed = true;
+                    event.outstanding_claims = event.outstanding_claims.saturating_sub(1);
+                    profile.release_exposure(bet.amount);
+                    profile.record_win()?;
+                    profile.balance = profile
+                        .balance
+                        .checked_add(winnings)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                } else {
+                    profile.release_exposure(bet.amount);
+                    profile.record_loss()?;
+                }
+                bet.settled = true;
+                profile.exit(&crate::ID)?;
+                settled += 1;
+            }
+
+            bet.close(owner_info.clone())?;
+            closed += 1;
+        }
+
+        let bounty = config
+            .crank_bounty
+            .saturating_mul(settled)
+            .min(fees_collected);
+        config.fees_accrued -= bounty;
+        withdraw_lamports(
+            &config.to_account_info(),
+            &ctx.accounts.cranker.to_account_info(),
+            bounty,
+        )?;
+
+        msg!(
+            "Cranked event {}: {} bets settled, {} closed, {} lamports bounty",
+            event_id,
+            settled,
+            closed,
+            bounty
+        );
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializePlayer<'info> {
-    #[account(
-        init,
-        payer = signer,
-        space = 8 + 32 + 8 + 8 + 8,
-        seeds = [b"player", signer.key().as_ref()],
-        bump
-    )]
-    pub player_profile: Account<'info, PlayerProfile>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Settles a bet whatever its result: pays out a win, records a loss on
+    /// the profile or refunds the stake of a cancelled event. With
+    /// `close_bet` set, the bet's rent is returned in the same call.
+    pub fn settle_bet(ctx: Context<ClaimWinnings>, close_bet: bool) -> Result<()> {
+        authorize_claim(ctx.accounts)?;
+        let accounts = &mut *ctx.accounts;
+        if accounts.oracle_event.cancelled {
+            refund_stake(
+                &mut accounts.bet,
+                &mut accounts.oracle_event,
+                &mut accounts.player_profile,
+                &accounts.player,
+            )?;
+        } else if accounts.oracle_event.outcome == Some(accounts.bet.chosen_outcome) {
+            pay_winnings(accounts)?;
+        } else {
+            settle_loss(accounts)?;
+        }
 
-#[derive(Accounts)]
-#[instruction(event_id: u64)]
-pub struct PlaceBet<'info> {
-    #[account(
-        init,
-        payer = signer,
-        space = 8 + PlayerProfile::INIT_SPACE,
-        seeds = [b"bet", signer.key().as_ref(), event_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub bet: Account<'info, Bet>,
-    #[account(
-        mut,
-        seeds = [b"player", signer.key().as_ref()],
-        bump
-    )]
-    pub player_profile: Account<'info, PlayerProfile>,
-    #[account(
-        mut,
-        seeds = [b"event", event_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub oracle_event: Account<'info, OracleEvent>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        if close_bet {
+            accounts.bet.close(accounts.player.to_account_info())?;
+        }
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(event_id: u64)]
-pub struct CreateOracleEvent<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + OracleEvent::INIT_SPACE,
-        seeds = [b"event", event_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub oracle_event: Account<'info, OracleEvent>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Founds a guild with the signer as its owner and an empty bankroll.
+    pub fn create_guild(ctx: Context<CreateGuild>, guild_id: u64) -> Result<()> {
+        let guild = &mut ctx.accounts.guild;
+        guild.founder = ctx.accounts.founder.key();
+        guild.guild_id = guild_id;
+        guild.members = vec![GuildMember {
+            member: guild.founder,
+            role: GuildRole::Owner,
+            shares: 0,
+        }];
+        guild.total_shares = 0;
+        guild.bankroll = 0;
+        guild.open_stake = 0;
+        guild.bump = ctx.bumps.guild;
 
-#[derive(Accounts)]
-pub struct ResolveEvent<'info> {
-    #[account(
-        mut,
-        has_one = authority,
-    )]
-    pub oracle_event: Account<'info, OracleEvent>,
-    pub authority: Signer<'info>,
-}
+        msg!("Guild {} founded by {}", guild_id, guild.founder);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
-    #[account(
-        mut,
-        seeds = [b"bet", signer.key().as_ref(), bet.event_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub bet: Account<'info, Bet>,
-    #[account(
-        seeds = [b"event", bet.event_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub oracle_event: Account<'info, OracleEvent>,
-    #[account(
-        mut,
-        seeds = [b"player", signer.key().as_ref()],
-        bump
-    )]
-    pub player_profile: Account<'info, PlayerProfile>,
-    pub signer: Signer<'info>,
-}
+    /// Adds `member` to the guild, or changes their role. Only the owner may
+    /// call this, and ownership itself cannot be granted.
+    pub fn set_guild_member(
+        ctx: Context<ManageGuild>,
+        member: Pubkey,
+        role: GuildRole,
+    ) -> Result<()> {
+        let guild = &mut ctx.accounts.guild;
+        guild.require_role(&ctx.accounts.signer.key(), GuildRole::Owner)?;
+        require!(role != GuildRole::Owner, ErrorCode::InvalidGuildRole);
+        require_keys_neq!(member, guild.founder, ErrorCode::InvalidGuildRole);
 
-#[derive(Accounts)]
-pub struct CloseBet<'info> {
-    #[account(mut, close = signer)]
-    pub bet: Account<'info, Bet>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-}
+        match guild.member_mut(&member) {
+            Some(existing) => existing.role = role,
+            None => {
+                require!(
+                    guild.members.len() < MAX_GUILD_MEMBERS,
+                    ErrorCode::GuildFull
+                );
+                guild.members.push(GuildMember {
+                    member,
+                    role,
+                    shares: 0,
+                });
+            }
+        }
 
-#[account]
-#[derive(InitSpace)]
-pub struct PlayerProfile {
-    pub owner: Pubkey,
-    pub balance: u64,
-    pub total_bets: u64,
-    pub bets_won: u64,
-}
+        msg!("Guild member {} set to {:?}", member, role);
+        Ok(())
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct Bet {
-    pub player: Pubkey,
-    pub event_id: u64,
-    pub chosen_outcome: bool,
-    pub amount: u64,
-    pub claimed: bool,
-}
+    /// Adds `amount` to the guild bankroll in exchange for shares priced at
+    /// the guild's current net asset value. Closed while guild bets are open,
+    /// as their stake is still valued at cost.
+    pub fn guild_deposit(ctx: Context<ManageGuild>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(ctx.accounts.guild.open_stake == 0, ErrorCode::GuildBetsOpen);
+        let signer = ctx.accounts.signer.key();
+        let shares = ctx.accounts.guild.shares_for_deposit(amount)?;
+        require!(shares > 0, ErrorCode::InvalidAmount);
 
-#[account]
-#[derive(InitSpace)]
-pub struct OracleEvent {
-    pub event_id: u64,
-    #[max_len(200)]
-    pub description: String,
-    pub resolved: bool,
-    pub outcome: Option<bool>,
-    pub authority: Pubkey,
-    pub total_bets: u64,
-    pub yes_votes: u64,
-    pub no_votes: u64,
-    pub total_amount: u64,
+        deposit_lamports(
+            &ctx.accounts.signer,
+            ctx.accounts.guild.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+
+        let guild = &mut ctx.accounts.guild;
+        let member = guild.member_mut(&signer).ok_or(ErrorCode::NotGuildMember)?;
+        member.shares = member
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        guild.total_shares = guild
+            .total_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        guild.bankroll = guild
+            .bankroll
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Guild deposit: {} lamports for {} shares", amount, shares);
+        Ok(())
+    }
+
+    /// Burns `shares` for their proportional cut of the guild's net asset
+    /// value. Closed while guild bets are open, so members cannot exit at
+    /// cost ahead of a known loss.
+    pub fn guild_withdraw(ctx: Context<ManageGuild>, shares: u64) -> Result<()> {
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        let signer = ctx.accounts.signer.key();
+        let guild = &mut ctx.accounts.guild;
+        require!(guild.open_stake == 0, ErrorCode::GuildBetsOpen);
+        let amount = guild.withdrawal_value(shares)?;
+        require!(amount <= guild.bankroll, ErrorCode::InsufficientLiquidity);
+
+        let member = guild.member_mut(&signer).ok_or(ErrorCode::NotGuildMember)?;
+        member.shares = member
+            .shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::InsufficientShares)?;
+        guild.total_shares -= shares;
+        guild.bankroll -= amount;
+
+        withdraw_lamports(
+            &guild.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            amount,
+        )?;
+
+        msg!(
+            "Guild withdrawal: {} shares for {} lamports",
+            shares,
+            amount
+        );
+        Ok(())
+    }
+
+    /// Stakes `amount` of the guild bankroll on an event. The guild is
+    /// recorded as the bet's player; a manager signs and pays the bet's rent.
+    pub fn guild_place_bet(
+        ctx: Context<GuildPlaceBet>,
+        event_id: u64,
+        chosen_outcome: bool,
+        amount: u64,
+    ) -> Result<()> {
+        let guild = &mut ctx.accounts.guild;
+        guild.require_role(&ctx.accounts.manager.key(), GuildRole::Manager)?;
+        // Guild bets could never be settled into a season score
+        require!(
+            ctx.accounts.oracle_event.season_id == 0,
+            ErrorCode::SeasonAccountRequired
+        );
+        ctx.accounts.oracle_event.check_stake(amount)?;
+
+        let open_stake = guild
+            .open_stake
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            open_stake <= ctx.accounts.config.max_player_exposure,
+            ErrorCode::ExposureLimitExceeded
+        );
+        guild.bankroll = guild
+            .bankroll
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        guild.open_stake = open_stake;
+        withdraw_lamports(
+            &guild.to_account_info(),
+            &ctx.accounts.oracle_event.to_account_info(),
+            amount,
+        )?;
+
+        let bet = &mut ctx.accounts.bet;
+        bet.player = guild.key();
+        bet.event_id = event_id;
+        bet.chosen_outcome = chosen_outcome;
+        bet.amount = amount;
+        bet.claimed = false;
+        bet.version = BET_VERSION;
+
+        ctx.accounts
+            .oracle_event
+            .record_bet(chosen_outcome, amount)?;
+
+        msg!(
+            "Guild bet placed: {} lamports on {}",
+            amount,
+            if chosen_outcome { "YES" } else { "NO" }
+        );
+        Ok(())
+    }
+
+    /// Settles a guild bet into the guild bankroll: winnings (less the
+    /// protocol fee) or a cancelled event's refund are paid to the guild, a
+    /// loss just releases the stake. Once the event has been closed nothing
+    /// is left to pay, so the stake is released with nothing returned. The
+    /// bet's rent goes to the manager.
+    pub fn guild_settle_bet(ctx: Context<GuildSettleBet>) -> Result<()> {
+        let guild = &mut ctx.accounts.guild;
+        guild.require_role(&ctx.accounts.manager.key(), GuildRole::Manager)?;
+        let bet = &ctx.accounts.bet;
+        let event_info = ctx.accounts.oracle_event.to_account_info();
+
+        let returned = if event_info.data_is_empty() {
+            0
+        } else {
+            let mut event = OracleEvent::try_deserialize(&mut &event_info.try_borrow_data()?[..])?;
+            require!(event.season_id == 0, ErrorCode::SeasonAccountRequired);
+            let returned = if event.cancelled {
+                event.outstanding_claims = event.outstanding_claims.saturating_sub(1);
+                bet.amount
+            } else {
+                require!(event.resolved, ErrorCode::EventNotResolved);
+                if event.outcome == Some(bet.chosen_outcome) {
+                    let config = &mut ctx.accounts.config;
+                    let (winnings, fee, _) = winning_payout(&event, bet.amount, config, false)?;
+                    withdraw_lamports(&event_info, &config.to_account_info(), fee)?;
+                    config.fees_accrued = config
+                        .fees_accrued
+                        .checked_add(fee)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                    event.outstanding_claims = event.outstanding_claims.saturating_sub(1);
+                    winnings
+                } else {
+                    0
+                }
+            };
+            event.try_serialize(&mut &mut event_info.try_borrow_mut_data()?[..])?;
+            withdraw_lamports(&event_info, &guild.to_account_info(), returned)?;
+            returned
+        };
+
+        guild.open_stake = guild.open_stake.saturating_sub(bet.amount);
+        guild.bankroll = guild
+            .bankroll
+            .checked_add(returned)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Guild bet settled: {} lamports returned", returned);
+        Ok(())
+    }
+
+    /// Opens a rewards account so other players can name the signer as
+    /// their referrer.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let rewards = &mut ctx.accounts.referrer_rewards;
+        rewards.referrer = ctx.accounts.signer.key();
+        rewards.referred_players = 0;
+        rewards.accrued = 0;
+        rewards.total_earned = 0;
+        rewards.bump = ctx.bumps.referrer_rewards;
+
+        msg!("Referrer registered: {}", rewards.referrer);
+        Ok(())
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let rewards = &mut ctx.accounts.referrer_rewards;
+        let amount = rewards.accrued;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        rewards.accrued = 0;
+        withdraw_lamports(
+            &rewards.to_account_info(),
+            &ctx.accounts.referrer.to_account_info(),
+            amount,
+        )?;
+
+        msg!("Referral rewards claimed: {} lamports", amount);
+        Ok(())
+    }
+
+    pub fn create_season(
+        ctx: Context<CreateSeason>,
+        season_id: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        require!(season_id != 0, ErrorCode::InvalidSeason);
+        require!(start_ts < end_ts, ErrorCode::InvalidSeason);
+
+        let season = &mut ctx.accounts.season;
+        season.season_id = season_id;
+        season.start_ts = start_ts;
+        season.end_ts = end_ts;
+        season.prize_pool = 0;
+        season.total_claimed = 0;
+        season.merkle_root = [0; 32];
+        season.finalized = false;
+        season.bump = ctx.bumps.season;
+
+        msg!("Season {} created: {} to {}", season_id, start_ts, end_ts);
+        Ok(())
+    }
+
+    pub fn fund_season(ctx: Context<FundSeason>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(!ctx.accounts.season.finalized, ErrorCode::SeasonFinalized);
+
+        deposit_lamports(
+            &ctx.accounts.signer,
+            ctx.accounts.season.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        let season = &mut ctx.accounts.season;
+        season.prize_pool = season
+            .prize_pool
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Season {} prize pool: {} lamports",
+            season.season_id,
+            season.prize_pool
+        );
+        Ok(())
+    }
+
+    pub fn join_season(ctx: Context<JoinSeason>, season_id: u64) -> Result<()> {
+        let season_score = &mut ctx.accounts.season_score;
+        season_score.season_id = season_id;
+        season_score.player = ctx.accounts.signer.key();
+        season_score.score = 0;
+        season_score.wins = 0;
+        season_score.bump = ctx.bumps.season_score;
+
+        msg!("{} joined season {}", season_score.player, season_id);
+        Ok(())
+    }
+
+    /// Locks in the final rankings as a Merkle root of
+    /// `season_prize_leaf(season_id, player, amount)` leaves.
+    pub fn finalize_season(ctx: Context<FinalizeSeason>, merkle_root: [u8; 32]) -> Result<()> {
+        let season = &mut ctx.accounts.season;
+        require!(!season.finalized, ErrorCode::SeasonFinalized);
+        require!(
+            Clock::get()?.unix_timestamp >= season.end_ts,
+            ErrorCode::SeasonNotEnded
+        );
+
+        season.merkle_root = merkle_root;
+        season.finalized = true;
+
+        msg!("Season {} finalized", season.season_id);
+        Ok(())
+    }
+
+    pub fn claim_season_prize(
+        ctx: Context<ClaimSeasonPrize>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            proof.len() <= MAX_MERKLE_PROOF_LEN,
+            ErrorCode::InvalidMerkleProof
+        );
+
+        let season = &mut ctx.accounts.season;
+        require!(season.finalized, ErrorCode::SeasonNotFinalized);
+
+        let leaf = season_prize_leaf(season.season_id, &ctx.accounts.signer.key(), amount);
+        require!(
+            verify_merkle_proof(&proof, season.merkle_root, leaf),
+            ErrorCode::InvalidMerkleProof
+        );
+
+        season.total_claimed = season
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            season.total_claimed <= season.prize_pool,
+            ErrorCode::InsufficientLiquidity
+        );
+        withdraw_lamports(
+            &season.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            amount,
+        )?;
+
+        let receipt = &mut ctx.accounts.prize_claim;
+        receipt.season_id = season.season_id;
+        receipt.player = ctx.accounts.signer.key();
+        receipt.amount = amount;
+
+        msg!(
+            "Season {} prize claimed: {} lamports",
+            season.season_id,
+            amount
+        );
+        Ok(())
+    }
+
+    /// Upgrades a profile written before `version` existed, reallocating it
+    /// and backfilling new fields.
+    pub fn migrate_player_profile(ctx: Context<MigratePlayerProfile>) -> Result<()> {
+        let info = ctx.accounts.player_profile.to_account_info();
+        let profile = {
+            let data = info.try_borrow_data()?;
+            // Only unversioned profiles have exactly this length
+            require!(
+                data.len() == PlayerProfileV0::LEN,
+                ErrorCode::AlreadyMigrated
+            );
+            PlayerProfile::from(read_legacy::<PlayerProfileV0>(
+                &data,
+                PlayerProfile::DISCRIMINATOR,
+            )?)
+        };
+        require_keys_eq!(
+            profile.owner,
+            ctx.accounts.signer.key(),
+            ErrorCode::NotBetOwner
+        );
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.signer,
+            &ctx.accounts.system_program,
+            8 + PlayerProfile::INIT_SPACE,
+            &profile,
+        )?;
+
+        msg!(
+            "Player profile migrated to v{}: level {}",
+            profile.version,
+            profile.level
+        );
+        Ok(())
+    }
+
+    /// Upgrades a bet written before `version` existed. Anyone may pay for
+    /// the migration.
+    pub fn migrate_bet(ctx: Context<MigrateAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+        let bet = {
+            let data = info.try_borrow_data()?;
+            let current = Bet::try_deserialize(&mut &data[..]);
+            require!(
+                !current.is_ok_and(|b| b.version == BET_VERSION),
+                ErrorCode::AlreadyMigrated
+            );
+            Bet::from(read_legacy::<BetV0>(&data, Bet::DISCRIMINATOR)?)
+        };
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + Bet::INIT_SPACE,
+            &bet,
+        )?;
+
+        msg!("Bet migrated to v{}", bet.version);
+        Ok(())
+    }
+
+    /// Upgrades an event written before `version` existed. Anyone may pay
+    /// for the migration.
+    pub fn migrate_event(ctx: Context<MigrateAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+        let mut event = {
+            let data = info.try_borrow_data()?;
+            let current = OracleEvent::try_deserialize(&mut &data[..]);
+            require!(
+                !current.is_ok_and(|e| e.version == ORACLE_EVENT_VERSION),
+                ErrorCode::AlreadyMigrated
+            );
+            OracleEvent::from(read_legacy::<OracleEventV0>(
+                &data,
+                OracleEvent::DISCRIMINATOR,
+            )?)
+        };
+        if event.resolved {
+            // The grace period for unclaimed stake starts at migration
+            event.resolved_at = Clock::get()?.unix_timestamp;
+        }
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            OracleEvent::space(&event.description, &event.metadata_uri),
+            &event,
+        )?;
+
+        msg!("Event {} migrated to v{}", event.event_id, event.version);
+        Ok(())
+    }
+
+    /// Returns a bet's rent to its owner once nothing is owed on it: the
+    /// winnings were claimed, the stake refunded or the loss settled with
+    /// `settle_losing_bet`. Once the event itself is closed an unsettled bet
+    /// can no longer be settled, so it closes as is and its stake leaves the
+    /// player's exposure.
+    pub fn close_bet(ctx: Context<CloseBet>) -> Result<()> {
+        let bet = &ctx.accounts.bet;
+        if !bet.settled {
+            require!(
+                ctx.accounts.oracle_event.data_is_empty(),
+                ErrorCode::BetNotSettled
+            );
+            ctx.accounts.player_profile.release_exposure(bet.amount);
+        }
+        Ok(())
+    }
+
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        event_id: u64,
+        fee_bps: u16,
+        initial_liquidity: u64,
+    ) -> Result<()> {
+        require!(fee_bps <= MAX_POOL_FEE_BPS, ErrorCode::FeeTooHigh);
+        require!(initial_liquidity > 0, ErrorCode::InvalidAmount);
+
+        deposit_lamports(
+            &ctx.accounts.authority,
+            ctx.accounts.pool.to_account_info(),
+            &ctx.accounts.system_program,
+            initial_liquidity,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.event_id = event_id;
+        pool.fee_bps = fee_bps;
+        pool.bump = ctx.bumps.pool;
+
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.authority.key();
+        position.event_id = event_id;
+        position.bump = ctx.bumps.position;
+        pool.add_liquidity(position, initial_liquidity)?;
+
+        msg!(
+            "Pool created for event {}: {} lamports, fee {} bps",
+            event_id,
+            initial_liquidity,
+            fee_bps
+        );
+        Ok(())
+    }
+
+    pub fn buy_outcome(
+        ctx: Context<TradePool>,
+        event_id: u64,
+        outcome: bool,
+        amount: u64,
+        min_shares_out: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        deposit_lamports(
+            &ctx.accounts.signer,
+            ctx.accounts.pool.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.init_if_empty(ctx.accounts.signer.key(), event_id, ctx.bumps.position);
+
+        let pool = &mut ctx.accounts.pool;
+        let shares_out = pool.buy(outcome, amount)?;
+        require!(shares_out >= min_shares_out, ErrorCode::SlippageExceeded);
+        position.credit_shares(outcome, shares_out)?;
+
+        msg!(
+            "Bought {} {} shares for {} lamports, YES now at {} bps",
+            shares_out,
+            if outcome { "YES" } else { "NO" },
+            amount,
+            pool.implied_yes_probability_bps()
+        );
+        Ok(())
+    }
+
+    pub fn add_liquidity(ctx: Context<TradePool>, event_id: u64, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        deposit_lamports(
+            &ctx.accounts.signer,
+            ctx.accounts.pool.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.init_if_empty(ctx.accounts.signer.key(), event_id, ctx.bumps.position);
+
+        let pool = &mut ctx.accounts.pool;
+        let fees = pool.settle_fees(position)?;
+        let minted = pool.add_liquidity(position, amount)?;
+        withdraw_lamports(
+            &pool.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            fees,
+        )?;
+
+        msg!(
+            "Liquidity added: {} lamports for {} LP shares",
+            amount,
+            minted
+        );
+        Ok(())
+    }
+
+    pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, lp_shares: u64) -> Result<()> {
+        require!(lp_shares > 0, ErrorCode::InvalidAmount);
+
+        let pool = &mut ctx.accounts.pool;
+        let position = &mut ctx.accounts.position;
+        let fees = pool.settle_fees(position)?;
+        let merged = pool.remove_liquidity(position, lp_shares)?;
+        let payout = fees.checked_add(merged).ok_or(ErrorCode::MathOverflow)?;
+        withdraw_lamports(
+            &pool.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            payout,
+        )?;
+
+        msg!(
+            "Liquidity removed: {} LP shares, {} lamports merged, {} lamports in fees",
+            lp_shares,
+            merged,
+            fees
+        );
+        Ok(())
+    }
+
+    pub fn redeem_outcome_shares(ctx: Context<RedeemOutcomeShares>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        let payout = position.redemption_value(&ctx.accounts.oracle_event)?;
+        position.yes_shares = 0;
+        position.no_shares = 0;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.collateral = pool
+            .collateral
+            .checked_sub(payout)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        withdraw_lamports(
+            &pool.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            payout,
+        )?;
+
+        msg!("Redeemed outcome shares for {} lamports", payout);
+        Ok(())
+    }
+
+    pub fn create_order_book(ctx: Context<CreateOrderBook>, event_id: u64) -> Result<()> {
+        let book = &mut ctx.accounts.order_book;
+        book.event_id = event_id;
+        book.next_order_id = 0;
+        book.bump = ctx.bumps.order_book;
+        msg!("Order book created for event {}", event_id);
+        Ok(())
+    }
+
+    /// Rests a new order. When its side is full the worst-priced order is
+    /// evicted and refunded, which takes one remaining account: the evicted
+    /// owner's wallet for a bid, or their `Bet` for an ask.
+    pub fn place_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceOrder<'info>>,
+        event_id: u64,
+        side: OrderSide,
+        price_bps: u16,
+        quantity: u64,
+    ) -> Result<()> {
+        require!(quantity > 0, ErrorCode::InvalidAmount);
+        require!(
+            price_bps > 0 && (price_bps as u64) < BPS_DENOMINATOR,
+            ErrorCode::InvalidPrice
+        );
+        require!(
+            order_cost(quantity, price_bps)? >= MIN_ORDER_NOTIONAL,
+            ErrorCode::OrderTooSmall
+        );
+
+        let bet = &mut ctx.accounts.bet;
+        if bet.player == Pubkey::default() {
+            bet.player = ctx.accounts.signer.key();
+            bet.event_id = event_id;
+            bet.chosen_outcome = true;
+            bet.version = BET_VERSION;
+            // Counted like any other bet so close_event waits for its claim
+            ctx.accounts.oracle_event.count_bet(true)?;
+        }
+        require!(bet.chosen_outcome, ErrorCode::NotYesPosition);
+
+        let escrow = match side {
+            OrderSide::Bid => {
+                // Checked again at fill time, when the exposure opens
+                let position = bet
+                    .amount
+                    .checked_add(quantity)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                ctx.accounts.oracle_event.check_stake(position)?;
+                let cost = order_cost(quantity, price_bps)?;
+                deposit_lamports(
+                    &ctx.accounts.signer,
+                    ctx.accounts.order_book.to_account_info(),
+                    &ctx.accounts.system_program,
+                    cost,
+                )?;
+                cost
+            }
+            OrderSide::Ask => {
+                // The offered stake leaves the bet until it fills or is cancelled
+                bet.amount = bet
+                    .amount
+                    .checked_sub(quantity)
+                    .ok_or(ErrorCode::InsufficientShares)?;
+                0
+            }
+        };
+
+        let book = &mut ctx.accounts.order_book;
+        let order_id = book.next_order_id;
+        let evicted = book.insert(
+            side,
+            Order {
+                order_id,
+                owner: ctx.accounts.signer.key(),
+                price_bps,
+                quantity,
+                escrow,
+            },
+        )?;
+        if let Some(evicted) = evicted {
+            let refund_info = ctx
+                .remaining_accounts
+                .first()
+                .ok_or(ErrorCode::EvictedOrderAccountMismatch)?;
+            refund_evicted_order(
+                &book.to_account_info(),
+                refund_info,
+                side,
+                &evicted,
+                event_id,
+            )?;
+            msg!("Order {} evicted", evicted.order_id);
+        }
+
+        msg!(
+            "Order {} placed: {:?} {} YES shares at {} bps",
+            order_id,
+            side,
+            quantity,
+            price_bps
+        );
+        Ok(())
+    }
+
+    /// Removes the signer's order from the book. A bid's escrow is refunded;
+    /// an ask's stake returns to the bet unless the bet was already settled
+    /// or closed without it. Orders are never filled once the event is
+    /// cancelled, so an ask left over from a refunded bet is paid back from
+    /// the event at cost.
+    pub fn cancel_order(ctx: Context<CancelOrder>, side: OrderSide, order_id: u64) -> Result<()> {
+        let book = &mut ctx.accounts.order_book;
+        let order = book.remove(side, order_id)?;
+        require!(
+            order.owner == ctx.accounts.signer.key(),
+            ErrorCode::NotOrderOwner
+        );
+
+        match side {
+            OrderSide::Bid => withdraw_lamports(
+                &book.to_account_info(),
+                &ctx.accounts.signer.to_account_info(),
+                order.escrow,
+            )?,
+            OrderSide::Ask => {
+                // A settled bet has already been paid or refunded without the
+                // offered stake, so it cannot take it back
+                let bet_info = ctx.accounts.bet.to_account_info();
+                let mut returned = false;
+                if !bet_info.data_is_empty() {
+                    let mut bet = Bet::try_deserialize(&mut &bet_info.try_borrow_data()?[..])?;
+                    if !bet.settled {
+                        bet.amount = bet
+                            .amount
+                            .checked_add(order.quantity)
+                            .ok_or(ErrorCode::MathOverflow)?;
+                        bet.try_serialize(&mut &mut bet_info.try_borrow_mut_data()?[..])?;
+                        returned = true;
+                    }
+                }
+
+                let event_info = ctx.accounts.oracle_event.to_account_info();
+                if !returned
+                    && !event_info.data_is_empty()
+                    && OracleEvent::try_deserialize(&mut &event_info.try_borrow_data()?[..])?
+                        .cancelled
+                {
+                    withdraw_lamports(
+                        &event_info,
+                        &ctx.accounts.signer.to_account_info(),
+                        order.quantity,
+                    )?;
+                }
+            }
+        }
+
+        msg!("Order {} cancelled", order_id);
+        Ok(())
+    }
+
+    /// Matches crossing orders. Each step consumes five remaining accounts:
+    /// the buyer's `Bet`, `PlayerProfile` and wallet, then the seller's
+    /// `PlayerProfile` and wallet. A bid whose buyer would go over the
+    /// event's stake limit or their exposure limit is dropped and refunded
+    /// instead of filled, so it cannot block the book.
+    pub fn crank_match<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrankMatch<'info>>,
+        max_fills: u8,
+    ) -> Result<()> {
+        let event = &ctx.accounts.oracle_event;
+        let max_exposure = ctx.accounts.config.max_player_exposure;
+        let book = &mut ctx.accounts.order_book;
+        let book_info = book.to_account_info();
+        let mut fill_accounts = ctx.remaining_accounts.chunks_exact(5);
+        let mut fills = 0u8;
+
+        while fills < max_fills {
+            let Some(fill) = book.peek_fill() else {
+                break;
+            };
+            let accounts = fill_accounts.next().ok_or(ErrorCode::MissingFillAccounts)?;
+            let (buyer_bet_info, buyer_profile_info, buyer_wallet) =
+                (&accounts[0], &accounts[1], &accounts[2]);
+            let (seller_profile_info, seller_wallet) = (&accounts[3], &accounts[4]);
+
+            require_keys_eq!(
+                buyer_wallet.key(),
+                fill.buyer,
+                ErrorCode::FillAccountMismatch
+            );
+            require_keys_eq!(
+                seller_wallet.key(),
+                fill.seller,
+                ErrorCode::FillAccountMismatch
+            );
+            let (buyer_bet_key, _) = Pubkey::find_program_address(
+                &[
+                    b"bet",
+                    fill.buyer.as_ref(),
+                    book.event_id.to_le_bytes().as_ref(),
+                ],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                buyer_bet_info.key(),
+                buyer_bet_key,
+                ErrorCode::FillAccountMismatch
+            );
+            let (buyer_profile_key, _) =
+                Pubkey::find_program_address(&[b"player", fill.buyer.as_ref()], &crate::ID);
+            require_keys_eq!(
+                buyer_profile_info.key(),
+                buyer_profile_key,
+                ErrorCode::FillAccountMismatch
+            );
+            let (seller_profile_key, _) =
+                Pubkey::find_program_address(&[b"player", fill.seller.as_ref()], &crate::ID);
+            require_keys_eq!(
+                seller_profile_info.key(),
+                seller_profile_key,
+                ErrorCode::FillAccountMismatch
+            );
+
+            let mut buyer_bet = Account::<Bet>::try_from(buyer_bet_info)?;
+            let buyer_amount = buyer_bet
+                .amount
+                .checked_add(fill.quantity)
+                .ok_or(ErrorCode::MathOverflow)?;
+            let mut buyer_profile = Account::<PlayerProfile>::try_from(buyer_profile_info).ok();
+            let accepted = event.check_stake(buyer_amount).is_ok()
+                && buyer_profile.as_mut().is_some_and(|profile| {
+                    profile.open_exposure(fill.quantity, max_exposure).is_ok()
+                });
+            if !accepted {
+                let bid = book.remove(OrderSide::Bid, fill.bid_id)?;
+                withdraw_lamports(&book_info, buyer_wallet, bid.escrow)?;
+                msg!("Bid {} dropped: over the buyer's limits", fill.bid_id);
+                fills += 1;
+                continue;
+            }
+
+            let fill = book.next_fill().ok_or(ErrorCode::NothingToMatch)?;
+            buyer_bet.amount = buyer_amount;
+            buyer_bet.exit(&crate::ID)?;
+            if let Some(buyer_profile) = buyer_profile {
+                buyer_profile.exit(&crate::ID)?;
+            }
+            let mut seller_profile = Account::<PlayerProfile>::try_from(seller_profile_info)?;
+            seller_profile.release_exposure(fill.quantity);
+            seller_profile.exit(&crate::ID)?;
+
+            withdraw_lamports(&book_info, seller_wallet, fill.cost)?;
+            withdraw_lamports(&book_info, buyer_wallet, fill.buyer_refund)?;
+
+            msg!(
+                "Filled {} YES shares at {} bps: bid {} / ask {}",
+                fill.quantity,
+                fill.price_bps,
+                fill.bid_id,
+                fill.ask_id
+            );
+            fills += 1;
+        }
+
+        require!(fills > 0, ErrorCode::NothingToMatch);
+        Ok(())
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.fees_accrued = 0;
+        config.house_liquidity = 0;
+        config.house_exposure = 0;
+        config.bump = ctx.bumps.config;
+        config.apply(params)?;
+
+        msg!("Config initialized, admin {}", config.admin);
+        Ok(())
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        ctx.accounts.config.apply(params)?;
+        msg!("Config updated");
+        Ok(())
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let amount = config.fees_accrued;
+        config.fees_accrued = 0;
+        withdraw_lamports(
+            &config.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            amount,
+        )?;
+
+        msg!("Withdrew {} lamports in fees to treasury", amount);
+        Ok(())
+    }
+
+    pub fn fund_house(ctx: Context<FundHouse>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        deposit_lamports(
+            &ctx.accounts.signer,
+            ctx.accounts.config.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        let config = &mut ctx.accounts.config;
+        config.house_liquidity = config
+            .house_liquidity
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("House funded with {} lamports", amount);
+        Ok(())
+    }
+
+    /// Moves house liquidity not needed to cover open parlays to the treasury.
+    pub fn withdraw_house(ctx: Context<WithdrawHouse>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let config = &mut ctx.accounts.config;
+        let free_liquidity = config.house_liquidity.saturating_sub(config.house_exposure);
+        require!(amount <= free_liquidity, ErrorCode::InsufficientLiquidity);
+        config.house_liquidity -= amount;
+        withdraw_lamports(
+            &config.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            amount,
+        )?;
+
+        msg!(
+            "Withdrew {} lamports of house liquidity to treasury",
+            amount
+        );
+        Ok(())
+    }
+
+    /// Sets the odds parlays lock for each outcome of an open event.
+    pub fn set_leg_odds(
+        ctx: Context<SetLegOdds>,
+        event_id: u64,
+        yes_odds_bps: u64,
+        no_odds_bps: u64,
+    ) -> Result<()> {
+        let leg_odds = &mut ctx.accounts.leg_odds;
+        leg_odds.event_id = event_id;
+        leg_odds.bump = ctx.bumps.leg_odds;
+        leg_odds.set(yes_odds_bps, no_odds_bps)?;
+
+        msg!(
+            "Leg odds for event {}: YES {} bps, NO {} bps",
+            event_id,
+            yes_odds_bps,
+            no_odds_bps
+        );
+        Ok(())
+    }
+
+    pub fn cancel_event(ctx: Context<ResolveEvent>) -> Result<()> {
+        let event = &mut ctx.accounts.oracle_event;
+        require!(!event.resolved, ErrorCode::AlreadyResolved);
+        require!(!event.cancelled, ErrorCode::EventCancelled);
+
+        event.cancelled = true;
+        event.resolved_at = Clock::get()?.unix_timestamp;
+        event.outstanding_claims = event.total_bets;
+        msg!("Event {} cancelled", event.event_id);
+        Ok(())
+    }
+
+    pub fn refund_bet(ctx: Context<RefundBet>) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        refund_stake(
+            &mut accounts.bet,
+            &mut accounts.oracle_event,
+            &mut accounts.player_profile,
+            &accounts.signer,
+        )
+    }
+
+    /// Closes a resolved or cancelled event and returns its rent to the
+    /// authority. Stakes are escrowed in the event account itself, so once
+    /// every winner has claimed (or every bet is refunded) and the event's
+    /// AMM pool is fully redeemed, or the grace period has passed, whatever
+    /// stake is left and any unredeemed pool collateral go to the treasury.
+    /// Parlays settle against the event, so it stays open until every
+    /// parlay with a leg on it is claimed. Bets left unsettled can still be
+    /// closed with `close_bet`.
+    pub fn close_event(ctx: Context<CloseEvent>) -> Result<()> {
+        let event = &ctx.accounts.oracle_event;
+        require!(
+            event.resolved || event.cancelled,
+            ErrorCode::EventNotResolved
+        );
+        require!(event.open_parlay_legs == 0, ErrorCode::ParlayLegsOpen);
+        // Outcome shares still backed by the event's pool redeem against it
+        let pool_info = ctx.accounts.pool.to_account_info();
+        let mut pool = if pool_info.data_is_empty() {
+            None
+        } else {
+            Some(AmmPool::try_deserialize(
+                &mut &pool_info.try_borrow_data()?[..],
+            )?)
+        };
+        let pool_collateral = pool.as_ref().map_or(0, |pool| pool.collateral);
+        if event.outstanding_claims > 0 || pool_collateral > 0 {
+            let now = Clock::get()?.unix_timestamp;
+            require!(
+                now >= event.resolved_at.saturating_add(EVENT_CLOSE_GRACE_PERIOD),
+                ErrorCode::ClaimsOutstanding
+            );
+        }
+
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let info = event.to_account_info();
+        let rent = Rent::get()?.minimum_balance(info.data_len());
+        let unclaimed = info.lamports().saturating_sub(rent);
+        withdraw_lamports(&info, &treasury, unclaimed)?;
+
+        // Shares can no longer redeem once the event is gone
+        if let Some(pool) = pool.as_mut().filter(|pool| pool.collateral > 0) {
+            withdraw_lamports(&pool_info, &treasury, pool.collateral)?;
+            pool.collateral = 0;
+            pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+        }
+
+        msg!(
+            "Event {} closed: {} unclaimed lamports and {} pool collateral to treasury, {} claims outstanding",
+            event.event_id,
+            unclaimed,
+            pool_collateral,
+            event.outstanding_claims
+        );
+        Ok(())
+    }
+
+    /// Places a multi-leg bet. Each leg takes two remaining accounts, in the
+    /// same order as `legs`: its writable `OracleEvent` and its `LegOdds`.
+    pub fn place_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceParlay<'info>>,
+        parlay_id: u64,
+        legs: Vec<ParlayLegInput>,
+        stake: u64,
+    ) -> Result<()> {
+        require!(stake > 0, ErrorCode::InvalidAmount);
+        require!(
+            (2..=MAX_PARLAY_LEGS).contains(&legs.len()),
+            ErrorCode::InvalidParlayLegs
+        );
+        require!(
+            ctx.remaining_accounts.len() == 2 * legs.len(),
+            ErrorCode::InvalidParlayLegs
+        );
+
+        let mut parlay_legs = Vec::with_capacity(legs.len());
+        for (input, leg_accounts) in legs.iter().zip(ctx.remaining_accounts.chunks_exact(2)) {
+            require!(
+                !parlay_legs
+                    .iter()
+                    .any(|leg: &ParlayLeg| leg.event_id == input.event_id),
+                ErrorCode::InvalidParlayLegs
+            );
+            let mut event = load_event(&leg_accounts[0], input.event_id)?;
+            require!(!event.resolved, ErrorCode::AlreadyResolved);
+            require!(!event.cancelled, ErrorCode::EventCancelled);
+            let leg_odds = load_leg_odds(&leg_accounts[1], input.event_id)?;
+            event.open_parlay_legs = event
+                .open_parlay_legs
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+            event.try_serialize(&mut &mut leg_accounts[0].try_borrow_mut_data()?[..])?;
+
+            parlay_legs.push(ParlayLeg {
+                event_id: input.event_id,
+                outcome: input.outcome,
+                odds_bps: leg_odds.odds_bps(input.outcome),
+            });
+        }
+
+        let potential_payout = parlay_payout(stake, parlay_legs.iter().map(|leg| leg.odds_bps))?;
+
+        deposit_lamports(
+            &ctx.accounts.signer,
+            ctx.accounts.config.to_account_info(),
+            &ctx.accounts.system_program,
+            stake,
+        )?;
+        let config = &mut ctx.accounts.config;
+        config.house_liquidity = config
+            .house_liquidity
+            .checked_add(stake)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.house_exposure = config
+            .house_exposure
+            .checked_add(potential_payout)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            config.house_exposure <= config.max_house_exposure
+                && config.house_exposure <= config.house_liquidity,
+            ErrorCode::HouseExposureExceeded
+        );
+
+        let parlay = &mut ctx.accounts.parlay;
+        parlay.player = ctx.accounts.signer.key();
+        parlay.parlay_id = parlay_id;
+        parlay.stake = stake;
+        parlay.potential_payout = potential_payout;
+        parlay.legs = parlay_legs;
+        parlay.bump = ctx.bumps.parlay;
+
+        msg!(
+            "Parlay {} placed: {} legs, {} lamports to win {}",
+            parlay_id,
+            parlay.legs.len(),
+            stake,
+            potential_payout
+        );
+        Ok(())
+    }
+
+    /// Settles a parlay once every leg is resolved or cancelled. Cancelled
+    /// legs are dropped from the odds; any lost leg settles it at zero.
+    /// Takes each leg's writable `OracleEvent` as a remaining account.
+    pub fn claim_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimParlay<'info>>,
+    ) -> Result<()> {
+        let parlay = &ctx.accounts.parlay;
+        require!(
+            ctx.remaining_accounts.len() == parlay.legs.len(),
+            ErrorCode::InvalidParlayLegs
+        );
+
+        let mut won = true;
+        let mut live_odds = Vec::with_capacity(parlay.legs.len());
+        for (leg, event_info) in parlay.legs.iter().zip(ctx.remaining_accounts) {
+            let mut event = load_event(event_info, leg.event_id)?;
+            event.open_parlay_legs = event.open_parlay_legs.saturating_sub(1);
+            event.try_serialize(&mut &mut event_info.try_borrow_mut_data()?[..])?;
+            if event.cancelled {
+                continue;
+            }
+            require!(event.resolved, ErrorCode::EventNotResolved);
+            if event.outcome != Some(leg.outcome) {
+                won = false;
+            }
+            live_odds.push(leg.odds_bps);
+        }
+
+        let payout = if won {
+            parlay_payout(parlay.stake, live_odds)?
+        } else {
+            0
+        };
+
+        let config = &mut ctx.accounts.config;
+        config.house_exposure = config
+            .house_exposure
+            .checked_sub(parlay.potential_payout)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.house_liquidity = config
+            .house_liquidity
+            .checked_sub(payout)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        withdraw_lamports(
+            &config.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            payout,
+        )?;
+
+        msg!(
+            "Parlay {} settled: {} lamports paid",
+            parlay.parlay_id,
+            payout
+        );
+        Ok(())
+    }
+
+    pub fn cash_out(ctx: Context<CashOut>, min_payout: u64) -> Result<()> {
+        let bet = &ctx.accounts.bet;
+        require!(!bet.claimed, ErrorCode::AlreadyClaimed);
+
+        let config = &mut ctx.accounts.config;
+        let event = &mut ctx.accounts.oracle_event;
+        let quote = CashOutQuote::new(event, bet, config.cash_out_fee_bps)?;
+        require!(quote.payout >= min_payout, ErrorCode::SlippageExceeded);
+
+        ctx.accounts.player_profile.release_exposure(bet.amount);
+        event.remove_stake(bet.chosen_outcome, quote.value)?;
+        event.total_amount = event
+            .total_amount
+            .checked_sub(quote.value)
+            .ok_or(ErrorCode::MathOverflow)?;
+        event.total_bets = event.total_bets.saturating_sub(1);
+        if bet.chosen_outcome {
+            event.yes_votes = event.yes_votes.saturating_sub(1);
+        } else {
+            event.no_votes = event.no_votes.saturating_sub(1);
+        }
+
+        withdraw_lamports(
+            &event.to_account_info(),
+            &ctx.accounts.signer.to_account_info(),
+            quote.payout,
+        )?;
+        withdraw_lamports(
+            &event.to_account_info(),
+            &config.to_account_info(),
+            quote.fee,
+        )?;
+        config.fees_accrued = config
+            .fees_accrued
+            .checked_add(quote.fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Cashed out {} lamports (value {}, fee {}) at {} bps",
+            quote.payout,
+            quote.value,
+            quote.fee,
+            quote.probability_bps
+        );
+        Ok(())
+    }
+
+    /// View: what a new bet of `amount` on `chosen_outcome` would pay if the
+    /// event resolved that way right after it. Meant for
+    /// `simulateTransaction`; nothing is written.
+    pub fn quote_payout(
+        ctx: Context<QuotePayout>,
+        chosen_outcome: bool,
+        amount: u64,
+    ) -> Result<PayoutQuote> {
+        ctx.accounts.oracle_event.check_stake(amount)?;
+        ctx.accounts.oracle_event.quote_payout(
+            chosen_outcome,
+            amount,
+            ctx.accounts.config.protocol_fee_bps,
+        )
+    }
+
+    /// View: the event's pool, odds and claim state.
+    pub fn get_event_summary(ctx: Context<GetEventSummary>) -> Result<EventSummary> {
+        Ok(ctx.accounts.oracle_event.summary())
+    }
+
+    /// View: the profile's stats and what its bets could claim now. Bets to
+    /// count are passed through `remaining_accounts` as `[bet, event]`
+    /// pairs, as for `claim_many`.
+    pub fn get_player_summary<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetPlayerSummary<'info>>,
+    ) -> Result<PlayerSummary> {
+        let pairs = ctx.remaining_accounts;
+        require!(
+            pairs.len().is_multiple_of(2),
+            ErrorCode::InvalidClaimAccounts
+        );
+
+        let player = &ctx.accounts.player_profile;
+        let config = &ctx.accounts.config;
+        let mut claimable = 0u64;
+        let mut claimable_bets = 0u64;
+        for pair in pairs.chunks_exact(2) {
+            let (bet_info, event_info) = (&pair[0], &pair[1]);
+            let bet = Account::<Bet>::try_from(bet_info)?;
+            let event_seed = bet.event_id.to_le_bytes();
+            let (expected_bet, _) = Pubkey::find_program_address(
+                &[b"bet", player.owner.as_ref(), event_seed.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                bet_info.key(),
+                expected_bet,
+                ErrorCode::InvalidClaimAccounts
+            );
+            let (expected_event, _) =
+                Pubkey::find_program_address(&[b"event", event_seed.as_ref()], &crate::ID);
+            require_keys_eq!(
+                event_info.key(),
+                expected_event,
+                ErrorCode::InvalidClaimAccounts
+            );
+            let event = Account::<OracleEvent>::try_from(event_info)?;
+
+            let amount = if bet.claimed {
+                0
+            } else if event.cancelled {
+                bet.amount
+            } else if event.resolved && event.outcome == Some(bet.chosen_outcome) {
+                winning_payout(&event, bet.amount, config, player.referrer.is_some())?.0
+            } else {
+                0
+            };
+            if amount > 0 {
+                claimable = claimable
+                    .checked_add(amount)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                claimable_bets += 1;
+            }
+        }
+
+        Ok(PlayerSummary {
+            owner: player.owner,
+            level: player.level,
+            xp: player.xp,
+            total_bets: player.total_bets,
+            bets_won: player.bets_won,
+            bets_lost: player.bets_lost,
+            win_streak: player.win_streak,
+            accuracy_bps: player.accuracy_bps,
+            open_exposure: player.open_exposure,
+            balance: player.balance,
+            claimable,
+            claimable_bets,
+        })
+    }
+}
+
+/// Return data of `quote_payout`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PayoutQuote {
+    pub event_id: u64,
+    pub chosen_outcome: bool,
+    pub amount: u64,
+    /// Chosen side's share of the pool once the bet is in.
+    pub implied_probability_bps: u64,
+    /// Decimal odds before fees, in bps.
+    pub odds_bps: u64,
+    /// Pot share before the protocol fee.
+    pub gross_payout: u64,
+    pub protocol_fee: u64,
+    /// What the bettor would receive.
+    pub potential_payout: u64,
+}
+
+/// Return data of `get_event_summary`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EventSummary {
+    pub event_id: u64,
+    pub category: EventCategory,
+    pub resolved: bool,
+    pub cancelled: bool,
+    pub outcome: Option<bool>,
+    pub total_bets: u64,
+    pub total_amount: u64,
+    pub yes_amount: u64,
+    pub no_amount: u64,
+    /// YES share of the pool; 5000 while the pool is empty.
+    pub yes_probability_bps: u64,
+    /// Decimal odds per side, as used to price parlay legs.
+    pub yes_odds_bps: u64,
+    pub no_odds_bps: u64,
+    pub outstanding_claims: u64,
+}
+
+/// Return data of `get_player_summary`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerSummary {
+    pub owner: Pubkey,
+    pub level: u32,
+    pub xp: u64,
+    pub total_bets: u64,
+    pub bets_won: u64,
+    pub bets_lost: u64,
+    pub win_streak: u32,
+    pub accuracy_bps: u16,
+    pub open_exposure: u64,
+    pub balance: u64,
+    /// Winnings and refunds the passed bets could claim now.
+    pub claimable: u64,
+    pub claimable_bets: u64,
+}
+
+/// Return data of `place_bet`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BetPlaced {
+    pub bet: Pubkey,
+    pub event_id: u64,
+    /// Event totals including this bet.
+    pub total_bets: u64,
+    pub total_amount: u64,
+    pub yes_amount: u64,
+    pub no_amount: u64,
+}
+
+/// Stable entry points for programs composing with oracle-quest over CPI.
+///
+/// The generated `cpi` module follows the instruction contexts, which grow
+/// accounts as features land. These wrappers pin the account set, so
+/// callers only change when the contract below does. The signing account
+/// may be a PDA of the caller, passed with `signer_seeds`; it pays any rent.
+///
+/// Account order of each instruction, as `AccountMeta`s; optional accounts
+/// that are absent are passed as this program's id:
+///
+/// - `initialize_player`: player profile (w), referrer rewards (w,
+///   optional), player (s, w), system program.
+/// - `place_bet`: bet (w), player profile (w), event (w), config, player,
+///   session token (optional; `None` here), signer (s, w), system program.
+///   With no session the player and signer are the same account.
+/// - `settle_bet` and `claim_winnings`: bet (w), event (w), player profile
+///   (w), season score (w, optional), config (w), referrer rewards (w,
+///   optional), player (w), session token (optional; `None` here), signer
+///   (s, w), season (optional).
+///
+/// PDAs are derived with the `*_address` functions. Requires the `cpi`
+/// feature.
+#[cfg(feature = "cpi")]
+pub mod interface {
+    use super::*;
+    use anchor_lang::error::ErrorCode as ProgramErrorCode;
+
+    pub fn player_address(player: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"player", player.as_ref()], &crate::ID).0
+    }
+
+    pub fn bet_address(player: &Pubkey, event_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"bet", player.as_ref(), event_id.to_le_bytes().as_ref()],
+            &crate::ID,
+        )
+        .0
+    }
+
+    pub fn event_address(event_id: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"event", event_id.to_le_bytes().as_ref()], &crate::ID).0
+    }
+
+    pub fn config_address() -> Pubkey {
+        Pubkey::find_program_address(&[b"config"], &crate::ID).0
+    }
+
+    pub struct InitializePlayerAccounts<'info> {
+        pub player_profile: AccountInfo<'info>,
+        pub player: AccountInfo<'info>,
+        pub system_program: AccountInfo<'info>,
+    }
+
+    pub struct PlaceBetAccounts<'info> {
+        pub bet: AccountInfo<'info>,
+        pub player_profile: AccountInfo<'info>,
+        pub oracle_event: AccountInfo<'info>,
+        pub config: AccountInfo<'info>,
+        /// Bets for itself and pays the stake and the bet's rent.
+        pub player: AccountInfo<'info>,
+        pub system_program: AccountInfo<'info>,
+    }
+
+    pub struct SettleBetAccounts<'info> {
+        pub bet: AccountInfo<'info>,
+        pub oracle_event: AccountInfo<'info>,
+        pub player_profile: AccountInfo<'info>,
+        /// With `season`, records wins on the player's season score.
+        pub season_score: Option<AccountInfo<'info>>,
+        pub config: AccountInfo<'info>,
+        /// Required when the player was referred.
+        pub referrer_rewards: Option<AccountInfo<'info>>,
+        /// Receives winnings, refunds and the bet's rent.
+        pub player: AccountInfo<'info>,
+        /// The event's season, alongside `season_score`.
+        pub season: Option<AccountInfo<'info>>,
+    }
+
+    /// Opens a profile for `accounts.player`, without a referrer.
+    pub fn initialize_player<'info>(
+        program: AccountInfo<'info>,
+        accounts: InitializePlayerAccounts<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        require_keys_eq!(program.key(), crate::ID, ProgramErrorCode::InvalidProgramId);
+        let accounts = cpi::accounts::InitializePlayer {
+            player_profile: accounts.player_profile,
+            referrer_rewards: None,
+            signer: accounts.player,
+            system_program: accounts.system_program,
+        };
+        cpi::initialize_player(
+            CpiContext::new_with_signer(program, accounts, signer_seeds),
+            None,
+        )
+    }
+
+    /// Places a bet for `accounts.player` and returns what `place_bet` set
+    /// as its return data.
+    pub fn place_bet<'info>(
+        program: AccountInfo<'info>,
+        accounts: PlaceBetAccounts<'info>,
+        signer_seeds: &[&[&[u8]]],
+        event_id: u64,
+        chosen_outcome: bool,
+        amount: u64,
+    ) -> Result<BetPlaced> {
+        require_keys_eq!(program.key(), crate::ID, ProgramErrorCode::InvalidProgramId);
+        let accounts = cpi::accounts::PlaceBet {
+            bet: accounts.bet,
+            player_profile: accounts.player_profile,
+            oracle_event: accounts.oracle_event,
+            config: accounts.config,
+            player: accounts.player.clone(),
+            session_token: None,
+            signer: accounts.player,
+            system_program: accounts.system_program,
+        };
+        let placed = cpi::place_bet(
+            CpiContext::new_with_signer(program, accounts, signer_seeds),
+            event_id,
+            chosen_outcome,
+            amount,
+        )?;
+        Ok(placed.get())
+    }
+
+    /// Settles `accounts.bet` whatever its result, optionally closing it.
+    pub fn settle_bet<'info>(
+        program: AccountInfo<'info>,
+        accounts: SettleBetAccounts<'info>,
+        signer_seeds: &[&[&[u8]]],
+        close_bet: bool,
+    ) -> Result<()> {
+        require_keys_eq!(program.key(), crate::ID, ProgramErrorCode::InvalidProgramId);
+        let accounts = cpi::accounts::ClaimWinnings {
+            bet: accounts.bet,
+            oracle_event: accounts.oracle_event,
+            player_profile: accounts.player_profile,
+            season_score: accounts.season_score,
+            config: accounts.config,
+            referrer_rewards: accounts.referrer_rewards,
+            player: accounts.player.clone(),
+            session_token: None,
+            signer: accounts.player,
+            season: accounts.season,
+        };
+        cpi::settle_bet(
+            CpiContext::new_with_signer(program, accounts, signer_seeds),
+            close_bet,
+        )
+    }
+}
+
+/// Scoped, expiring authority for an ephemeral key to bet for its owner.
+///
+/// The owner's stake budget is escrowed here, so a leaked key can never
+/// risk more than `max_stake - staked`.
+#[account]
+#[derive(InitSpace)]
+pub struct SessionToken {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    /// Bitmask of `SESSION_SCOPE_*` values the key may sign.
+    pub scopes: u8,
+    /// Total lamports the key may stake.
+    pub max_stake: u64,
+    pub staked: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl SessionToken {
+    pub fn authorize(&self, owner: &Pubkey, signer: &Pubkey, scope: u8, now: i64) -> Result<()> {
+        require!(
+            self.owner == *owner && self.session_key == *signer,
+            ErrorCode::UnauthorizedSigner
+        );
+        require!(now < self.expires_at, ErrorCode::SessionExpired);
+        require!(self.scopes & scope == scope, ErrorCode::SessionScopeDenied);
+        Ok(())
+    }
+
+    pub fn spend(&mut self, amount: u64) -> Result<()> {
+        let staked = self
+            .staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(staked <= self.max_stake, ErrorCode::SessionBudgetExceeded);
+        self.staked = staked;
+        Ok(())
+    }
+}
+
+/// Shared bankroll betting on behalf of its members.
+///
+/// The bankroll is held in this account's own lamports. Members own it in
+/// proportion to their `shares`; stake out in open bets counts towards the
+/// guild's value at cost until the bet is settled.
+#[account]
+#[derive(InitSpace)]
+pub struct Guild {
+    pub founder: Pubkey,
+    pub guild_id: u64,
+    #[max_len(MAX_GUILD_MEMBERS)]
+    pub members: Vec<GuildMember>,
+    pub total_shares: u64,
+    /// Idle lamports available to bet or withdraw.
+    pub bankroll: u64,
+    /// Lamports staked in unsettled guild bets.
+    pub open_stake: u64,
+    pub bump: u8,
+}
+
+/// Roles are ordered: each one may do everything the previous one can.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq, PartialOrd,
+)]
+pub enum GuildRole {
+    /// May deposit and withdraw.
+    Member,
+    /// May also place and settle guild bets.
+    Manager,
+    /// May also manage membership. Held by the founder only.
+    Owner,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Debug, PartialEq, Eq)]
+pub struct GuildMember {
+    pub member: Pubkey,
+    pub role: GuildRole,
+    pub shares: u64,
+}
+
+impl Guild {
+    pub fn member(&self, key: &Pubkey) -> Option<&GuildMember> {
+        self.members.iter().find(|m| m.member == *key)
+    }
+
+    pub fn member_mut(&mut self, key: &Pubkey) -> Option<&mut GuildMember> {
+        self.members.iter_mut().find(|m| m.member == *key)
+    }
+
+    pub fn require_role(&self, key: &Pubkey, role: GuildRole) -> Result<()> {
+        let member = self.member(key).ok_or(ErrorCode::NotGuildMember)?;
+        require!(member.role >= role, ErrorCode::GuildRoleRequired);
+        Ok(())
+    }
+
+    pub fn net_asset_value(&self) -> Result<u64> {
+        self.bankroll
+            .checked_add(self.open_stake)
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+
+    /// Shares minted for a deposit of `amount`. The first deposit, or any
+    /// deposit into a guild whose value was wiped out, mints one share per
+    /// lamport.
+    pub fn shares_for_deposit(&self, amount: u64) -> Result<u64> {
+        let nav = self.net_asset_value()?;
+        if self.total_shares == 0 || nav == 0 {
+            return Ok(amount);
+        }
+        let shares = amount as u128 * self.total_shares as u128 / nav as u128;
+        u64::try_from(shares).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Lamports `shares` are worth at the guild's current value.
+    pub fn withdrawal_value(&self, shares: u64) -> Result<u64> {
+        require!(shares <= self.total_shares, ErrorCode::InsufficientShares);
+        let value = shares as u128 * self.net_asset_value()? as u128 / self.total_shares as u128;
+        u64::try_from(value).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// Outcome of one bet in a `claim_many` call.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ClaimResult {
+    pub event_id: u64,
+    /// False when the bet was skipped rather than paid.
+    pub claimed: bool,
+    pub winnings: u64,
+}
+
+#[event]
+pub struct BetsClaimed {
+    pub player: Pubkey,
+    pub total_winnings: u64,
+    pub results: Vec<ClaimResult>,
+}
+
+/// Value of an open `Bet` as paid out by `cash_out`.
+///
+/// At the parimutuel split's own implied probability a bet is worth exactly
+/// its stake, so that is what a cash-out returns, less the fee. Pricing
+/// from anything else, such as an `AmmPool`, would let a cash-out take
+/// other bettors' stake out of the pot. Clients can rebuild the same quote
+/// from the fetched accounts before sending the transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CashOutQuote {
+    pub probability_bps: u64,
+    pub value: u64,
+    pub fee: u64,
+    pub payout: u64,
+}
+
+impl CashOutQuote {
+    pub fn new(event: &OracleEvent, bet: &Bet, fee_bps: u16) -> Result<Self> {
+        require!(!event.resolved, ErrorCode::AlreadyResolved);
+        require!(!event.cancelled, ErrorCode::EventCancelled);
+
+        let side_amount = event.stake_on(bet.chosen_outcome);
+        let denominator = BPS_DENOMINATOR as u128;
+        let probability_bps = (side_amount as u128 * denominator)
+            .checked_div(event.total_amount as u128)
+            .unwrap_or(0) as u64;
+
+        // Never more than the side still holds, whatever the bet records
+        let value = bet.amount.min(side_amount);
+        let fee = (value as u128 * fee_bps as u128 / denominator) as u64;
+        Ok(Self {
+            probability_bps,
+            value,
+            fee,
+            payout: value - fee,
+        })
+    }
+}
+
+/// Deserializes the `LegOdds` PDA for `event_id` from an unchecked account.
+fn load_leg_odds(info: &AccountInfo, event_id: u64) -> Result<LegOdds> {
+    let (expected, _) =
+        Pubkey::find_program_address(&[b"odds", event_id.to_le_bytes().as_ref()], &crate::ID);
+    require_keys_eq!(info.key(), expected, ErrorCode::LegOddsNotSet);
+    require_keys_eq!(*info.owner, crate::ID, ErrorCode::LegOddsNotSet);
+    LegOdds::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Deserializes the `OracleEvent` PDA for `event_id` from an unchecked account.
+fn load_event(info: &AccountInfo, event_id: u64) -> Result<OracleEvent> {
+    let (expected, _) =
+        Pubkey::find_program_address(&[b"event", event_id.to_le_bytes().as_ref()], &crate::ID);
+    require_keys_eq!(info.key(), expected, ErrorCode::EventMismatch);
+    require_keys_eq!(*info.owner, crate::ID, ErrorCode::EventMismatch);
+    OracleEvent::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Multiplies `stake` by every leg's odds in turn.
+pub fn parlay_payout(stake: u64, odds_bps: impl IntoIterator<Item = u64>) -> Result<u64> {
+    let payout = odds_bps
+        .into_iter()
+        .try_fold(stake as u128, |payout, odds| {
+            payout
+                .checked_mul(odds as u128)
+                .map(|p| p / BPS_DENOMINATOR as u128)
+        })
+        .ok_or(ErrorCode::MathOverflow)?;
+    u64::try_from(payout).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Checks that `signer` is `player` or holds a live session of theirs
+/// covering `scope`.
+fn authorize_player(
+    player: &AccountInfo,
+    signer: &Signer,
+    session: Option<&SessionToken>,
+    scope: u8,
+) -> Result<()> {
+    if player.key() == signer.key() {
+        return Ok(());
+    }
+    let session = session.ok_or(ErrorCode::UnauthorizedSigner)?;
+    session.authorize(
+        &player.key(),
+        &signer.key(),
+        scope,
+        Clock::get()?.unix_timestamp,
+    )
+}
+
+fn authorize_claim(accounts: &ClaimWinnings) -> Result<()> {
+    authorize_player(
+        &accounts.player,
+        &accounts.signer,
+        accounts.session_token.as_deref(),
+        SESSION_SCOPE_CLAIM,
+    )
+}
+
+/// Pays a winning bet its parimutuel share, less the protocol fee.
+fn pay_winnings(accounts: &mut ClaimWinnings) -> Result<()> {
+    let bet = &mut accounts.bet;
+    let event = &accounts.oracle_event;
+
+    require!(event.resolved, ErrorCode::EventNotResolved);
+    require!(!bet.claimed, ErrorCode::AlreadyClaimed);
+    require!(
+        bet.chosen_outcome == event.outcome.unwrap(),
+        ErrorCode::BetLost
+    );
+    require!(bet.player == accounts.player.key(), ErrorCode::NotBetOwner);
+
+    bet.claimed = true;
+    bet.settled = true;
+
+    let config = &mut accounts.config;
+    let referrer = accounts.player_profile.referrer;
+    let (winnings, fee, referral_cut) =
+        winning_payout(event, bet.amount, config, referrer.is_some())?;
+
+    withdraw_lamports(
+        &event.to_account_info(),
+        &accounts.player.to_account_info(),
+        winnings,
+    )?;
+    withdraw_lamports(&event.to_account_info(), &config.to_account_info(), fee)?;
+    config.fees_accrued = config
+        .fees_accrued
+        .checked_add(fee)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    if let Some(referrer) = referrer {
+        let rewards = accounts
+            .referrer_rewards
+            .as_mut()
+            .ok_or(ErrorCode::ReferrerNotRegistered)?;
+        require_keys_eq!(rewards.referrer, referrer, ErrorCode::ReferrerNotRegistered);
+        withdraw_lamports(
+            &event.to_account_info(),
+            &rewards.to_account_info(),
+            referral_cut,
+        )?;
+        rewards.credit(referral_cut)?;
+    }
+
+    let player = &mut accounts.player_profile;
+    player.release_exposure(bet.amount);
+    player.record_win()?;
+    player.balance = player
+        .balance
+        .checked_add(winnings)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Best effort: a player who never joined the season is still paid
+    if let (Some(season_score), Some(season)) =
+        (accounts.season_score.as_mut(), accounts.season.as_ref())
+    {
+        season_score.record_win(
+            season,
+            Clock::get()?.unix_timestamp,
+            winnings.saturating_sub(bet.amount),
+        )?;
+    }
+
+    let event = &mut accounts.oracle_event;
+    event.outstanding_claims = event.outstanding_claims.saturating_sub(1);
+
+    msg!("Winnings claimed: {} lamports", winnings);
+    Ok(())
+}
+
+/// Splits the payout of a winning `stake` into the player's winnings, the
+/// protocol's share of the fee and the referrer's cut.
+fn winning_payout(
+    event: &OracleEvent,
+    stake: u64,
+    config: &Config,
+    referred: bool,
+) -> Result<(u64, u64, u64)> {
+    let payout = event.parimutuel_payout(stake)?;
+    let profit = payout.saturating_sub(stake);
+    let referral_share_bps = if referred {
+        config.referral_share_bps
+    } else {
+        0
+    };
+    let (fee, referral_cut) =
+        split_protocol_fee(profit, config.protocol_fee_bps, referral_share_bps)?;
+    Ok((payout - fee, fee - referral_cut, referral_cut))
+}
+
+/// Records a losing bet on the player's profile.
+fn settle_loss(accounts: &mut ClaimWinnings) -> Result<()> {
+    let bet = &mut accounts.bet;
+    let event = &accounts.oracle_event;
+
+    require!(event.resolved, ErrorCode::EventNotResolved);
+    require!(!bet.settled, ErrorCode::AlreadySettled);
+    require!(event.outcome != Some(bet.chosen_outcome), ErrorCode::BetWon);
+
+    bet.settled = true;
+
+    let player = &mut accounts.player_profile;
+    player.release_exposure(bet.amount);
+    player.record_loss()?;
+
+    msg!(
+        "Losing bet settled: level {}, {} XP",
+        player.level,
+        player.xp
+    );
+    Ok(())
+}
+
+/// Returns a cancelled event's stake to the bettor.
+fn refund_stake(
+    bet: &mut Bet,
+    event: &mut Account<OracleEvent>,
+    player: &mut PlayerProfile,
+    signer: &AccountInfo,
+) -> Result<()> {
+    require!(event.cancelled, ErrorCode::EventNotCancelled);
+    require!(!bet.claimed, ErrorCode::AlreadyClaimed);
+
+    bet.claimed = true;
+    bet.settled = true;
+    event.outstanding_claims = event.outstanding_claims.saturating_sub(1);
+    player.release_exposure(bet.amount);
+    withdraw_lamports(&event.to_account_info(), signer, bet.amount)?;
+
+    msg!("Bet refunded: {} lamports", bet.amount);
+    Ok(())
+}
+
+/// Validates `params` and writes a fresh event.
+fn init_event(
+    event: &mut OracleEvent,
+    event_id: u64,
+    authority: Pubkey,
+    season: Option<&Season>,
+    params: EventParams,
+) -> Result<()> {
+    require!(
+        params.description.len() <= MAX_DESCRIPTION_LEN,
+        ErrorCode::DescriptionTooLong
+    );
+    require!(
+        params.min_stake > 0 && params.min_stake <= params.max_stake,
+        ErrorCode::InvalidStakeLimits
+    );
+    if params.season_id != 0 {
+        let season = season.ok_or(ErrorCode::SeasonAccountRequired)?;
+        require!(!season.finalized, ErrorCode::SeasonFinalized);
+        require!(
+            season.is_active(Clock::get()?.unix_timestamp),
+            ErrorCode::SeasonNotActive
+        );
+    }
+
+    event.event_id = event_id;
+    event.category = params.category;
+    event.tags = normalize_tags(params.tags)?;
+    event.description = params.description;
+    event.resolved = false;
+    event.authority = authority;
+    event.total_bets = 0;
+    event.yes_votes = 0;
+    event.no_votes = 0;
+    event.total_amount = 0;
+    event.min_stake = params.min_stake;
+    event.max_stake = params.max_stake;
+    event.season_id = params.season_id;
+    event.version = ORACLE_EVENT_VERSION;
+
+    msg!("Event {} created: {}", event_id, event.description);
+    Ok(())
+}
+
+/// Checks an event's tags for duplicates and sorts them, empty slots last,
+/// so equal tag sets always serialize to the same bytes.
+pub fn normalize_tags(mut tags: [u8; MAX_EVENT_TAGS]) -> Result<[u8; MAX_EVENT_TAGS]> {
+    tags.sort_unstable_by_key(|&tag| (tag == 0, tag));
+    require!(
+        tags.windows(2)
+            .all(|pair| pair[0] != pair[1] || pair[0] == 0),
+        ErrorCode::DuplicateTag
+    );
+    Ok(tags)
+}
+
+/// Splits the protocol fee on `profit`, returning the whole fee and the part
+/// of it owed to the player's referrer.
+pub fn split_protocol_fee(
+    profit: u64,
+    protocol_fee_bps: u16,
+    referral_share_bps: u16,
+) -> Result<(u64, u64)> {
+    let fee = (profit as u128)
+        .checked_mul(protocol_fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    let referral_cut = fee * referral_share_bps as u128 / BPS_DENOMINATOR as u128;
+    Ok((fee as u64, referral_cut as u64))
+}
+
+/// Leaf committed to by a season's Merkle root for one player's prize.
+pub fn season_prize_leaf(season_id: u64, player: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        &[0u8],
+        season_id.to_le_bytes().as_ref(),
+        player.as_ref(),
+        amount.to_le_bytes().as_ref(),
+    ])
+    .to_bytes()
+}
+
+/// Parent of two Merkle nodes. Pairs are sorted so proofs carry no
+/// left/right flags, and the `1` prefix keeps nodes distinct from leaves.
+pub fn merkle_parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1u8], left.as_ref(), right.as_ref()]).to_bytes()
+}
+
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| merkle_parent(node, *sibling))
+        == root
+}
+
+/// Lamports needed to buy `quantity` YES shares at `price_bps`, rounded up.
+pub fn order_cost(quantity: u64, price_bps: u16) -> Result<u64> {
+    let cost = (quantity as u128 * price_bps as u128).div_ceil(BPS_DENOMINATOR as u128);
+    u64::try_from(cost).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Returns an evicted order's stake to its owner: the escrow of a bid goes
+/// back to their wallet, the offered stake of an ask back to their `Bet`.
+/// An ask whose bet was closed or settled in the meantime is dropped.
+fn refund_evicted_order<'info>(
+    book: &AccountInfo<'info>,
+    refund_info: &'info AccountInfo<'info>,
+    side: OrderSide,
+    order: &Order,
+    event_id: u64,
+) -> Result<()> {
+    match side {
+        OrderSide::Bid => {
+            require_keys_eq!(
+                refund_info.key(),
+                order.owner,
+                ErrorCode::EvictedOrderAccountMismatch
+            );
+            withdraw_lamports(book, refund_info, order.escrow)
+        }
+        OrderSide::Ask => {
+            let (bet_key, _) = Pubkey::find_program_address(
+                &[
+                    b"bet",
+                    order.owner.as_ref(),
+                    event_id.to_le_bytes().as_ref(),
+                ],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                refund_info.key(),
+                bet_key,
+                ErrorCode::EvictedOrderAccountMismatch
+            );
+            if refund_info.data_is_empty() {
+                return Ok(());
+            }
+            let mut bet = Account::<Bet>::try_from(refund_info)?;
+            if bet.settled {
+                return Ok(());
+            }
+            bet.amount = bet
+                .amount
+                .checked_add(order.quantity)
+                .ok_or(ErrorCode::MathOverflow)?;
+            bet.exit(&crate::ID)
+        }
+    }
+}
+
+/// Parses a legacy layout after checking the account's discriminator.
+fn read_legacy<T: AnchorDeserialize>(data: &[u8], discriminator: &[u8]) -> Result<T> {
+    require!(
+        data.len() >= 8 && data[..8] == *discriminator,
+        ErrorCode::InvalidAccountLayout
+    );
+    T::deserialize(&mut &data[8..]).map_err(|_| error!(ErrorCode::InvalidAccountLayout))
+}
+
+/// Overwrites a program account with `account`, first growing it to `space`
+/// bytes if needed. Never shrinks.
+fn rewrite_account<'info, T: AccountSerialize>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    account: &T,
+) -> Result<()> {
+    resize_account(info, payer, system_program, space.max(info.data_len()))?;
+
+    let mut data = info.try_borrow_mut_data()?;
+    data.fill(0);
+    account.try_serialize(&mut &mut data[..])
+}
+
+/// Resizes a program account to `space` bytes, charging `payer` the extra
+/// rent or refunding the rent freed. Unlike Anchor's `realloc`, any other
+/// lamports held by the account (e.g. escrowed stake) stay where they are.
+fn resize_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?;
+    let old_rent = rent.minimum_balance(info.data_len());
+    let new_rent = rent.minimum_balance(space);
+    if new_rent > old_rent {
+        deposit_lamports(payer, info.clone(), system_program, new_rent - old_rent)?;
+    } else {
+        withdraw_lamports(info, &payer.to_account_info(), old_rent - new_rent)?;
+    }
+    info.resize(space)?;
+    Ok(())
+}
+
+/// Moves lamports from a wallet into a program account through the system program.
+fn deposit_lamports<'info>(
+    from: &Signer<'info>,
+    to: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: from.to_account_info(),
+                to,
+            },
+        ),
+        amount,
+    )
+}
+
+/// Moves lamports out of an account owned by this program.
+fn withdraw_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let from_balance = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientLiquidity)?;
+    let to_balance = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializePlayer<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + PlayerProfile::INIT_SPACE,
+        seeds = [b"player", signer.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    /// Required when a referrer is given.
+    #[account(
+        mut,
+        seeds = [b"referral", referrer_rewards.referrer.as_ref()],
+        bump = referrer_rewards.bump
+    )]
+    pub referrer_rewards: Option<Account<'info, ReferrerRewards>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct PlaceBet<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", player.key().as_ref(), event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"player", player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(
+        mut,
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: the bettor; must be the signer or the owner of `session_token`.
+    pub player: UncheckedAccount<'info>,
+    /// Required when a session key signs for `player`.
+    #[account(
+        mut,
+        seeds = [b"session", player.key().as_ref(), signer.key().as_ref()],
+        bump = session_token.bump
+    )]
+    pub session_token: Option<Account<'info, SessionToken>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: EventCategory)]
+pub struct InitializeCategoryCounter<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + CategoryCounter::INIT_SPACE,
+        seeds = [b"category", [category as u8].as_ref()],
+        bump
+    )]
+    pub category_counter: Account<'info, CategoryCounter>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: EventParams)]
+pub struct CreateOracleEvent<'info> {
+    #[account(
+        mut,
+        seeds = [b"category", [params.category as u8].as_ref()],
+        bump = category_counter.bump
+    )]
+    pub category_counter: Account<'info, CategoryCounter>,
+    #[account(
+        init,
+        payer = payer,
+        space = OracleEvent::space(&params.description, ""),
+        seeds = [b"event", category_counter.next_event_id()?.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"season", params.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Account<'info, Season>>,
+    /// Kept apart from `payer` so a PDA, e.g. a multisig vault signing
+    /// through CPI, can hold the authority without funding the account.
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64, params: EventParams)]
+pub struct CreateOracleEventWithId<'info> {
+    #[account(
+        seeds = [b"category", [params.category as u8].as_ref()],
+        bump = category_counter.bump
+    )]
+    pub category_counter: Account<'info, CategoryCounter>,
+    #[account(
+        init,
+        payer = payer,
+        space = OracleEvent::space(&params.description, ""),
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"season", params.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Account<'info, Season>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveEvent<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEvent<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        close = authority
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = treasury
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: receives unclaimed stake; checked against `config.treasury`.
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    /// CHECK: the event's AMM pool, which may not exist; only its collateral is swept.
+    #[account(
+        mut,
+        seeds = [b"pool", oracle_event.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEvent<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"bet", player.key().as_ref(), bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"event", bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        mut,
+        seeds = [b"player", player.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    /// With `season`, records the win on the player's season score.
+    #[account(
+        mut,
+        seeds = [
+            b"season_score",
+            oracle_event.season_id.to_le_bytes().as_ref(),
+            player.key().as_ref()
+        ],
+        bump = season_score.bump
+    )]
+    pub season_score: Option<Account<'info, SeasonScore>>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    /// Required when the player was referred.
+    #[account(
+        mut,
+        seeds = [b"referral", referrer_rewards.referrer.as_ref()],
+        bump = referrer_rewards.bump
+    )]
+    pub referrer_rewards: Option<Account<'info, ReferrerRewards>>,
+    /// CHECK: the bettor, who receives the winnings; must be the signer or
+    /// the owner of `session_token`.
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+    /// Required when a session key signs for `player`.
+    #[account(
+        seeds = [b"session", player.key().as_ref(), signer.key().as_ref()],
+        bump = session_token.bump
+    )]
+    pub session_token: Option<Account<'info, SessionToken>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// The event's season, whose window bounds the score update. Last so
+    /// callers built before it existed keep their account order.
+    #[account(
+        seeds = [b"season", oracle_event.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Option<Account<'info, Season>>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SessionToken::INIT_SPACE,
+        seeds = [b"session", owner.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session_token: Account<'info, SessionToken>,
+    /// CHECK: the ephemeral key, funded with `fee_budget`.
+    #[account(mut, address = session_key)]
+    pub session_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"session", owner.key().as_ref(), session_token.session_key.as_ref()],
+        bump = session_token.bump,
+        has_one = owner
+    )]
+    pub session_token: Account<'info, SessionToken>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(guild_id: u64)]
+pub struct CreateGuild<'info> {
+    #[account(
+        init,
+        payer = founder,
+        space = 8 + Guild::INIT_SPACE,
+        seeds = [b"guild", founder.key().as_ref(), guild_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub guild: Account<'info, Guild>,
+    #[account(mut)]
+    pub founder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageGuild<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild", guild.founder.as_ref(), guild.guild_id.to_le_bytes().as_ref()],
+        bump = guild.bump
+    )]
+    pub guild: Account<'info, Guild>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct GuildPlaceBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild", guild.founder.as_ref(), guild.guild_id.to_le_bytes().as_ref()],
+        bump = guild.bump
+    )]
+    pub guild: Account<'info, Guild>,
+    #[account(
+        init,
+        payer = manager,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", guild.key().as_ref(), event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GuildSettleBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"guild", guild.founder.as_ref(), guild.guild_id.to_le_bytes().as_ref()],
+        bump = guild.bump
+    )]
+    pub guild: Account<'info, Guild>,
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"bet", guild.key().as_ref(), bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    /// CHECK: the bet's event, which may already be closed; deserialized in
+    /// the handler when it still exists.
+    #[account(
+        mut,
+        seeds = [b"event", bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QuotePayout<'info> {
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct GetEventSummary<'info> {
+    pub oracle_event: Account<'info, OracleEvent>,
+}
+
+#[derive(Accounts)]
+pub struct GetPlayerSummary<'info> {
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct CrankPayouts<'info> {
+    #[account(
+        mut,
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(
+        mut,
+        seeds = [b"player", signer.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    /// Required when the player was referred.
+    #[account(
+        mut,
+        seeds = [b"referral", referrer_rewards.referrer.as_ref()],
+        bump = referrer_rewards.bump
+    )]
+    pub referrer_rewards: Option<Account<'info, ReferrerRewards>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + ReferrerRewards::INIT_SPACE,
+        seeds = [b"referral", signer.key().as_ref()],
+        bump
+    )]
+    pub referrer_rewards: Account<'info, ReferrerRewards>,
+    #[account(
+        seeds = [b"player", signer.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"referral", referrer.key().as_ref()],
+        bump = referrer_rewards.bump,
+        has_one = referrer
+    )]
+    pub referrer_rewards: Account<'info, ReferrerRewards>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBet<'info> {
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"bet", signer.key().as_ref(), bet.event_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = bet.player == signer.key() @ ErrorCode::NotBetOwner
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"player", signer.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    /// CHECK: the bet's event, only checked for having been closed.
+    #[account(
+        seeds = [b"event", bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_id: u64)]
+pub struct CreateSeason<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Season::INIT_SPACE,
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"season", season.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(season_id: u64)]
+pub struct JoinSeason<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + SeasonScore::INIT_SPACE,
+        seeds = [b"season_score", season_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub season_score: Account<'info, SeasonScore>,
+    #[account(
+        seeds = [b"season", season_id.to_le_bytes().as_ref()],
+        bump = season.bump,
+        constraint = !season.finalized @ ErrorCode::SeasonFinalized
+    )]
+    pub season: Account<'info, Season>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"season", season.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSeasonPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"season", season.season_id.to_le_bytes().as_ref()],
+        bump = season.bump
+    )]
+    pub season: Account<'info, Season>,
+    /// Created on the first claim, so a second claim fails.
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + SeasonPrizeClaim::INIT_SPACE,
+        seeds = [b"season_claim", season.season_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub prize_claim: Account<'info, SeasonPrizeClaim>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePlayerProfile<'info> {
+    /// CHECK: legacy layout that may not deserialize as `PlayerProfile`;
+    /// the discriminator and layout version are checked in the instruction
+    #[account(
+        mut,
+        seeds = [b"player", signer.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub player_profile: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: legacy layout that may not deserialize as the current type;
+    /// the discriminator is checked in the instruction
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct CreatePool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AmmPool::INIT_SPACE,
+        seeds = [b"pool", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, AmmPool>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AmmPosition::INIT_SPACE,
+        seeds = [b"position", authority.key().as_ref(), event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, AmmPosition>,
+    #[account(
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority,
+        constraint = !oracle_event.resolved @ ErrorCode::AlreadyResolved
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct TradePool<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", event_id.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, AmmPool>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + AmmPosition::INIT_SPACE,
+        seeds = [b"position", signer.key().as_ref(), event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, AmmPosition>,
+    #[account(
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = !oracle_event.resolved @ ErrorCode::AlreadyResolved,
+        constraint = !oracle_event.cancelled @ ErrorCode::EventCancelled
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", position.event_id.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, AmmPool>,
+    #[account(
+        mut,
+        seeds = [b"position", signer.key().as_ref(), position.event_id.to_le_bytes().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, AmmPosition>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemOutcomeShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", position.event_id.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, AmmPool>,
+    #[account(
+        mut,
+        seeds = [b"position", signer.key().as_ref(), position.event_id.to_le_bytes().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, AmmPosition>,
+    #[account(
+        seeds = [b"event", position.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct CreateOrderBook<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + OrderBook::INIT_SPACE,
+        seeds = [b"book", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = !oracle_event.resolved @ ErrorCode::AlreadyResolved
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct PlaceOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"book", event_id.to_le_bytes().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [b"bet", signer.key().as_ref(), event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = !oracle_event.resolved @ ErrorCode::AlreadyResolved,
+        constraint = !oracle_event.cancelled @ ErrorCode::EventCancelled
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"book", order_book.event_id.to_le_bytes().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    /// CHECK: the signer's bet PDA, which may already be closed; parsed as a
+    /// `Bet` when an ask's stake is returned to it
+    #[account(
+        mut,
+        seeds = [b"bet", signer.key().as_ref(), order_book.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: UncheckedAccount<'info>,
+    /// CHECK: the book's event PDA, which may already be closed; parsed as
+    /// an `OracleEvent` when a cancelled event pays back an ask's stake
+    #[account(
+        mut,
+        seeds = [b"event", order_book.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CrankMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"book", order_book.event_id.to_le_bytes().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        seeds = [b"event", order_book.event_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = !oracle_event.resolved @ ErrorCode::AlreadyResolved,
+        constraint = !oracle_event.cancelled @ ErrorCode::EventCancelled
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    /// Only the upgrade authority may claim the admin role, so deploying
+    /// and initializing do not race.
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SimpleOracleQuest>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+        has_one = treasury
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only receives lamports, matched against `config.treasury`
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CashOut<'info> {
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"bet", signer.key().as_ref(), bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"event", bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        mut,
+        seeds = [b"player", signer.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawHouse<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+        has_one = treasury
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only receives lamports, matched against `config.treasury`
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct SetLegOdds<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + LegOdds::INIT_SPACE,
+        seeds = [b"odds", event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub leg_odds: Account<'info, LegOdds>,
+    #[account(
+        seeds = [b"event", event_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = !oracle_event.resolved @ ErrorCode::AlreadyResolved,
+        constraint = !oracle_event.cancelled @ ErrorCode::EventCancelled
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundHouse<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"bet", signer.key().as_ref(), bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    #[account(
+        mut,
+        seeds = [b"event", bet.event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub oracle_event: Account<'info, OracleEvent>,
+    #[account(
+        mut,
+        seeds = [b"player", signer.key().as_ref()],
+        bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(parlay_id: u64)]
+pub struct PlaceParlay<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + Parlay::INIT_SPACE,
+        seeds = [b"parlay", signer.key().as_ref(), parlay_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimParlay<'info> {
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"parlay", signer.key().as_ref(), parlay.parlay_id.to_le_bytes().as_ref()],
+        bump = parlay.bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct PlayerProfile {
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub total_bets: u64,
+    pub bets_won: u64,
+    /// Stake currently locked in unsettled bets across all events.
+    pub open_exposure: u64,
+    pub version: u8,
+    pub xp: u64,
+    pub level: u32,
+    pub bets_lost: u64,
+    pub win_streak: u32,
+    pub loss_streak: u32,
+    pub best_win_streak: u32,
+    /// Share of settled bets that were won, in bps.
+    pub accuracy_bps: u16,
+    /// Player whose link this profile signed up through.
+    pub referrer: Option<Pubkey>,
+}
+
+/// `PlayerProfile` layout before versioning was introduced.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PlayerProfileV0 {
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub total_bets: u64,
+    pub bets_won: u64,
+}
+
+impl PlayerProfileV0 {
+    /// Account length including the discriminator.
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8;
+}
+
+impl From<PlayerProfileV0> for PlayerProfile {
+    fn from(legacy: PlayerProfileV0) -> Self {
+        let xp = legacy
+            .total_bets
+            .saturating_mul(XP_PER_BET)
+            .saturating_add(legacy.bets_won.saturating_mul(XP_PER_WIN));
+        Self {
+            owner: legacy.owner,
+            balance: legacy.balance,
+            total_bets: legacy.total_bets,
+            bets_won: legacy.bets_won,
+            version: PLAYER_PROFILE_VERSION,
+            xp,
+            level: level_for_xp(xp),
+            ..Default::default()
+        }
+    }
+}
+
+/// Level reached with `xp`, i.e. the largest `n` with `XP_LEVEL_BASE * n^2 <= xp`.
+pub fn level_for_xp(xp: u64) -> u32 {
+    (xp / XP_LEVEL_BASE).isqrt() as u32
+}
+
+/// XP needed to reach `level`.
+pub fn xp_for_level(level: u32) -> u64 {
+    XP_LEVEL_BASE.saturating_mul((level as u64).saturating_mul(level as u64))
+}
+
+impl PlayerProfile {
+    pub fn add_xp(&mut self, xp: u64) -> Result<()> {
+        self.xp = self.xp.checked_add(xp).ok_or(ErrorCode::MathOverflow)?;
+        self.level = level_for_xp(self.xp);
+        Ok(())
+    }
+
+    pub fn record_win(&mut self) -> Result<()> {
+        self.bets_won = self
+            .bets_won
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.win_streak = self.win_streak.saturating_add(1);
+        self.loss_streak = 0;
+        self.best_win_streak = self.best_win_streak.max(self.win_streak);
+
+        let streak_bonus = XP_STREAK_BONUS * (self.win_streak as u64).min(MAX_STREAK_BONUS_WINS);
+        self.refresh_accuracy();
+        self.add_xp(XP_PER_WIN + streak_bonus)
+    }
+
+    pub fn record_loss(&mut self) -> Result<()> {
+        self.bets_lost = self
+            .bets_lost
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.loss_streak = self.loss_streak.saturating_add(1);
+        self.win_streak = 0;
+        self.refresh_accuracy();
+        self.add_xp(XP_PER_LOSS)
+    }
+
+    fn refresh_accuracy(&mut self) {
+        let settled = self.bets_won as u128 + self.bets_lost as u128;
+        self.accuracy_bps = (self.bets_won as u128 * BPS_DENOMINATOR as u128)
+            .checked_div(settled)
+            .unwrap_or(0) as u16;
+    }
+
+    pub fn open_exposure(&mut self, amount: u64, max_exposure: u64) -> Result<()> {
+        let exposure = self
+            .open_exposure
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(exposure <= max_exposure, ErrorCode::ExposureLimitExceeded);
+        self.open_exposure = exposure;
+        Ok(())
+    }
+
+    /// Saturates because bets placed before exposure was tracked never
+    /// opened any.
+    pub fn release_exposure(&mut self, amount: u64) {
+        self.open_exposure = self.open_exposure.saturating_sub(amount);
+    }
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Bet {
+    pub player: Pubkey,
+    pub event_id: u64,
+    pub chosen_outcome: bool,
+    pub amount: u64,
+    pub claimed: bool,
+    /// Set once the result has been recorded on the player's profile.
+    pub settled: bool,
+    pub version: u8,
+}
+
+/// `Bet` layout before versioning was introduced.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BetV0 {
+    pub player: Pubkey,
+    pub event_id: u64,
+    pub chosen_outcome: bool,
+    pub amount: u64,
+    pub claimed: bool,
+}
+
+impl BetV0 {
+    /// Account length including the discriminator. Legacy bets were sized
+    /// from the legacy `PlayerProfile`.
+    pub const LEN: usize = PlayerProfileV0::LEN;
+}
+
+impl From<BetV0> for Bet {
+    fn from(legacy: BetV0) -> Self {
+        Self {
+            player: legacy.player,
+            event_id: legacy.event_id,
+            chosen_outcome: legacy.chosen_outcome,
+            amount: legacy.amount,
+            claimed: legacy.claimed,
+            settled: legacy.claimed,
+            version: BET_VERSION,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct OracleEvent {
+    // Fixed-size fields come first so they sit at the `EVENT_*_OFFSET`s.
+    pub event_id: u64,
+    pub category: EventCategory,
+    /// Creator-chosen tag ids, sorted with empty slots last.
+    pub tags: [u8; MAX_EVENT_TAGS],
+    pub resolved: bool,
+    pub cancelled: bool,
+    pub authority: Pubkey,
+    pub total_bets: u64,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub total_amount: u64,
+    /// Lamports staked on YES, held in this account until claimed.
+    pub yes_amount: u64,
+    /// Lamports staked on NO, held in this account until claimed.
+    pub no_amount: u64,
+    pub min_stake: u64,
+    pub max_stake: u64,
+    /// Season this event counts towards, or 0 for none.
+    pub season_id: u64,
+    /// sha256 of the document at `metadata_uri`.
+    pub metadata_hash: [u8; 32],
+    /// Bets still owed a payout or refund once resolved or cancelled.
+    pub outstanding_claims: u64,
+    /// Legs of unclaimed parlays on this event; it cannot close until zero.
+    pub open_parlay_legs: u64,
+    /// When the event was resolved or cancelled; starts the close grace period.
+    pub resolved_at: i64,
+    pub outcome: Option<bool>,
+    #[max_len(200)]
+    pub description: String,
+    /// Off-chain JSON with the full rules, resolution sources and artwork.
+    #[max_len(200)]
+    pub metadata_uri: String,
+    pub version: u8,
+}
+
+/// `OracleEvent` layout before versioning was introduced.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct OracleEventV0 {
+    pub event_id: u64,
+    pub description: String,
+    pub resolved: bool,
+    pub outcome: Option<bool>,
+    pub authority: Pubkey,
+    pub total_bets: u64,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub total_amount: u64,
+}
+
+impl OracleEventV0 {
+    /// Account length including the discriminator.
+    pub const LEN: usize = 8 + 8 + (4 + 200) + 1 + 2 + 32 + 8 + 8 + 8 + 8;
+}
+
+impl From<OracleEventV0> for OracleEvent {
+    /// Legacy events held no stake, so the pot and per-side pools start
+    /// empty, any stake is accepted and they land in `General` with no tags.
+    /// Which bets were already paid is unknown, so every bet that could
+    /// still claim counts as outstanding and such events close only after
+    /// the grace period.
+    fn from(legacy: OracleEventV0) -> Self {
+        let outstanding_claims = match legacy.outcome {
+            Some(true) => legacy.yes_votes,
+            Some(false) => legacy.no_votes,
+            None => 0,
+        };
+        Self {
+            event_id: legacy.event_id,
+            description: legacy.description,
+            resolved: legacy.resolved,
+            outcome: legacy.outcome,
+            authority: legacy.authority,
+            total_bets: legacy.total_bets,
+            yes_votes: legacy.yes_votes,
+            no_votes: legacy.no_votes,
+            min_stake: 1,
+            max_stake: u64::MAX,
+            outstanding_claims,
+            version: ORACLE_EVENT_VERSION,
+            ..Default::default()
+        }
+    }
+}
+
+impl OracleEvent {
+    /// Account length for an event with `description`, including the
+    /// discriminator.
+    pub fn space(description: &str, metadata_uri: &str) -> usize {
+        8 + Self::INIT_SPACE - MAX_DESCRIPTION_LEN - MAX_METADATA_URI_LEN
+            + description.len()
+            + metadata_uri.len()
+    }
+
+    pub fn check_stake(&self, amount: u64) -> Result<()> {
+        require!(amount >= self.min_stake, ErrorCode::StakeBelowMinimum);
+        require!(amount <= self.max_stake, ErrorCode::StakeAboveMaximum);
+        Ok(())
+    }
+
+    /// Counts a new bet of `amount` on `outcome`.
+    pub fn record_bet(&mut self, outcome: bool, amount: u64) -> Result<()> {
+        self.count_bet(outcome)?;
+        self.total_amount = self
+            .total_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.add_stake(outcome, amount)
+    }
+
+    /// Counts a new bet on `outcome` without adding stake, for bets whose
+    /// stake comes from another bet, e.g. through the order book.
+    pub fn count_bet(&mut self, outcome: bool) -> Result<()> {
+        self.total_bets = self
+            .total_bets
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let votes = if outcome {
+            &mut self.yes_votes
+        } else {
+            &mut self.no_votes
+        };
+        *votes = votes.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_stake(&mut self, outcome: bool, amount: u64) -> Result<()> {
+        let side = if outcome {
+            &mut self.yes_amount
+        } else {
+            &mut self.no_amount
+        };
+        *side = side.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn remove_stake(&mut self, outcome: bool, amount: u64) -> Result<()> {
+        let side = if outcome {
+            &mut self.yes_amount
+        } else {
+            &mut self.no_amount
+        };
+        *side = side.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Decimal odds in bps currently implied by the parimutuel split. Only
+    /// informational: parlays lock the admin-set `LegOdds` instead, since
+    /// the split moves with every bet.
+    pub fn leg_odds_bps(&self, outcome: bool) -> u64 {
+        let side_amount = self.stake_on(outcome) as u128;
+        if side_amount == 0 {
+            return DEFAULT_LEG_ODDS_BPS;
+        }
+        let odds = self.total_amount as u128 * BPS_DENOMINATOR as u128 / side_amount;
+        (odds as u64).clamp(BPS_DENOMINATOR, MAX_LEG_ODDS_BPS)
+    }
+
+    pub fn stake_on(&self, outcome: bool) -> u64 {
+        if outcome {
+            self.yes_amount
+        } else {
+            self.no_amount
+        }
+    }
+
+    /// Prices a new bet of `amount` on `outcome` against the pool it would
+    /// join, as if the event resolved that way right after.
+    pub fn quote_payout(
+        &self,
+        outcome: bool,
+        amount: u64,
+        protocol_fee_bps: u16,
+    ) -> Result<PayoutQuote> {
+        require!(!self.resolved, ErrorCode::AlreadyResolved);
+        require!(!self.cancelled, ErrorCode::EventCancelled);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let side_amount = self.stake_on(outcome) as u128 + amount as u128;
+        let total_amount = self.total_amount as u128 + amount as u128;
+        let denominator = BPS_DENOMINATOR as u128;
+        let gross_payout = u64::try_from(amount as u128 * total_amount / side_amount)
+            .map_err(|_| error!(ErrorCode::MathOverflow))?;
+        // A pot out of step with its side stakes must not underflow the profit
+        let (protocol_fee, _) =
+            split_protocol_fee(gross_payout.saturating_sub(amount), protocol_fee_bps, 0)?;
+
+        Ok(PayoutQuote {
+            event_id: self.event_id,
+            chosen_outcome: outcome,
+            amount,
+            implied_probability_bps: (side_amount * denominator / total_amount) as u64,
+            odds_bps: (total_amount * denominator / side_amount) as u64,
+            gross_payout,
+            protocol_fee,
+            potential_payout: gross_payout.saturating_sub(protocol_fee),
+        })
+    }
+
+    pub fn summary(&self) -> EventSummary {
+        let yes_probability_bps = (self.yes_amount as u128 * BPS_DENOMINATOR as u128)
+            .checked_div(self.total_amount as u128)
+            .unwrap_or(BPS_DENOMINATOR as u128 / 2) as u64;
+        EventSummary {
+            event_id: self.event_id,
+            category: self.category,
+            resolved: self.resolved,
+            cancelled: self.cancelled,
+            outcome: self.outcome,
+            total_bets: self.total_bets,
+            total_amount: self.total_amount,
+            yes_amount: self.yes_amount,
+            no_amount: self.no_amount,
+            yes_probability_bps,
+            yes_odds_bps: self.leg_odds_bps(true),
+            no_odds_bps: self.leg_odds_bps(false),
+            outstanding_claims: self.outstanding_claims,
+        }
+    }
+
+    /// Share of the whole pot owed to a winning `stake` once resolved.
+    pub fn parimutuel_payout(&self, stake: u64) -> Result<u64> {
+        let winning_amount = match self.outcome {
+            Some(true) => self.yes_amount,
+            Some(false) => self.no_amount,
+            None => return err!(ErrorCode::EventNotResolved),
+        };
+        if winning_amount == 0 {
+            return Ok(0);
+        }
+        let payout = stake as u128 * self.total_amount as u128 / winning_amount as u128;
+        u64::try_from(payout).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// Constant-product pool of YES/NO outcome shares for one `OracleEvent`.
+///
+/// Every lamport deposited mints one complete set (one YES + one NO share).
+/// The implied YES probability is `no_reserve / (yes_reserve + no_reserve)`,
+/// so clients can read live odds straight from the account data.
+#[account]
+#[derive(InitSpace)]
+pub struct AmmPool {
+    pub event_id: u64,
+    pub yes_reserve: u64,
+    pub no_reserve: u64,
+    pub total_lp_shares: u64,
+    pub fee_bps: u16,
+    /// Lamports backing outstanding complete sets.
+    pub collateral: u64,
+    /// Swap fees accrued per LP share, scaled by `FEE_PER_SHARE_SCALE`.
+    pub acc_fee_per_share: u128,
+    pub bump: u8,
+}
+
+impl AmmPool {
+    pub fn implied_yes_probability_bps(&self) -> u64 {
+        let total = self.yes_reserve as u128 + self.no_reserve as u128;
+        if total == 0 {
+            return BPS_DENOMINATOR / 2;
+        }
+        (self.no_reserve as u128 * BPS_DENOMINATOR as u128 / total) as u64
+    }
+
+    /// Shares received for `amount` lamports, without mutating the pool.
+    pub fn quote_buy(&self, outcome: bool, amount: u64) -> Option<(u64, u64, u64)> {
+        let fee = (amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let net = amount - fee;
+        let (buy_reserve, sell_reserve) = if outcome {
+            (self.yes_reserve, self.no_reserve)
+        } else {
+            (self.no_reserve, self.yes_reserve)
+        };
+        if buy_reserve == 0 || sell_reserve == 0 {
+            return None;
+        }
+
+        let invariant = buy_reserve as u128 * sell_reserve as u128;
+        let new_sell_reserve = sell_reserve as u128 + net as u128;
+        // Round the remaining reserve up so the invariant never decreases.
+        let new_buy_reserve = invariant.div_ceil(new_sell_reserve);
+        let shares_out = (buy_reserve as u128 + net as u128).checked_sub(new_buy_reserve)?;
+
+        Some((
+            u64::try_from(shares_out).ok()?,
+            u64::try_from(new_buy_reserve).ok()?,
+            fee,
+        ))
+    }
+
+    /// Mints a complete set with `amount` minus fees and swaps out the unwanted side.
+    pub fn buy(&mut self, outcome: bool, amount: u64) -> Result<u64> {
+        let (shares_out, new_buy_reserve, fee) = self
+            .quote_buy(outcome, amount)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        let net = amount - fee;
+
+        if outcome {
+            self.yes_reserve = new_buy_reserve;
+            self.no_reserve = self
+                .no_reserve
+                .checked_add(net)
+                .ok_or(ErrorCode::MathOverflow)?;
+        } else {
+            self.no_reserve = new_buy_reserve;
+            self.yes_reserve = self
+                .yes_reserve
+                .checked_add(net)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        self.collateral = self
+            .collateral
+            .checked_add(net)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.accrue_fee(fee)?;
+        Ok(shares_out)
+    }
+
+    /// Adds `amount` lamports of liquidity, returning the LP shares minted.
+    ///
+    /// When the pool is unbalanced only the proportional part of each side
+    /// goes into the reserves; the excess is handed to the provider as
+    /// outcome shares so the price is left unchanged.
+    pub fn add_liquidity(&mut self, position: &mut AmmPosition, amount: u64) -> Result<u64> {
+        let minted = if self.total_lp_shares == 0 {
+            self.yes_reserve = amount;
+            self.no_reserve = amount;
+            amount
+        } else {
+            let pool_weight = self.yes_reserve.max(self.no_reserve) as u128;
+            let yes_added = (amount as u128 * self.yes_reserve as u128 / pool_weight) as u64;
+            let no_added = (amount as u128 * self.no_reserve as u128 / pool_weight) as u64;
+            let minted = (amount as u128 * self.total_lp_shares as u128 / pool_weight) as u64;
+
+            self.yes_reserve = self
+                .yes_reserve
+                .checked_add(yes_added)
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.no_reserve = self
+                .no_reserve
+                .checked_add(no_added)
+                .ok_or(ErrorCode::MathOverflow)?;
+            position.credit_shares(true, amount - yes_added)?;
+            position.credit_shares(false, amount - no_added)?;
+            minted
+        };
+        require!(minted > 0, ErrorCode::InvalidAmount);
+
+        self.total_lp_shares = self
+            .total_lp_shares
+            .checked_add(minted)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.collateral = self
+            .collateral
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        position.lp_shares = position
+            .lp_shares
+            .checked_add(minted)
+            .ok_or(ErrorCode::MathOverflow)?;
+        position.fee_debt = self.fee_debt_for(position.lp_shares)?;
+        Ok(minted)
+    }
+
+    /// Burns `lp_shares`, returning the lamports released by merging complete sets.
+    ///
+    /// The unmatched remainder of the withdrawn reserves is credited to the
+    /// position as outcome shares.
+    pub fn remove_liquidity(&mut self, position: &mut AmmPosition, lp_shares: u64) -> Result<u64> {
+        require!(
+            position.lp_shares >= lp_shares,
+            ErrorCode::InsufficientShares
+        );
+
+        let total = self.total_lp_shares as u128;
+        let yes_out = (self.yes_reserve as u128 * lp_shares as u128 / total) as u64;
+        let no_out = (self.no_reserve as u128 * lp_shares as u128 / total) as u64;
+        let merged = yes_out.min(no_out);
+
+        self.yes_reserve -= yes_out;
+        self.no_reserve -= no_out;
+        self.total_lp_shares -= lp_shares;
+        self.collateral = self
+            .collateral
+            .checked_sub(merged)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+
+        position.lp_shares -= lp_shares;
+        position.fee_debt = self.fee_debt_for(position.lp_shares)?;
+        position.credit_shares(true, yes_out - merged)?;
+        position.credit_shares(false, no_out - merged)?;
+        Ok(merged)
+    }
+
+    /// Returns the fees owed to `position` and resets its fee checkpoint.
+    pub fn settle_fees(&self, position: &mut AmmPosition) -> Result<u64> {
+        let accrued = self.fee_debt_for(position.lp_shares)?;
+        let pending = accrued.saturating_sub(position.fee_debt) / FEE_PER_SHARE_SCALE;
+        position.fee_debt = accrued;
+        u64::try_from(pending).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    fn accrue_fee(&mut self, fee: u64) -> Result<()> {
+        if fee == 0 || self.total_lp_shares == 0 {
+            return Ok(());
+        }
+        self.acc_fee_per_share = self
+            .acc_fee_per_share
+            .checked_add(fee as u128 * FEE_PER_SHARE_SCALE / self.total_lp_shares as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    fn fee_debt_for(&self, lp_shares: u64) -> Result<u128> {
+        self.acc_fee_per_share
+            .checked_mul(lp_shares as u128)
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// A player's outcome shares and LP stake in one event's `AmmPool`.
+#[account]
+#[derive(InitSpace)]
+pub struct AmmPosition {
+    pub owner: Pubkey,
+    pub event_id: u64,
+    pub yes_shares: u64,
+    pub no_shares: u64,
+    pub lp_shares: u64,
+    /// `acc_fee_per_share * lp_shares` at the last fee settlement.
+    pub fee_debt: u128,
+    pub bump: u8,
+}
+
+impl AmmPosition {
+    fn init_if_empty(&mut self, owner: Pubkey, event_id: u64, bump: u8) {
+        if self.owner == Pubkey::default() {
+            self.owner = owner;
+            self.event_id = event_id;
+            self.bump = bump;
+        }
+    }
+
+    /// Lamports the position's outcome shares redeem for. After resolution
+    /// each winning share is worth one lamport. On a cancelled event every
+    /// lamport of collateral backs one YES and one NO share, so each share
+    /// is worth half a lamport.
+    pub fn redemption_value(&self, event: &OracleEvent) -> Result<u64> {
+        if event.cancelled {
+            let shares = self.yes_shares as u128 + self.no_shares as u128;
+            return Ok((shares / 2) as u64);
+        }
+        require!(event.resolved, ErrorCode::EventNotResolved);
+        match event.outcome {
+            Some(true) => Ok(self.yes_shares),
+            Some(false) => Ok(self.no_shares),
+            None => err!(ErrorCode::EventNotResolved),
+        }
+    }
+
+    pub fn credit_shares(&mut self, outcome: bool, shares: u64) -> Result<()> {
+        let balance = if outcome {
+            &mut self.yes_shares
+        } else {
+            &mut self.no_shares
+        };
+        *balance = balance.checked_add(shares).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Global protocol settings. The account itself holds collected fees and
+/// the house bankroll that backs parlays.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub cash_out_fee_bps: u16,
+    /// Fees held by this account and not yet withdrawn to the treasury.
+    pub fees_accrued: u64,
+    /// Lamports held by this account to pay parlays.
+    pub house_liquidity: u64,
+    /// Sum of potential payouts of open parlays.
+    pub house_exposure: u64,
+    pub max_house_exposure: u64,
+    /// Cap on a single player's `open_exposure` across events.
+    pub max_player_exposure: u64,
+    /// Fee taken on the profit of winning bets.
+    pub protocol_fee_bps: u16,
+    /// Part of the protocol fee paid to the winner's referrer.
+    pub referral_share_bps: u16,
+    /// Lets the admin create events under explicit legacy ids.
+    pub allow_explicit_event_ids: bool,
+    /// Paid for each bet settled by `crank_payouts`, up to the fees collected.
+    pub crank_bounty: u64,
+    pub bump: u8,
+}
+
+impl Config {
+    pub fn apply(&mut self, params: ConfigParams) -> Result<()> {
+        require!(
+            params.cash_out_fee_bps <= MAX_CASH_OUT_FEE_BPS,
+            ErrorCode::FeeTooHigh
+        );
+        require!(
+            params.protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            ErrorCode::FeeTooHigh
+        );
+        require!(
+            params.referral_share_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::FeeTooHigh
+        );
+        self.treasury = params.treasury;
+        self.cash_out_fee_bps = params.cash_out_fee_bps;
+        self.max_house_exposure = params.max_house_exposure;
+        self.max_player_exposure = params.max_player_exposure;
+        self.protocol_fee_bps = params.protocol_fee_bps;
+        self.referral_share_bps = params.referral_share_bps;
+        self.allow_explicit_event_ids = params.allow_explicit_event_ids;
+        self.crank_bounty = params.crank_bounty;
+        Ok(())
+    }
+}
+
+/// Admin-settable fields of `Config`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConfigParams {
+    pub treasury: Pubkey,
+    pub cash_out_fee_bps: u16,
+    pub max_house_exposure: u64,
+    pub max_player_exposure: u64,
+    pub protocol_fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub allow_explicit_event_ids: bool,
+    pub crank_bounty: u64,
+}
+
+/// Creator-supplied fields of a new `OracleEvent`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EventParams {
+    pub category: EventCategory,
+    pub tags: [u8; MAX_EVENT_TAGS],
+    pub description: String,
+    pub min_stake: u64,
+    pub max_stake: u64,
+    /// Season the event counts towards, or 0 for none.
+    pub season_id: u64,
+}
+
+/// Referral rewards owed to one referrer. The account itself holds the
+/// unclaimed lamports.
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerRewards {
+    pub referrer: Pubkey,
+    pub referred_players: u64,
+    /// Lamports held by this account and not yet claimed.
+    pub accrued: u64,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+impl ReferrerRewards {
+    pub fn credit(&mut self, amount: u64) -> Result<()> {
+        self.accrued = self
+            .accrued
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.total_earned = self
+            .total_earned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub enum EventCategory {
+    #[default]
+    General,
+    Sports,
+    Crypto,
+    Politics,
+    Entertainment,
+    Gaming,
+}
+
+/// Allocates event ids within one category.
+#[account]
+#[derive(InitSpace)]
+pub struct CategoryCounter {
+    pub category: EventCategory,
+    /// Sequence numbers below this are reserved for explicit legacy ids.
+    pub start_seq: u64,
+    pub next_seq: u64,
+    pub bump: u8,
+}
+
+impl CategoryCounter {
+    pub fn next_event_id(&self) -> Result<u64> {
+        category_event_id(self.category, self.next_seq)
+    }
+
+    /// Hands out the next event id and advances the counter.
+    pub fn allocate(&mut self) -> Result<u64> {
+        let event_id = self.next_event_id()?;
+        self.next_seq += 1;
+        Ok(event_id)
+    }
+
+    /// Accepts an explicit id only if it falls in this category's reserved
+    /// range, so the allocator can never collide with it.
+    pub fn check_explicit(&self, event_id: u64) -> Result<()> {
+        require!(
+            event_id >> CATEGORY_SEQ_BITS == self.category as u64
+                && event_id & ((1 << CATEGORY_SEQ_BITS) - 1) < self.start_seq,
+            ErrorCode::ExplicitEventIdOutOfRange
+        );
+        Ok(())
+    }
+}
+
+/// Event id for the `seq`-th event of `category`: the category in the top
+/// byte, the sequence number below it.
+pub fn category_event_id(category: EventCategory, seq: u64) -> Result<u64> {
+    require!(seq < 1 << CATEGORY_SEQ_BITS, ErrorCode::MathOverflow);
+    Ok((category as u64) << CATEGORY_SEQ_BITS | seq)
+}
+
+/// Competitive season with a prize pool paid out against a Merkle root of
+/// final rankings. The account itself holds the prize pool.
+#[account]
+#[derive(InitSpace)]
+pub struct Season {
+    pub season_id: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub prize_pool: u64,
+    pub total_claimed: u64,
+    pub merkle_root: [u8; 32],
+    pub finalized: bool,
+    pub bump: u8,
+}
+
+impl Season {
+    /// Whether `now` falls in `[start_ts, end_ts)`.
+    pub fn is_active(&self, now: i64) -> bool {
+        self.start_ts <= now && now < self.end_ts
+    }
+}
+
+/// A player's running score for one season.
+#[account]
+#[derive(InitSpace)]
+pub struct SeasonScore {
+    pub season_id: u64,
+    pub player: Pubkey,
+    /// Lamports won above stake on the season's events.
+    pub score: u64,
+    pub wins: u64,
+    pub bump: u8,
+}
+
+impl SeasonScore {
+    /// Adds a win claimed at `now`. Wins claimed outside the season's window
+    /// are paid but do not count towards the score.
+    pub fn record_win(&mut self, season: &Season, now: i64, profit: u64) -> Result<()> {
+        if !season.is_active(now) {
+            return Ok(());
+        }
+        self.score = self
+            .score
+            .checked_add(profit)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.wins = self.wins.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Receipt marking a player's season prize as claimed.
+#[account]
+#[derive(InitSpace)]
+pub struct SeasonPrizeClaim {
+    pub season_id: u64,
+    pub player: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ParlayLegInput {
+    pub event_id: u64,
+    pub outcome: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ParlayLeg {
+    pub event_id: u64,
+    pub outcome: bool,
+    /// Decimal odds locked in at placement, in bps (20_000 = 2x).
+    pub odds_bps: u64,
+}
+
+/// Accumulator bet over several events, backed by the house bankroll.
+#[account]
+#[derive(InitSpace)]
+pub struct Parlay {
+    pub player: Pubkey,
+    pub parlay_id: u64,
+    pub stake: u64,
+    pub potential_payout: u64,
+    #[max_len(MAX_PARLAY_LEGS)]
+    pub legs: Vec<ParlayLeg>,
+    pub bump: u8,
+}
+
+/// Odds the house offers on each outcome of an event when it is a parlay
+/// leg, set by the admin.
+#[account]
+#[derive(InitSpace)]
+pub struct LegOdds {
+    pub event_id: u64,
+    pub yes_odds_bps: u64,
+    pub no_odds_bps: u64,
+    pub bump: u8,
+}
+
+impl LegOdds {
+    pub fn set(&mut self, yes_odds_bps: u64, no_odds_bps: u64) -> Result<()> {
+        for odds in [yes_odds_bps, no_odds_bps] {
+            require!(
+                odds > BPS_DENOMINATOR && odds <= MAX_LEG_ODDS_BPS,
+                ErrorCode::InvalidLegOdds
+            );
+        }
+        self.yes_odds_bps = yes_odds_bps;
+        self.no_odds_bps = no_odds_bps;
+        Ok(())
+    }
+
+    pub fn odds_bps(&self, outcome: bool) -> u64 {
+        if outcome {
+            self.yes_odds_bps
+        } else {
+            self.no_odds_bps
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderSide {
+    Bid,
+    Ask,
+}
+
+/// A resting limit order for YES stake, priced in bps of the stake amount.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct Order {
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub price_bps: u16,
+    pub quantity: u64,
+    /// Lamports still escrowed for a bid; always zero for asks.
+    pub escrow: u64,
+}
+
+/// Result of matching the best bid against the best ask.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fill {
+    pub bid_id: u64,
+    pub ask_id: u64,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub price_bps: u16,
+    pub quantity: u64,
+    /// Lamports paid to the seller.
+    pub cost: u64,
+    /// Leftover bid escrow returned once the bid is fully filled.
+    pub buyer_refund: u64,
+}
+
+/// Per-event limit order book on YES stake.
+///
+/// Both sides are kept as sorted slabs with the best price first and older
+/// orders ahead of newer ones at the same price.
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBook {
+    pub event_id: u64,
+    pub next_order_id: u64,
+    #[max_len(ORDER_BOOK_DEPTH)]
+    pub bids: Vec<Order>,
+    #[max_len(ORDER_BOOK_DEPTH)]
+    pub asks: Vec<Order>,
+    pub bump: u8,
+}
+
+impl OrderBook {
+    /// Inserts `order` by price-time priority. On a full side it evicts and
+    /// returns the worst-priced order, provided `order` beats its price.
+    pub fn insert(&mut self, side: OrderSide, order: Order) -> Result<Option<Order>> {
+        let orders = match side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        };
+
+        let index = match side {
+            OrderSide::Bid => orders.partition_point(|o| o.price_bps >= order.price_bps),
+            OrderSide::Ask => orders.partition_point(|o| o.price_bps <= order.price_bps),
+        };
+        let evicted = if orders.len() < ORDER_BOOK_DEPTH {
+            None
+        } else {
+            // Sides are sorted best first, so the worst order is last and the
+            // new one only beats it if it would be inserted ahead of it
+            require!(index < orders.len(), ErrorCode::OrderBookFull);
+            orders.pop()
+        };
+        orders.insert(index, order);
+        self.next_order_id = self
+            .next_order_id
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(evicted)
+    }
+
+    pub fn remove(&mut self, side: OrderSide, order_id: u64) -> Result<Order> {
+        let orders = match side {
+            OrderSide::Bid => &mut self.bids,
+            OrderSide::Ask => &mut self.asks,
+        };
+        let index = orders
+            .iter()
+            .position(|o| o.order_id == order_id)
+            .ok_or(ErrorCode::OrderNotFound)?;
+        Ok(orders.remove(index))
+    }
+
+    /// Fills the best bid against the best ask if they cross.
+    pub fn next_fill(&mut self) -> Option<Fill> {
+        let fill = self.peek_fill()?;
+
+        let bid = &mut self.bids[0];
+        bid.quantity -= fill.quantity;
+        bid.escrow -= fill.cost;
+        if bid.quantity == 0 {
+            self.bids.remove(0);
+        }
+        let ask = &mut self.asks[0];
+        ask.quantity -= fill.quantity;
+        if ask.quantity == 0 {
+            self.asks.remove(0);
+        }
+        Some(fill)
+    }
+
+    /// The fill `next_fill` would make, without changing the book.
+    ///
+    /// Trades execute at the resting (older) order's price.
+    pub fn peek_fill(&self) -> Option<Fill> {
+        let bid = self.bids.first()?;
+        let ask = self.asks.first()?;
+        if bid.price_bps < ask.price_bps {
+            return None;
+        }
+
+        let price_bps = if bid.order_id < ask.order_id {
+            bid.price_bps
+        } else {
+            ask.price_bps
+        };
+        let quantity = bid.quantity.min(ask.quantity);
+        let cost = order_cost(quantity, price_bps).ok()?.min(bid.escrow);
+        let buyer_refund = if quantity == bid.quantity {
+            bid.escrow - cost
+        } else {
+            0
+        };
+        Some(Fill {
+            bid_id: bid.order_id,
+            ask_id: ask.order_id,
+            buyer: bid.owner,
+            seller: ask.owner,
+            price_bps,
+            quantity,
+            cost,
+            buyer_refund,
+        })
+    }
 }
 
 #[error_code]
@@ -258,4 +4967,126 @@ pub enum ErrorCode {
     DescriptionTooLong,
     #[msg("Not the bet owner")]
     NotBetOwner,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Pool fee too high (max 1000 bps)")]
+    FeeTooHigh,
+    #[msg("Not enough liquidity in the pool")]
+    InsufficientLiquidity,
+    #[msg("Trade would receive fewer shares than the minimum")]
+    SlippageExceeded,
+    #[msg("Not enough shares in the position")]
+    InsufficientShares,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Price must be between 1 and 9999 bps")]
+    InvalidPrice,
+    #[msg("Order book side is full and the order does not beat its worst price")]
+    OrderBookFull,
+    #[msg("Order not found")]
+    OrderNotFound,
+    #[msg("Not the order owner")]
+    NotOrderOwner,
+    #[msg("Orders trade YES stake but this bet is on NO")]
+    NotYesPosition,
+    #[msg("Missing accounts for a fill")]
+    MissingFillAccounts,
+    #[msg("Fill account does not match the matched order")]
+    FillAccountMismatch,
+    #[msg("No crossing orders to match")]
+    NothingToMatch,
+    #[msg("Event cancelled")]
+    EventCancelled,
+    #[msg("Event not cancelled")]
+    EventNotCancelled,
+    #[msg("Event account does not match the expected event")]
+    EventMismatch,
+    #[msg("Parlay needs 2 to 5 distinct legs with matching event accounts")]
+    InvalidParlayLegs,
+    #[msg("Parlay would exceed the house exposure limit")]
+    HouseExposureExceeded,
+    #[msg("Stake limits must satisfy 0 < min_stake <= max_stake")]
+    InvalidStakeLimits,
+    #[msg("Stake below the event minimum")]
+    StakeBelowMinimum,
+    #[msg("Stake above the event maximum")]
+    StakeAboveMaximum,
+    #[msg("Bet would exceed the player's exposure limit")]
+    ExposureLimitExceeded,
+    #[msg("Bet already settled")]
+    AlreadySettled,
+    #[msg("Bet won, claim the winnings instead")]
+    BetWon,
+    #[msg("Account already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("Account data does not match the expected layout")]
+    InvalidAccountLayout,
+    #[msg("Season id must be non-zero and start before it ends")]
+    InvalidSeason,
+    #[msg("Season account required for seasonal events")]
+    SeasonAccountRequired,
+    #[msg("Season already finalized")]
+    SeasonFinalized,
+    #[msg("Season not finalized yet")]
+    SeasonNotFinalized,
+    #[msg("Season has not ended yet")]
+    SeasonNotEnded,
+    #[msg("Invalid Merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Players cannot refer themselves")]
+    SelfReferral,
+    #[msg("Referrer has no registered rewards account")]
+    ReferrerNotRegistered,
+    #[msg("Event already has bets")]
+    EventHasBets,
+    #[msg("Metadata URI too long")]
+    MetadataUriTooLong,
+    #[msg("Event tags must be unique")]
+    DuplicateTag,
+    #[msg("Explicit event ids are disabled")]
+    ExplicitEventIdsDisabled,
+    #[msg("Explicit event id is outside the category's reserved range")]
+    ExplicitEventIdOutOfRange,
+    #[msg("Winners have not all claimed and the grace period has not passed")]
+    ClaimsOutstanding,
+    #[msg("Bet must be claimed, refunded or settled before closing")]
+    BetNotSettled,
+    #[msg("Claim accounts must be pairs of the signer's bet and its event")]
+    InvalidClaimAccounts,
+    #[msg("Crank accounts must be groups of bet, owner, profile and referrer rewards")]
+    InvalidCrankAccounts,
+    #[msg("Signer is neither the player nor one of their session keys")]
+    UnauthorizedSigner,
+    #[msg("Session scopes or expiry are invalid")]
+    InvalidSession,
+    #[msg("Session has expired")]
+    SessionExpired,
+    #[msg("Session does not allow this instruction")]
+    SessionScopeDenied,
+    #[msg("Session stake budget exceeded")]
+    SessionBudgetExceeded,
+    #[msg("Signer is not a member of this guild")]
+    NotGuildMember,
+    #[msg("Guild role does not allow this instruction")]
+    GuildRoleRequired,
+    #[msg("Guild has no room for more members")]
+    GuildFull,
+    #[msg("Guild role cannot be assigned")]
+    InvalidGuildRole,
+    #[msg("Order notional below the minimum")]
+    OrderTooSmall,
+    #[msg("Account for the evicted order is missing or does not match its owner")]
+    EvictedOrderAccountMismatch,
+    #[msg("Only the program upgrade authority may initialize the config")]
+    NotUpgradeAuthority,
+    #[msg("Leg odds must be above 1x and at most 10x")]
+    InvalidLegOdds,
+    #[msg("No leg odds have been set for this event")]
+    LegOddsNotSet,
+    #[msg("Season is not running")]
+    SeasonNotActive,
+    #[msg("Guild has unsettled bets")]
+    GuildBetsOpen,
+    #[msg("Parlays with a leg on this event are still unclaimed")]
+    ParlayLegsOpen,
 }
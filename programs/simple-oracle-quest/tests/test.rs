@@ -0,0 +1,2329 @@
+#![cfg(feature = "test-sbf")]
+
+use anchor_lang::{
+    solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    system_program, AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator,
+    InstructionData, Space, ToAccountMetas,
+};
+use litesvm::{types::TransactionMetadata, LiteSVM};
+use simple_oracle_quest::{
+    AmmPool, Bet, BetV0, CategoryCounter, Config, ConfigParams, EventCategory, EventParams,
+    EventSummary, Guild, GuildRole, OracleEvent, OracleEventV0, OrderBook, OrderSide,
+    ParlayLegInput, PayoutQuote, PlayerProfile, PlayerProfileV0, PlayerSummary, SessionToken,
+    BET_VERSION, EVENT_CLOSE_GRACE_PERIOD, ORACLE_EVENT_VERSION, ORDER_BOOK_DEPTH,
+    PLAYER_PROFILE_VERSION, SESSION_SCOPE_PLACE_BET,
+};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const PROGRAM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../target/deploy/simple_oracle_quest.so"
+);
+
+fn setup() -> LiteSVM {
+    let mut svm = LiteSVM::new();
+    deploy_upgradeable(&mut svm);
+    svm
+}
+
+fn program_data_pda() -> Pubkey {
+    bpf_loader_upgradeable::get_program_data_address(&simple_oracle_quest::ID)
+}
+
+/// Deploys the program under the upgradeable loader, as on a cluster, so
+/// `initialize_config` has an upgrade authority to check.
+fn deploy_upgradeable(svm: &mut LiteSVM) {
+    let elf = std::fs::read(PROGRAM_PATH).unwrap();
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let mut program_data = Account::new_data_with_space(
+        svm.minimum_balance_for_rent_exemption(metadata_len + elf.len()),
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(Pubkey::default()),
+        },
+        metadata_len + elf.len(),
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program_data.data[metadata_len..].copy_from_slice(&elf);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+
+    let mut program = Account::new_data(
+        svm.minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+        &UpgradeableLoaderState::Program {
+            programdata_address: program_data_pda(),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program.executable = true;
+    svm.set_account(simple_oracle_quest::ID, program).unwrap();
+}
+
+/// Hands the program's upgrade authority to `authority`.
+fn set_upgrade_authority(svm: &mut LiteSVM, authority: &Pubkey) {
+    let mut program_data = svm.get_account(&program_data_pda()).unwrap();
+    let metadata = Account::new_data(
+        0,
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(*authority),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap()
+    .data;
+    program_data.data[..metadata.len()].copy_from_slice(&metadata);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+}
+
+fn funded_keypair(svm: &mut LiteSVM) -> Keypair {
+    let keypair = Keypair::new();
+    svm.airdrop(&keypair.pubkey(), 100_000_000_000).unwrap();
+    keypair
+}
+
+fn try_send(svm: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> Result<(), Vec<String>> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx);
+    svm.expire_blockhash();
+    result.map(|_| ()).map_err(|e| e.meta.logs)
+}
+
+fn send(svm: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> TransactionMetadata {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx);
+    svm.expire_blockhash();
+    result.unwrap_or_else(|e| panic!("transaction failed: {:?}", e.meta.logs))
+}
+
+fn fetch<T: AccountDeserialize>(svm: &LiteSVM, address: &Pubkey) -> T {
+    let account = svm.get_account(address).unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// Writes `legacy` into a program-owned account of `len` bytes, as an older
+/// program version would have left it.
+fn set_legacy_account<T: AnchorSerialize>(
+    svm: &mut LiteSVM,
+    address: Pubkey,
+    discriminator: &[u8],
+    legacy: &T,
+    len: usize,
+) {
+    let mut data = discriminator.to_vec();
+    legacy.serialize(&mut data).unwrap();
+    data.resize(len, 0);
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(len),
+        data,
+        owner: simple_oracle_quest::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(address, account).unwrap();
+}
+
+fn player_pda(player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"player", player.as_ref()], &simple_oracle_quest::ID).0
+}
+
+fn bet_pda(player: &Pubkey, event_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"bet", player.as_ref(), &event_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn event_pda(event_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"event", &event_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn book_pda(event_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"book", &event_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn pool_pda(event_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"pool", &event_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &simple_oracle_quest::ID).0
+}
+
+fn config_params(admin: &Keypair, allow_explicit_event_ids: bool) -> ConfigParams {
+    ConfigParams {
+        treasury: admin.pubkey(),
+        cash_out_fee_bps: 100,
+        max_house_exposure: 1_000_000_000,
+        max_player_exposure: 10_000_000_000,
+        protocol_fee_bps: 200,
+        referral_share_bps: 2_500,
+        allow_explicit_event_ids,
+        crank_bounty: 1_000,
+    }
+}
+
+fn initialize_config_ix(admin: &Keypair) -> Instruction {
+    Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializeConfig {
+            config: config_pda(),
+            program: simple_oracle_quest::ID,
+            program_data: program_data_pda(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeConfig {
+            params: config_params(admin, false),
+        }
+        .data(),
+    }
+}
+
+fn initialize_config(svm: &mut LiteSVM, admin: &Keypair) {
+    set_upgrade_authority(svm, &admin.pubkey());
+    send(svm, initialize_config_ix(admin), admin);
+}
+
+fn initialize_player(svm: &mut LiteSVM, player: &Keypair) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializePlayer {
+            player_profile: player_pda(&player.pubkey()),
+            referrer_rewards: None,
+            signer: player.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializePlayer { referrer: None }.data(),
+    };
+    send(svm, ix, player);
+}
+
+fn category_counter_pda(category: EventCategory) -> Pubkey {
+    Pubkey::find_program_address(&[b"category", &[category as u8]], &simple_oracle_quest::ID).0
+}
+
+fn initialize_category_counter(
+    svm: &mut LiteSVM,
+    admin: &Keypair,
+    category: EventCategory,
+    start_seq: u64,
+) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializeCategoryCounter {
+            category_counter: category_counter_pda(category),
+            config: config_pda(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeCategoryCounter {
+            category,
+            start_seq,
+        }
+        .data(),
+    };
+    send(svm, ix, admin);
+}
+
+fn event_params(category: EventCategory) -> EventParams {
+    EventParams {
+        category,
+        tags: [1, 0, 0, 0],
+        description: "Will the dragon be slain?".to_string(),
+        min_stake: 1_000,
+        max_stake: 1_000_000_000,
+        season_id: 0,
+    }
+}
+
+/// Creates a `General` event, initializing the category counter on first
+/// use, and returns the allocated event id. `authority` must be the config admin.
+fn create_event(svm: &mut LiteSVM, authority: &Keypair) -> u64 {
+    let category = EventCategory::General;
+    let counter = category_counter_pda(category);
+    if svm.get_account(&counter).is_none() {
+        initialize_category_counter(svm, authority, category, 0);
+    }
+    let event_id = fetch::<CategoryCounter>(svm, &counter)
+        .next_event_id()
+        .unwrap();
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateOracleEvent {
+            category_counter: counter,
+            oracle_event: event_pda(event_id),
+            season: None,
+            authority: authority.pubkey(),
+            payer: authority.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateOracleEvent {
+            params: event_params(category),
+        }
+        .data(),
+    };
+    send(svm, ix, authority);
+    event_id
+}
+
+fn place_bet(svm: &mut LiteSVM, player: &Keypair, event_id: u64, outcome: bool, amount: u64) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceBet {
+            bet: bet_pda(&player.pubkey(), event_id),
+            player_profile: player_pda(&player.pubkey()),
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+            player: player.pubkey(),
+            session_token: None,
+            signer: player.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceBet {
+            event_id,
+            chosen_outcome: outcome,
+            amount,
+        }
+        .data(),
+    };
+    send(svm, ix, player);
+}
+
+fn place_order(
+    svm: &mut LiteSVM,
+    player: &Keypair,
+    event_id: u64,
+    side: OrderSide,
+    price_bps: u16,
+    quantity: u64,
+) -> TransactionMetadata {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceOrder {
+            order_book: book_pda(event_id),
+            bet: bet_pda(&player.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            signer: player.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceOrder {
+            event_id,
+            side,
+            price_bps,
+            quantity,
+        }
+        .data(),
+    };
+    send(svm, ix, player)
+}
+
+fn create_order_book(svm: &mut LiteSVM, signer: &Keypair, event_id: u64) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateOrderBook {
+            order_book: book_pda(event_id),
+            oracle_event: event_pda(event_id),
+            signer: signer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateOrderBook { event_id }.data(),
+    };
+    send(svm, ix, signer);
+}
+
+fn cancel_order(
+    svm: &mut LiteSVM,
+    player: &Keypair,
+    event_id: u64,
+    side: OrderSide,
+    order_id: u64,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CancelOrder {
+            order_book: book_pda(event_id),
+            bet: bet_pda(&player.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            signer: player.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CancelOrder { side, order_id }.data(),
+    };
+    try_send(svm, ix, player)
+}
+
+#[test]
+fn test_only_upgrade_authority_initializes_config() {
+    let mut svm = setup();
+    let deployer = funded_keypair(&mut svm);
+    let squatter = funded_keypair(&mut svm);
+    set_upgrade_authority(&mut svm, &deployer.pubkey());
+
+    let logs = try_send(&mut svm, initialize_config_ix(&squatter), &squatter).unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("NotUpgradeAuthority")));
+    assert!(svm.get_account(&config_pda()).is_none());
+
+    send(&mut svm, initialize_config_ix(&deployer), &deployer);
+    let config: simple_oracle_quest::Config = fetch(&svm, &config_pda());
+    assert_eq!(config.admin, deployer.pubkey());
+}
+
+/// Cranks up to `max_fills` steps, all between `buyer` and `seller`.
+fn crank_match_ix(event_id: u64, buyer: &Keypair, seller: &Keypair, max_fills: u8) -> Instruction {
+    let mut accounts = simple_oracle_quest::accounts::CrankMatch {
+        order_book: book_pda(event_id),
+        oracle_event: event_pda(event_id),
+        config: config_pda(),
+    }
+    .to_account_metas(None);
+    for _ in 0..max_fills {
+        accounts.extend([
+            AccountMeta::new(bet_pda(&buyer.pubkey(), event_id), false),
+            AccountMeta::new(player_pda(&buyer.pubkey()), false),
+            AccountMeta::new(buyer.pubkey(), false),
+            AccountMeta::new(player_pda(&seller.pubkey()), false),
+            AccountMeta::new(seller.pubkey(), false),
+        ]);
+    }
+    Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts,
+        data: simple_oracle_quest::instruction::CrankMatch { max_fills }.data(),
+    }
+}
+
+#[test]
+fn test_order_book_compute_units_on_full_book() {
+    let mut svm = setup();
+    let authority = funded_keypair(&mut svm);
+    let buyer = funded_keypair(&mut svm);
+    let seller = funded_keypair(&mut svm);
+    let depth = ORDER_BOOK_DEPTH as u64;
+    // Smallest quantity that clears the minimum notional at every price used
+    let quantity = 10_000_000;
+
+    initialize_config(&mut svm, &authority);
+    let event_id = create_event(&mut svm, &authority);
+    initialize_player(&mut svm, &buyer);
+    initialize_player(&mut svm, &seller);
+    place_bet(&mut svm, &seller, event_id, true, 2 * depth * quantity);
+
+    create_order_book(&mut svm, &authority, event_id);
+
+    // Fill both sides without crossing
+    let mut max_place_cu = 0;
+    for i in 0..depth {
+        let bid = place_order(
+            &mut svm,
+            &buyer,
+            event_id,
+            OrderSide::Bid,
+            1_000 + i as u16,
+            quantity,
+        );
+        let ask = place_order(
+            &mut svm,
+            &seller,
+            event_id,
+            OrderSide::Ask,
+            6_000 + i as u16,
+            quantity,
+        );
+        max_place_cu = max_place_cu
+            .max(bid.compute_units_consumed)
+            .max(ask.compute_units_consumed);
+    }
+    println!("place_order on a full book: {} CU", max_place_cu);
+
+    let book: OrderBook = fetch(&svm, &book_pda(event_id));
+    assert_eq!(book.bids.len(), ORDER_BOOK_DEPTH);
+    assert_eq!(book.asks.len(), ORDER_BOOK_DEPTH);
+
+    // A full side evicts its worst order, refunding the escrow to its owner
+    let worst_bid = book.bids[ORDER_BOOK_DEPTH - 1].clone();
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceOrder {
+            order_book: book_pda(event_id),
+            bet: bet_pda(&buyer.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            signer: buyer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None)
+        .into_iter()
+        .chain([AccountMeta::new(buyer.pubkey(), false)])
+        .collect(),
+        data: simple_oracle_quest::instruction::PlaceOrder {
+            event_id,
+            side: OrderSide::Bid,
+            price_bps: 1_500,
+            quantity,
+        }
+        .data(),
+    };
+    let book_balance = svm.get_account(&book_pda(event_id)).unwrap().lamports;
+    send(&mut svm, ix, &buyer);
+    let book: OrderBook = fetch(&svm, &book_pda(event_id));
+    assert_eq!(book.bids.len(), ORDER_BOOK_DEPTH);
+    assert!(book.bids.iter().all(|o| o.order_id != worst_bid.order_id));
+    assert_eq!(
+        svm.get_account(&book_pda(event_id)).unwrap().lamports,
+        book_balance + 1_500_000 - worst_bid.escrow
+    );
+
+    // Cancel the best ask and replace it with one that crosses every bid
+    let best_ask = book.asks[0].order_id;
+    cancel_order(&mut svm, &seller, event_id, OrderSide::Ask, best_ask).unwrap();
+    place_order(
+        &mut svm,
+        &seller,
+        event_id,
+        OrderSide::Ask,
+        1_000,
+        depth * quantity,
+    );
+
+    let fills_per_crank = 8;
+    let crank = send(
+        &mut svm,
+        crank_match_ix(event_id, &buyer, &seller, fills_per_crank as u8),
+        &authority,
+    );
+    println!(
+        "crank_match with {} fills: {} CU",
+        fills_per_crank, crank.compute_units_consumed
+    );
+
+    let buyer_bet: Bet = fetch(&svm, &bet_pda(&buyer.pubkey(), event_id));
+    assert_eq!(buyer_bet.amount, fills_per_crank * quantity);
+    // Filled stake moves exposure from the seller to the buyer
+    let buyer_profile: PlayerProfile = fetch(&svm, &player_pda(&buyer.pubkey()));
+    assert_eq!(buyer_profile.open_exposure, fills_per_crank * quantity);
+    let seller_profile: PlayerProfile = fetch(&svm, &player_pda(&seller.pubkey()));
+    assert_eq!(
+        seller_profile.open_exposure,
+        (2 * depth - fills_per_crank) * quantity
+    );
+    let book: OrderBook = fetch(&svm, &book_pda(event_id));
+    assert_eq!(book.bids.len(), ORDER_BOOK_DEPTH - fills_per_crank as usize);
+}
+
+#[test]
+fn test_crank_match_drops_bids_over_buyer_limits() {
+    let mut svm = setup();
+    let authority = funded_keypair(&mut svm);
+    let buyer = funded_keypair(&mut svm);
+    let seller = funded_keypair(&mut svm);
+
+    initialize_config(&mut svm, &authority);
+    let event_id = create_event(&mut svm, &authority);
+    initialize_player(&mut svm, &seller);
+    place_bet(&mut svm, &seller, event_id, true, 100_000_000);
+    create_order_book(&mut svm, &authority, event_id);
+
+    // The event caps a bet at 1 SOL, so a bid past it never rests
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceOrder {
+            order_book: book_pda(event_id),
+            bet: bet_pda(&buyer.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            signer: buyer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceOrder {
+            event_id,
+            side: OrderSide::Bid,
+            price_bps: 5_000,
+            quantity: 2_000_000_000,
+        }
+        .data(),
+    };
+    let logs = try_send(&mut svm, ix, &buyer).unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("StakeAboveMaximum")));
+
+    // A buyer without a profile has no exposure to open, so their bid is
+    // dropped and refunded rather than filled
+    place_order(
+        &mut svm,
+        &seller,
+        event_id,
+        OrderSide::Ask,
+        5_000,
+        50_000_000,
+    );
+    place_order(
+        &mut svm,
+        &buyer,
+        event_id,
+        OrderSide::Bid,
+        5_000,
+        50_000_000,
+    );
+    let buyer_balance = svm.get_balance(&buyer.pubkey()).unwrap();
+    send(
+        &mut svm,
+        crank_match_ix(event_id, &buyer, &seller, 1),
+        &authority,
+    );
+
+    let book: OrderBook = fetch(&svm, &book_pda(event_id));
+    assert!(book.bids.is_empty());
+    assert_eq!(book.asks.len(), 1);
+    assert_eq!(
+        svm.get_balance(&buyer.pubkey()).unwrap(),
+        buyer_balance + 25_000_000
+    );
+    let buyer_bet: Bet = fetch(&svm, &bet_pda(&buyer.pubkey(), event_id));
+    assert_eq!(buyer_bet.amount, 0);
+}
+
+#[test]
+fn test_cancelled_event_freezes_the_book_and_returns_orders() {
+    let mut svm = setup();
+    let authority = funded_keypair(&mut svm);
+    let buyer = funded_keypair(&mut svm);
+    let seller = funded_keypair(&mut svm);
+    let lamports = |svm: &LiteSVM, address: &Pubkey| svm.get_account(address).unwrap().lamports;
+
+    initialize_config(&mut svm, &authority);
+    let event_id = create_event(&mut svm, &authority);
+    initialize_player(&mut svm, &seller);
+    initialize_player(&mut svm, &buyer);
+    place_bet(&mut svm, &seller, event_id, true, 100_000_000);
+    create_order_book(&mut svm, &authority, event_id);
+    place_order(
+        &mut svm,
+        &seller,
+        event_id,
+        OrderSide::Ask,
+        5_000,
+        40_000_000,
+    );
+    place_order(
+        &mut svm,
+        &buyer,
+        event_id,
+        OrderSide::Bid,
+        4_000,
+        20_000_000,
+    );
+    let book: OrderBook = fetch(&svm, &book_pda(event_id));
+    let (ask_id, bid_id) = (book.asks[0].order_id, book.bids[0].order_id);
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ResolveEvent {
+            oracle_event: event_pda(event_id),
+            authority: authority.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CancelEvent {}.data(),
+    };
+    send(&mut svm, ix, &authority);
+
+    // Nothing is placed or matched on a cancelled event
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceOrder {
+            order_book: book_pda(event_id),
+            bet: bet_pda(&buyer.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            signer: buyer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceOrder {
+            event_id,
+            side: OrderSide::Bid,
+            price_bps: 5_000,
+            quantity: 40_000_000,
+        }
+        .data(),
+    };
+    let logs = try_send(&mut svm, ix, &buyer).unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("EventCancelled")));
+    let logs = try_send(
+        &mut svm,
+        crank_match_ix(event_id, &buyer, &seller, 1),
+        &authority,
+    )
+    .unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("EventCancelled")));
+
+    // The refund covers the 60M still on the bet, then the resting ask's
+    // 40M comes back from the event when it is cancelled
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::RefundBet {
+            bet: bet_pda(&seller.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            player_profile: player_pda(&seller.pubkey()),
+            signer: seller.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::RefundBet {}.data(),
+    };
+    let event_before = lamports(&svm, &event_pda(event_id));
+    send(&mut svm, ix, &seller);
+    assert_eq!(
+        lamports(&svm, &event_pda(event_id)),
+        event_before - 60_000_000
+    );
+    cancel_order(&mut svm, &seller, event_id, OrderSide::Ask, ask_id).unwrap();
+    assert_eq!(
+        lamports(&svm, &event_pda(event_id)),
+        event_before - 100_000_000
+    );
+
+    // The bid's escrow is still refunded from the book
+    let book_before = lamports(&svm, &book_pda(event_id));
+    cancel_order(&mut svm, &buyer, event_id, OrderSide::Bid, bid_id).unwrap();
+    assert_eq!(lamports(&svm, &book_pda(event_id)), book_before - 8_000_000);
+    let book: OrderBook = fetch(&svm, &book_pda(event_id));
+    assert!(book.asks.is_empty() && book.bids.is_empty());
+}
+
+#[test]
+fn test_cancel_ask_after_settlement_removes_the_order() {
+    let mut svm = setup();
+    let authority = funded_keypair(&mut svm);
+    let seller = funded_keypair(&mut svm);
+    let rival = funded_keypair(&mut svm);
+
+    initialize_config(&mut svm, &authority);
+    let event_id = create_event(&mut svm, &authority);
+    initialize_player(&mut svm, &seller);
+    initialize_player(&mut svm, &rival);
+    place_bet(&mut svm, &seller, event_id, true, 100_000_000);
+    place_bet(&mut svm, &rival, event_id, false, 100_000_000);
+    create_order_book(&mut svm, &authority, event_id);
+
+    // Orders below the minimum notional are rejected
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceOrder {
+            order_book: book_pda(event_id),
+            bet: bet_pda(&seller.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            signer: seller.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceOrder {
+            event_id,
+            side: OrderSide::Ask,
+            price_bps: 5_000,
+            quantity: 1_000,
+        }
+        .data(),
+    };
+    let logs = try_send(&mut svm, ix, &seller).unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("OrderTooSmall")));
+
+    for quantity in [30_000_000, 10_000_000] {
+        place_order(&mut svm, &seller, event_id, OrderSide::Ask, 5_000, quantity);
+    }
+    let asks: Vec<u64> = fetch::<OrderBook>(&svm, &book_pda(event_id))
+        .asks
+        .iter()
+        .map(|order| order.order_id)
+        .collect();
+    resolve_event(&mut svm, &authority, event_id, true);
+    claim_winnings(&mut svm, &seller, event_id);
+
+    // The claim paid out the remaining 60M stake; the offered 30M is not put
+    // back on a bet that has already been paid, but the order still leaves
+    cancel_order(&mut svm, &seller, event_id, OrderSide::Ask, asks[0]).unwrap();
+    assert_eq!(fetch::<OrderBook>(&svm, &book_pda(event_id)).asks.len(), 1);
+    let bet: Bet = fetch(&svm, &bet_pda(&seller.pubkey(), event_id));
+    assert_eq!(bet.amount, 60_000_000);
+
+    // Once the bet is closed its remaining ask can still be cancelled
+    close_bet(
+        &mut svm,
+        &seller,
+        bet_pda(&seller.pubkey(), event_id),
+        event_id,
+    )
+    .unwrap();
+    cancel_order(&mut svm, &seller, event_id, OrderSide::Ask, asks[1]).unwrap();
+    assert!(fetch::<OrderBook>(&svm, &book_pda(event_id))
+        .asks
+        .is_empty());
+}
+
+#[test]
+fn test_migrates_legacy_layouts_in_place() {
+    let mut svm = setup();
+    let payer = funded_keypair(&mut svm);
+    let v0_player = funded_keypair(&mut svm);
+    let event_id = 7;
+
+    set_legacy_account(
+        &mut svm,
+        player_pda(&v0_player.pubkey()),
+        PlayerProfile::DISCRIMINATOR,
+        &PlayerProfileV0 {
+            owner: v0_player.pubkey(),
+            balance: 2_000,
+            total_bets: 3,
+            bets_won: 1,
+        },
+        PlayerProfileV0::LEN,
+    );
+    set_legacy_account(
+        &mut svm,
+        bet_pda(&v0_player.pubkey(), event_id),
+        Bet::DISCRIMINATOR,
+        &BetV0 {
+            player: v0_player.pubkey(),
+            event_id,
+            chosen_outcome: true,
+            amount: 1_000,
+            claimed: false,
+        },
+        BetV0::LEN,
+    );
+    set_legacy_account(
+        &mut svm,
+        event_pda(event_id),
+        OracleEvent::DISCRIMINATOR,
+        &OracleEventV0 {
+            event_id,
+            description: "Will the dragon be slain?".to_string(),
+            resolved: false,
+            outcome: None,
+            authority: payer.pubkey(),
+            total_bets: 1,
+            yes_votes: 1,
+            no_votes: 0,
+            total_amount: 1_000,
+        },
+        OracleEventV0::LEN,
+    );
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::MigratePlayerProfile {
+            player_profile: player_pda(&v0_player.pubkey()),
+            signer: v0_player.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::MigratePlayerProfile {}.data(),
+    };
+    send(&mut svm, ix, &v0_player);
+    let migrate = |address: Pubkey, data: Vec<u8>| Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::MigrateAccount {
+            account: address,
+            payer: payer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data,
+    };
+    let bet_address = bet_pda(&v0_player.pubkey(), event_id);
+    send(
+        &mut svm,
+        migrate(
+            bet_address,
+            simple_oracle_quest::instruction::MigrateBet {}.data(),
+        ),
+        &payer,
+    );
+    send(
+        &mut svm,
+        migrate(
+            event_pda(event_id),
+            simple_oracle_quest::instruction::MigrateEvent {}.data(),
+        ),
+        &payer,
+    );
+
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&v0_player.pubkey()));
+    assert_eq!(profile.version, PLAYER_PROFILE_VERSION);
+    assert_eq!(profile.balance, 2_000);
+    assert_eq!(profile.xp, 80);
+    let bet: Bet = fetch(&svm, &bet_address);
+    assert_eq!(bet.version, BET_VERSION);
+    assert_eq!(bet.amount, 1_000);
+    let event: OracleEvent = fetch(&svm, &event_pda(event_id));
+    assert_eq!(event.version, ORACLE_EVENT_VERSION);
+    assert_eq!(event.total_amount, 0);
+    let event_account = svm.get_account(&event_pda(event_id)).unwrap();
+    // Migration grows accounts but never shrinks them
+    assert_eq!(event_account.data.len(), OracleEventV0::LEN);
+    assert!(
+        event_account.lamports >= svm.minimum_balance_for_rent_exemption(event_account.data.len())
+    );
+
+    // A second migration is rejected
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate(
+            bet_address,
+            simple_oracle_quest::instruction::MigrateBet {}.data(),
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        svm.latest_blockhash(),
+    );
+    assert!(svm.send_transaction(tx).is_err());
+
+    // Claims on the migrated event pay out of the stake it actually holds
+    initialize_config(&mut svm, &payer);
+    let winner = funded_keypair(&mut svm);
+    let loser = funded_keypair(&mut svm);
+    initialize_player(&mut svm, &winner);
+    initialize_player(&mut svm, &loser);
+    place_bet(&mut svm, &winner, event_id, true, 1_000_000);
+    place_bet(&mut svm, &loser, event_id, false, 2_000_000);
+    resolve_event(&mut svm, &payer, event_id, true);
+
+    let event_before = svm.get_account(&event_pda(event_id)).unwrap().lamports;
+    claim_winnings(&mut svm, &winner, event_id);
+    let event_after = svm.get_account(&event_pda(event_id)).unwrap().lamports;
+    // Winnings plus the protocol fee: the whole pot, nothing more
+    assert_eq!(event_before - event_after, 3_000_000);
+    let bet: Bet = fetch(&svm, &bet_pda(&winner.pubkey(), event_id));
+    assert!(bet.claimed);
+}
+
+fn update_event_description(
+    svm: &mut LiteSVM,
+    authority: &Keypair,
+    event_id: u64,
+    description: &str,
+) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::UpdateEvent {
+            oracle_event: event_pda(event_id),
+            authority: authority.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::UpdateEventDescription {
+            description: description.to_string(),
+        }
+        .data(),
+    };
+    send(svm, ix, authority);
+}
+
+#[test]
+fn test_account_sizes_match_init_space() {
+    let mut svm = setup();
+    let authority = funded_keypair(&mut svm);
+    let player = funded_keypair(&mut svm);
+    let data_len = |svm: &LiteSVM, address: &Pubkey| svm.get_account(address).unwrap().data.len();
+    let rent_exempt = |svm: &LiteSVM, address: &Pubkey| {
+        let account = svm.get_account(address).unwrap();
+        account.lamports == svm.minimum_balance_for_rent_exemption(account.data.len())
+    };
+
+    initialize_config(&mut svm, &authority);
+    let event_id = create_event(&mut svm, &authority);
+    assert_eq!(event_id, 0);
+    assert_eq!(create_event(&mut svm, &authority), 1);
+    let event = event_pda(event_id);
+    assert_eq!(
+        data_len(&svm, &event),
+        OracleEvent::space("Will the dragon be slain?", "")
+    );
+
+    let long = "x".repeat(simple_oracle_quest::MAX_DESCRIPTION_LEN);
+    update_event_description(&mut svm, &authority, event_id, &long);
+    assert_eq!(data_len(&svm, &event), OracleEvent::space(&long, ""));
+    assert!(rent_exempt(&svm, &event));
+
+    update_event_description(&mut svm, &authority, event_id, "Short");
+    assert_eq!(data_len(&svm, &event), OracleEvent::space("Short", ""));
+    assert!(rent_exempt(&svm, &event));
+    let stored: OracleEvent = fetch(&svm, &event);
+    assert_eq!(stored.description, "Short");
+
+    let metadata_uri = "https://quests.example/events/1.json";
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::UpdateEvent {
+            oracle_event: event,
+            authority: authority.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::UpdateEventMetadata {
+            metadata_uri: metadata_uri.to_string(),
+            metadata_hash: [7; 32],
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &authority);
+    assert_eq!(
+        data_len(&svm, &event),
+        OracleEvent::space("Short", metadata_uri)
+    );
+    assert!(rent_exempt(&svm, &event));
+    let stored: OracleEvent = fetch(&svm, &event);
+    assert_eq!(stored.metadata_hash, [7; 32]);
+
+    initialize_player(&mut svm, &player);
+    place_bet(&mut svm, &player, event_id, true, 10_000);
+    assert_eq!(
+        data_len(&svm, &player_pda(&player.pubkey())),
+        8 + PlayerProfile::INIT_SPACE
+    );
+    assert_eq!(
+        data_len(&svm, &bet_pda(&player.pubkey(), event_id)),
+        8 + Bet::INIT_SPACE
+    );
+    // Stake stays in the event on top of its rent
+    let account = svm.get_account(&event).unwrap();
+    assert_eq!(
+        account.lamports,
+        svm.minimum_balance_for_rent_exemption(account.data.len()) + 10_000
+    );
+}
+
+fn create_event_with_id(
+    svm: &mut LiteSVM,
+    admin: &Keypair,
+    event_id: u64,
+    category: EventCategory,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateOracleEventWithId {
+            category_counter: category_counter_pda(category),
+            oracle_event: event_pda(event_id),
+            season: None,
+            config: config_pda(),
+            admin: admin.pubkey(),
+            payer: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateOracleEventWithId {
+            event_id,
+            params: event_params(category),
+        }
+        .data(),
+    };
+    try_send(svm, ix, admin)
+}
+
+#[test]
+fn test_explicit_event_ids_are_admin_gated() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let stranger = funded_keypair(&mut svm);
+    let category = EventCategory::Sports;
+    let legacy_id = simple_oracle_quest::category_event_id(category, 1).unwrap();
+
+    initialize_config(&mut svm, &admin);
+    initialize_category_counter(&mut svm, &admin, category, 3);
+
+    // Disabled until the admin opts in
+    assert!(create_event_with_id(&mut svm, &admin, legacy_id, category).is_err());
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::UpdateConfig {
+            config: config_pda(),
+            admin: admin.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::UpdateConfig {
+            params: config_params(&admin, true),
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &admin);
+
+    assert!(create_event_with_id(&mut svm, &stranger, legacy_id, category).is_err());
+    create_event_with_id(&mut svm, &admin, legacy_id, category).unwrap();
+    let event: OracleEvent = fetch(&svm, &event_pda(legacy_id));
+    assert_eq!(event.event_id, legacy_id);
+
+    // Ids the counter will hand out, or ids of another category, are refused
+    let next_id = simple_oracle_quest::category_event_id(category, 3).unwrap();
+    assert!(create_event_with_id(&mut svm, &admin, next_id, category).is_err());
+    let general_id = simple_oracle_quest::category_event_id(EventCategory::General, 1).unwrap();
+    assert!(create_event_with_id(&mut svm, &admin, general_id, category).is_err());
+
+    // The allocator starts past the reserved range
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateOracleEvent {
+            category_counter: category_counter_pda(category),
+            oracle_event: event_pda(next_id),
+            season: None,
+            authority: stranger.pubkey(),
+            payer: stranger.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateOracleEvent {
+            params: event_params(category),
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &stranger);
+    let counter: CategoryCounter = fetch(&svm, &category_counter_pda(category));
+    assert_eq!(counter.next_seq, 4);
+}
+
+fn resolve_event(svm: &mut LiteSVM, authority: &Keypair, event_id: u64, outcome: bool) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ResolveEvent {
+            oracle_event: event_pda(event_id),
+            authority: authority.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::ResolveEvent { outcome }.data(),
+    };
+    send(svm, ix, authority);
+}
+
+fn claim_winnings(svm: &mut LiteSVM, player: &Keypair, event_id: u64) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ClaimWinnings {
+            bet: bet_pda(&player.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            player_profile: player_pda(&player.pubkey()),
+            season_score: None,
+            config: config_pda(),
+            referrer_rewards: None,
+            player: player.pubkey(),
+            session_token: None,
+            signer: player.pubkey(),
+            season: None,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::ClaimWinnings {}.data(),
+    };
+    send(svm, ix, player);
+}
+
+fn close_event(
+    svm: &mut LiteSVM,
+    authority: &Keypair,
+    treasury: &Pubkey,
+    event_id: u64,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CloseEvent {
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+            treasury: *treasury,
+            pool: pool_pda(event_id),
+            authority: authority.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CloseEvent {}.data(),
+    };
+    try_send(svm, ix, authority)
+}
+
+#[test]
+fn test_close_event_after_claims_or_grace_period() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let creator = funded_keypair(&mut svm);
+    let winner = funded_keypair(&mut svm);
+    let loser = funded_keypair(&mut svm);
+    let treasury = admin.pubkey();
+    let balance = |svm: &LiteSVM, address: &Pubkey| svm.get_account(address).unwrap().lamports;
+
+    initialize_config(&mut svm, &admin);
+    create_event(&mut svm, &admin);
+    initialize_player(&mut svm, &winner);
+    initialize_player(&mut svm, &loser);
+
+    let event_id = create_event(&mut svm, &creator);
+    let event = event_pda(event_id);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    place_bet(&mut svm, &loser, event_id, false, 30_000);
+
+    // Open events cannot be closed, nor resolved ones with unpaid winners
+    assert!(close_event(&mut svm, &creator, &treasury, event_id).is_err());
+    resolve_event(&mut svm, &creator, event_id, true);
+    let stored: OracleEvent = fetch(&svm, &event);
+    assert_eq!(stored.outstanding_claims, 1);
+    assert!(close_event(&mut svm, &creator, &treasury, event_id).is_err());
+    assert!(close_event(&mut svm, &winner, &treasury, event_id).is_err());
+
+    claim_winnings(&mut svm, &winner, event_id);
+    let stored: OracleEvent = fetch(&svm, &event);
+    assert_eq!(stored.outstanding_claims, 0);
+    let rent = balance(&svm, &event);
+    assert_eq!(
+        rent,
+        svm.minimum_balance_for_rent_exemption(svm.get_account(&event).unwrap().data.len())
+    );
+    let creator_before = balance(&svm, &creator.pubkey());
+    close_event(&mut svm, &creator, &treasury, event_id).unwrap();
+    assert!(svm
+        .get_account(&event)
+        .is_none_or(|account| account.lamports == 0));
+    assert!(balance(&svm, &creator.pubkey()) > creator_before);
+
+    // The unsettled loser can still close their bet and release its exposure
+    let loser_bet = bet_pda(&loser.pubkey(), event_id);
+    close_bet(&mut svm, &loser, loser_bet, event_id).unwrap();
+    assert!(svm
+        .get_account(&loser_bet)
+        .is_none_or(|account| account.lamports == 0));
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&loser.pubkey()));
+    assert_eq!(profile.open_exposure, 0);
+
+    // Unclaimed stake is swept to the treasury once the grace period ends
+    let event_id = create_event(&mut svm, &creator);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    resolve_event(&mut svm, &creator, event_id, true);
+    assert!(close_event(&mut svm, &creator, &treasury, event_id).is_err());
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += EVENT_CLOSE_GRACE_PERIOD;
+    svm.set_sysvar(&clock);
+    let treasury_before = balance(&svm, &treasury);
+    close_event(&mut svm, &creator, &treasury, event_id).unwrap();
+    assert_eq!(balance(&svm, &treasury), treasury_before + 10_000);
+}
+
+#[test]
+fn test_close_event_sweeps_unredeemed_pool_collateral() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let creator = funded_keypair(&mut svm);
+    let treasury = admin.pubkey();
+    let balance = |svm: &LiteSVM, address: &Pubkey| svm.get_account(address).unwrap().lamports;
+
+    initialize_config(&mut svm, &admin);
+    create_event(&mut svm, &admin);
+    let event_id = create_event(&mut svm, &creator);
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreatePool {
+            pool: pool_pda(event_id),
+            position: Pubkey::find_program_address(
+                &[
+                    b"position",
+                    creator.pubkey().as_ref(),
+                    &event_id.to_le_bytes(),
+                ],
+                &simple_oracle_quest::ID,
+            )
+            .0,
+            oracle_event: event_pda(event_id),
+            authority: creator.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreatePool {
+            event_id,
+            fee_bps: 30,
+            initial_liquidity: 50_000,
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &creator);
+    resolve_event(&mut svm, &creator, event_id, true);
+
+    // Shares backed by the pool keep the event open through the grace period
+    let logs = close_event(&mut svm, &creator, &treasury, event_id).unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("ClaimsOutstanding")));
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += EVENT_CLOSE_GRACE_PERIOD;
+    svm.set_sysvar(&clock);
+    let pool_before = balance(&svm, &pool_pda(event_id));
+    let treasury_before = balance(&svm, &treasury);
+    close_event(&mut svm, &creator, &treasury, event_id).unwrap();
+    assert_eq!(balance(&svm, &treasury), treasury_before + 50_000);
+    assert_eq!(balance(&svm, &pool_pda(event_id)), pool_before - 50_000);
+    let pool: AmmPool = fetch(&svm, &pool_pda(event_id));
+    assert_eq!(pool.collateral, 0);
+}
+
+fn leg_odds_pda(event_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"odds", &event_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn parlay_pda(player: &Pubkey, parlay_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"parlay", player.as_ref(), &parlay_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+#[test]
+fn test_close_event_waits_for_open_parlay_legs() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let creator = funded_keypair(&mut svm);
+    let player = funded_keypair(&mut svm);
+    let treasury = admin.pubkey();
+
+    initialize_config(&mut svm, &admin);
+    create_event(&mut svm, &admin);
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::FundHouse {
+            config: config_pda(),
+            signer: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::FundHouse { amount: 1_000_000 }.data(),
+    };
+    send(&mut svm, ix, &admin);
+
+    let event_ids = [
+        create_event(&mut svm, &creator),
+        create_event(&mut svm, &creator),
+    ];
+    for event_id in event_ids {
+        let ix = Instruction {
+            program_id: simple_oracle_quest::ID,
+            accounts: simple_oracle_quest::accounts::SetLegOdds {
+                leg_odds: leg_odds_pda(event_id),
+                oracle_event: event_pda(event_id),
+                config: config_pda(),
+                admin: admin.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: simple_oracle_quest::instruction::SetLegOdds {
+                event_id,
+                yes_odds_bps: 20_000,
+                no_odds_bps: 20_000,
+            }
+            .data(),
+        };
+        send(&mut svm, ix, &admin);
+    }
+
+    let mut accounts = simple_oracle_quest::accounts::PlaceParlay {
+        parlay: parlay_pda(&player.pubkey(), 0),
+        config: config_pda(),
+        signer: player.pubkey(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    for event_id in event_ids {
+        accounts.push(AccountMeta::new(event_pda(event_id), false));
+        accounts.push(AccountMeta::new_readonly(leg_odds_pda(event_id), false));
+    }
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts,
+        data: simple_oracle_quest::instruction::PlaceParlay {
+            parlay_id: 0,
+            legs: event_ids
+                .iter()
+                .map(|&event_id| ParlayLegInput {
+                    event_id,
+                    outcome: true,
+                })
+                .collect(),
+            stake: 10_000,
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &player);
+    for event_id in event_ids {
+        let stored: OracleEvent = fetch(&svm, &event_pda(event_id));
+        assert_eq!(stored.open_parlay_legs, 1);
+        resolve_event(&mut svm, &creator, event_id, true);
+    }
+
+    // The unclaimed parlay settles against the event, even past the grace period
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += EVENT_CLOSE_GRACE_PERIOD;
+    svm.set_sysvar(&clock);
+    let logs = close_event(&mut svm, &creator, &treasury, event_ids[0]).unwrap_err();
+    assert!(logs.iter().any(|l| l.contains("ParlayLegsOpen")));
+
+    let mut accounts = simple_oracle_quest::accounts::ClaimParlay {
+        parlay: parlay_pda(&player.pubkey(), 0),
+        config: config_pda(),
+        signer: player.pubkey(),
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        event_ids
+            .iter()
+            .map(|&event_id| AccountMeta::new(event_pda(event_id), false)),
+    );
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts,
+        data: simple_oracle_quest::instruction::ClaimParlay {}.data(),
+    };
+    send(&mut svm, ix, &player);
+    let config: Config = fetch(&svm, &config_pda());
+    assert_eq!(config.house_exposure, 0);
+
+    for event_id in event_ids {
+        let stored: OracleEvent = fetch(&svm, &event_pda(event_id));
+        assert_eq!(stored.open_parlay_legs, 0);
+        close_event(&mut svm, &creator, &treasury, event_id).unwrap();
+    }
+}
+
+fn close_bet(
+    svm: &mut LiteSVM,
+    signer: &Keypair,
+    bet: Pubkey,
+    event_id: u64,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CloseBet {
+            bet,
+            player_profile: player_pda(&signer.pubkey()),
+            oracle_event: event_pda(event_id),
+            signer: signer.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CloseBet {}.data(),
+    };
+    try_send(svm, ix, signer)
+}
+
+#[test]
+fn test_close_bet_requires_owner_and_settlement() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let winner = funded_keypair(&mut svm);
+    let loser = funded_keypair(&mut svm);
+    let attacker = funded_keypair(&mut svm);
+
+    initialize_config(&mut svm, &admin);
+    for player in [&winner, &loser, &attacker] {
+        initialize_player(&mut svm, player);
+    }
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    place_bet(&mut svm, &loser, event_id, false, 10_000);
+    let winner_bet = bet_pda(&winner.pubkey(), event_id);
+    let loser_bet = bet_pda(&loser.pubkey(), event_id);
+
+    // Open bets cannot be closed, even by their owner
+    assert!(close_bet(&mut svm, &winner, winner_bet, event_id).is_err());
+
+    resolve_event(&mut svm, &admin, event_id, true);
+
+    // Someone else's bet fails the seeds check
+    assert!(close_bet(&mut svm, &attacker, winner_bet, event_id).is_err());
+    // A winning bet must be claimed first
+    assert!(close_bet(&mut svm, &winner, winner_bet, event_id).is_err());
+    // A losing bet must be settled first, so its exposure is released
+    assert!(close_bet(&mut svm, &loser, loser_bet, event_id).is_err());
+
+    // A settled bet forged at a non-PDA address is rejected
+    let forged = Pubkey::new_unique();
+    set_legacy_account(
+        &mut svm,
+        forged,
+        Bet::DISCRIMINATOR,
+        &Bet {
+            player: attacker.pubkey(),
+            event_id,
+            claimed: true,
+            settled: true,
+            version: BET_VERSION,
+            ..Default::default()
+        },
+        8 + Bet::INIT_SPACE,
+    );
+    assert!(close_bet(&mut svm, &attacker, forged, event_id).is_err());
+
+    claim_winnings(&mut svm, &winner, event_id);
+    close_bet(&mut svm, &winner, winner_bet, event_id).unwrap();
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ClaimWinnings {
+            bet: loser_bet,
+            oracle_event: event_pda(event_id),
+            player_profile: player_pda(&loser.pubkey()),
+            season_score: None,
+            config: config_pda(),
+            referrer_rewards: None,
+            player: loser.pubkey(),
+            session_token: None,
+            signer: loser.pubkey(),
+            season: None,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::SettleLosingBet {}.data(),
+    };
+    send(&mut svm, ix, &loser);
+    close_bet(&mut svm, &loser, loser_bet, event_id).unwrap();
+    assert!(svm
+        .get_account(&loser_bet)
+        .is_none_or(|account| account.lamports == 0));
+
+    // Refunded bets on a cancelled event can be closed
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &attacker, event_id, true, 10_000);
+    let attacker_bet = bet_pda(&attacker.pubkey(), event_id);
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ResolveEvent {
+            oracle_event: event_pda(event_id),
+            authority: admin.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CancelEvent {}.data(),
+    };
+    send(&mut svm, ix, &admin);
+    assert!(close_bet(&mut svm, &attacker, attacker_bet, event_id).is_err());
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::RefundBet {
+            bet: attacker_bet,
+            oracle_event: event_pda(event_id),
+            player_profile: player_pda(&attacker.pubkey()),
+            signer: attacker.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::RefundBet {}.data(),
+    };
+    send(&mut svm, ix, &attacker);
+    close_bet(&mut svm, &attacker, attacker_bet, event_id).unwrap();
+}
+
+fn settle_bet(svm: &mut LiteSVM, player: &Keypair, event_id: u64, close_bet: bool) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ClaimWinnings {
+            bet: bet_pda(&player.pubkey(), event_id),
+            oracle_event: event_pda(event_id),
+            player_profile: player_pda(&player.pubkey()),
+            season_score: None,
+            config: config_pda(),
+            referrer_rewards: None,
+            player: player.pubkey(),
+            session_token: None,
+            signer: player.pubkey(),
+            season: None,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::SettleBet { close_bet }.data(),
+    };
+    send(svm, ix, player);
+}
+
+#[test]
+fn test_settle_bet_handles_wins_losses_and_refunds() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let winner = funded_keypair(&mut svm);
+    let loser = funded_keypair(&mut svm);
+    let is_closed = |svm: &LiteSVM, address: &Pubkey| {
+        svm.get_account(address)
+            .is_none_or(|account| account.lamports == 0)
+    };
+
+    initialize_config(&mut svm, &admin);
+    initialize_player(&mut svm, &winner);
+    initialize_player(&mut svm, &loser);
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    place_bet(&mut svm, &loser, event_id, false, 10_000);
+    resolve_event(&mut svm, &admin, event_id, true);
+
+    let winner_before = svm.get_account(&winner.pubkey()).unwrap().lamports;
+    settle_bet(&mut svm, &winner, event_id, true);
+    assert!(is_closed(&svm, &bet_pda(&winner.pubkey(), event_id)));
+    assert!(svm.get_account(&winner.pubkey()).unwrap().lamports > winner_before + 10_000);
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&winner.pubkey()));
+    assert_eq!(profile.bets_won, 1);
+    assert_eq!(profile.open_exposure, 0);
+
+    settle_bet(&mut svm, &loser, event_id, false);
+    let bet: Bet = fetch(&svm, &bet_pda(&loser.pubkey(), event_id));
+    assert!(bet.settled);
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&loser.pubkey()));
+    assert_eq!(profile.bets_lost, 1);
+    assert_eq!(profile.open_exposure, 0);
+    let event: OracleEvent = fetch(&svm, &event_pda(event_id));
+    assert_eq!(event.outstanding_claims, 0);
+
+    // Cancelled events refund the stake
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &loser, event_id, true, 10_000);
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ResolveEvent {
+            oracle_event: event_pda(event_id),
+            authority: admin.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CancelEvent {}.data(),
+    };
+    send(&mut svm, ix, &admin);
+    let loser_before = svm.get_account(&loser.pubkey()).unwrap().lamports;
+    settle_bet(&mut svm, &loser, event_id, true);
+    assert!(is_closed(&svm, &bet_pda(&loser.pubkey(), event_id)));
+    assert!(svm.get_account(&loser.pubkey()).unwrap().lamports > loser_before + 10_000);
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&loser.pubkey()));
+    assert_eq!(profile.bets_lost, 1);
+    assert_eq!(profile.open_exposure, 0);
+}
+
+fn claim_many(
+    svm: &mut LiteSVM,
+    player: &Keypair,
+    pairs: &[(Pubkey, Pubkey)],
+) -> Result<(), Vec<String>> {
+    let mut accounts = simple_oracle_quest::accounts::ClaimMany {
+        player_profile: player_pda(&player.pubkey()),
+        config: config_pda(),
+        referrer_rewards: None,
+        signer: player.pubkey(),
+    }
+    .to_account_metas(None);
+    for (bet, event) in pairs {
+        accounts.push(AccountMeta::new(*bet, false));
+        accounts.push(AccountMeta::new(*event, false));
+    }
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts,
+        data: simple_oracle_quest::instruction::ClaimMany {}.data(),
+    };
+    try_send(svm, ix, player)
+}
+
+#[test]
+fn test_claim_many_pays_every_winning_bet_once() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let player = funded_keypair(&mut svm);
+    let rival = funded_keypair(&mut svm);
+
+    initialize_config(&mut svm, &admin);
+    initialize_player(&mut svm, &player);
+    initialize_player(&mut svm, &rival);
+    let mut pairs = vec![];
+    for outcome in [true, true, false] {
+        let event_id = create_event(&mut svm, &admin);
+        place_bet(&mut svm, &player, event_id, true, 10_000);
+        place_bet(&mut svm, &rival, event_id, false, 10_000);
+        resolve_event(&mut svm, &admin, event_id, outcome);
+        pairs.push((bet_pda(&player.pubkey(), event_id), event_pda(event_id)));
+    }
+
+    // Pairs must be the signer's own bet with its event
+    assert!(claim_many(&mut svm, &rival, &pairs[..1]).is_err());
+    assert!(claim_many(&mut svm, &player, &[(pairs[0].0, pairs[1].1)]).is_err());
+
+    let before = svm.get_account(&player.pubkey()).unwrap().lamports;
+    claim_many(&mut svm, &player, &pairs).unwrap();
+    let gained = svm.get_account(&player.pubkey()).unwrap().lamports - before;
+    // Two 2x wins, less 2% of each 10_000 profit, less the transaction fee
+    assert!(gained > 0 && gained <= 2 * (20_000 - 200));
+
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&player.pubkey()));
+    assert_eq!(profile.bets_won, 2);
+    assert_eq!(profile.balance, 2 * (20_000 - 200));
+    // The losing bet is still open until it is settled
+    assert_eq!(profile.open_exposure, 10_000);
+    for (bet, event) in &pairs[..2] {
+        assert!(fetch::<Bet>(&svm, bet).claimed);
+        assert_eq!(fetch::<OracleEvent>(&svm, event).outstanding_claims, 0);
+    }
+    assert!(!fetch::<Bet>(&svm, &pairs[2].0).claimed);
+
+    // A second batch skips everything
+    claim_many(&mut svm, &player, &pairs).unwrap();
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&player.pubkey()));
+    assert_eq!(profile.bets_won, 2);
+    assert_eq!(profile.balance, 2 * (20_000 - 200));
+}
+
+#[test]
+fn test_crank_payouts_settles_and_closes_every_bet_once() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let winner = funded_keypair(&mut svm);
+    let loser = funded_keypair(&mut svm);
+    let cranker = funded_keypair(&mut svm);
+    let balance = |svm: &LiteSVM, address: &Pubkey| svm.get_account(address).unwrap().lamports;
+
+    initialize_config(&mut svm, &admin);
+    initialize_player(&mut svm, &winner);
+    initialize_player(&mut svm, &loser);
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    place_bet(&mut svm, &loser, event_id, false, 10_000);
+
+    let crank = |svm: &mut LiteSVM, players: &[&Keypair]| {
+        let mut accounts = simple_oracle_quest::accounts::CrankPayouts {
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+            cranker: cranker.pubkey(),
+        }
+        .to_account_metas(None);
+        for player in players {
+            accounts.push(AccountMeta::new(bet_pda(&player.pubkey(), event_id), false));
+            accounts.push(AccountMeta::new(player.pubkey(), false));
+            accounts.push(AccountMeta::new(player_pda(&player.pubkey()), false));
+            accounts.push(AccountMeta::new_readonly(simple_oracle_quest::ID, false));
+        }
+        let ix = Instruction {
+            program_id: simple_oracle_quest::ID,
+            accounts,
+            data: simple_oracle_quest::instruction::CrankPayouts { event_id }.data(),
+        };
+        try_send(svm, ix, &cranker)
+    };
+
+    // Nothing to pay before the event is finished
+    assert!(crank(&mut svm, &[&winner, &loser]).is_err());
+    resolve_event(&mut svm, &admin, event_id, true);
+
+    let winner_before = balance(&svm, &winner.pubkey());
+    let loser_before = balance(&svm, &loser.pubkey());
+    let cranker_before = balance(&svm, &cranker.pubkey());
+    // Passing a bet twice is harmless
+    crank(&mut svm, &[&winner, &loser, &winner]).unwrap();
+
+    let bet_rent = svm.minimum_balance_for_rent_exemption(8 + Bet::INIT_SPACE);
+    // 2x payout less 2% of the profit, plus the bet's rent
+    assert_eq!(
+        balance(&svm, &winner.pubkey()),
+        winner_before + 20_000 - 200 + bet_rent
+    );
+    assert_eq!(balance(&svm, &loser.pubkey()), loser_before + bet_rent);
+    for player in [&winner, &loser] {
+        assert!(svm
+            .get_account(&bet_pda(&player.pubkey(), event_id))
+            .is_none_or(|account| account.lamports == 0));
+        let profile: PlayerProfile = fetch(&svm, &player_pda(&player.pubkey()));
+        assert_eq!(profile.open_exposure, 0);
+    }
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&winner.pubkey()));
+    assert_eq!(profile.bets_won, 1);
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&loser.pubkey()));
+    assert_eq!(profile.bets_lost, 1);
+    let event: OracleEvent = fetch(&svm, &event_pda(event_id));
+    assert_eq!(event.outstanding_claims, 0);
+
+    // The bounty is capped by the 200 lamports of fees this crank collected
+    let config: simple_oracle_quest::Config = fetch(&svm, &config_pda());
+    assert_eq!(config.fees_accrued, 0);
+    assert!(balance(&svm, &cranker.pubkey()) <= cranker_before + 200);
+
+    // Cranking closed bets again pays nobody
+    let winner_after = balance(&svm, &winner.pubkey());
+    crank(&mut svm, &[&winner]).unwrap();
+    assert_eq!(balance(&svm, &winner.pubkey()), winner_after);
+
+    // Closing a bet its owner already claimed earns no bounty from older fees
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    place_bet(&mut svm, &loser, event_id, false, 10_000);
+    resolve_event(&mut svm, &admin, event_id, true);
+    claim_winnings(&mut svm, &winner, event_id);
+    let config: simple_oracle_quest::Config = fetch(&svm, &config_pda());
+    assert_eq!(config.fees_accrued, 200);
+    let mut accounts = simple_oracle_quest::accounts::CrankPayouts {
+        oracle_event: event_pda(event_id),
+        config: config_pda(),
+        cranker: cranker.pubkey(),
+    }
+    .to_account_metas(None);
+    accounts.push(AccountMeta::new(bet_pda(&winner.pubkey(), event_id), false));
+    accounts.push(AccountMeta::new(winner.pubkey(), false));
+    accounts.push(AccountMeta::new(player_pda(&winner.pubkey()), false));
+    accounts.push(AccountMeta::new_readonly(simple_oracle_quest::ID, false));
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts,
+        data: simple_oracle_quest::instruction::CrankPayouts { event_id }.data(),
+    };
+    send(&mut svm, ix, &cranker);
+    assert!(svm
+        .get_account(&bet_pda(&winner.pubkey(), event_id))
+        .is_none_or(|account| account.lamports == 0));
+    let config: simple_oracle_quest::Config = fetch(&svm, &config_pda());
+    assert_eq!(config.fees_accrued, 200);
+
+    // Seasonal events are paid out too, just without season scoring
+    let event_id = create_event(&mut svm, &admin);
+    let event = event_pda(event_id);
+    place_bet(&mut svm, &winner, event_id, true, 10_000);
+    place_bet(&mut svm, &loser, event_id, false, 10_000);
+    resolve_event(&mut svm, &admin, event_id, true);
+    let mut stored: OracleEvent = fetch(&svm, &event);
+    stored.season_id = 1;
+    let len = svm.get_account(&event).unwrap().data.len();
+    set_legacy_account(&mut svm, event, OracleEvent::DISCRIMINATOR, &stored, len);
+    let mut accounts = simple_oracle_quest::accounts::CrankPayouts {
+        oracle_event: event,
+        config: config_pda(),
+        cranker: cranker.pubkey(),
+    }
+    .to_account_metas(None);
+    accounts.push(AccountMeta::new(bet_pda(&winner.pubkey(), event_id), false));
+    accounts.push(AccountMeta::new(winner.pubkey(), false));
+    accounts.push(AccountMeta::new(player_pda(&winner.pubkey()), false));
+    accounts.push(AccountMeta::new_readonly(simple_oracle_quest::ID, false));
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts,
+        data: simple_oracle_quest::instruction::CrankPayouts { event_id }.data(),
+    };
+    send(&mut svm, ix, &cranker);
+    let stored: OracleEvent = fetch(&svm, &event);
+    assert_eq!(stored.outstanding_claims, 0);
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&winner.pubkey()));
+    assert_eq!(profile.bets_won, 3);
+}
+
+fn session_pda(owner: &Pubkey, session_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"session", owner.as_ref(), session_key.as_ref()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn place_bet_as(
+    svm: &mut LiteSVM,
+    signer: &Keypair,
+    player: &Pubkey,
+    session_token: Option<Pubkey>,
+    event_id: u64,
+    amount: u64,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceBet {
+            bet: bet_pda(player, event_id),
+            player_profile: player_pda(player),
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+            player: *player,
+            session_token,
+            signer: signer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceBet {
+            event_id,
+            chosen_outcome: true,
+            amount,
+        }
+        .data(),
+    };
+    try_send(svm, ix, signer)
+}
+
+#[test]
+fn test_session_keys_bet_within_scope_budget_and_expiry() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let owner = funded_keypair(&mut svm);
+    let stranger = funded_keypair(&mut svm);
+    let session_key = Keypair::new();
+    let session = session_pda(&owner.pubkey(), &session_key.pubkey());
+
+    initialize_config(&mut svm, &admin);
+    initialize_player(&mut svm, &owner);
+    let first = create_event(&mut svm, &admin);
+    let second = create_event(&mut svm, &admin);
+
+    let expires_at = svm.get_sysvar::<Clock>().unix_timestamp + 3_600;
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateSession {
+            session_token: session,
+            session_signer: session_key.pubkey(),
+            owner: owner.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateSession {
+            session_key: session_key.pubkey(),
+            scopes: SESSION_SCOPE_PLACE_BET,
+            max_stake: 15_000,
+            expires_at,
+            fee_budget: 1_000_000_000,
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &owner);
+
+    // The session key bets for the owner out of the escrowed budget
+    place_bet_as(
+        &mut svm,
+        &session_key,
+        &owner.pubkey(),
+        Some(session),
+        first,
+        10_000,
+    )
+    .unwrap();
+    let bet: Bet = fetch(&svm, &bet_pda(&owner.pubkey(), first));
+    assert_eq!(bet.player, owner.pubkey());
+    let token: SessionToken = fetch(&svm, &session);
+    assert_eq!(token.staked, 10_000);
+    let profile: PlayerProfile = fetch(&svm, &player_pda(&owner.pubkey()));
+    assert_eq!(profile.open_exposure, 10_000);
+
+    // Over budget, or signed by someone else, is refused
+    assert!(place_bet_as(
+        &mut svm,
+        &session_key,
+        &owner.pubkey(),
+        Some(session),
+        second,
+        10_000
+    )
+    .is_err());
+    assert!(place_bet_as(
+        &mut svm,
+        &stranger,
+        &owner.pubkey(),
+        Some(session),
+        second,
+        1_000
+    )
+    .is_err());
+    assert!(place_bet_as(&mut svm, &stranger, &owner.pubkey(), None, second, 1_000).is_err());
+
+    // The session was not granted the claim scope
+    resolve_event(&mut svm, &admin, first, true);
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ClaimWinnings {
+            bet: bet_pda(&owner.pubkey(), first),
+            oracle_event: event_pda(first),
+            player_profile: player_pda(&owner.pubkey()),
+            season_score: None,
+            config: config_pda(),
+            referrer_rewards: None,
+            player: owner.pubkey(),
+            session_token: Some(session),
+            signer: session_key.pubkey(),
+            season: None,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::ClaimWinnings {}.data(),
+    };
+    assert!(try_send(&mut svm, ix, &session_key).is_err());
+    claim_winnings(&mut svm, &owner, first);
+
+    // Expired sessions stop working
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = expires_at;
+    svm.set_sysvar(&clock);
+    assert!(place_bet_as(
+        &mut svm,
+        &session_key,
+        &owner.pubkey(),
+        Some(session),
+        second,
+        1_000
+    )
+    .is_err());
+
+    // Revoking returns the unspent budget with the rent
+    let owner_before = svm.get_account(&owner.pubkey()).unwrap().lamports;
+    let session_lamports = svm.get_account(&session).unwrap().lamports;
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::RevokeSession {
+            session_token: session,
+            owner: owner.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::RevokeSession {}.data(),
+    };
+    send(&mut svm, ix, &owner);
+    assert_eq!(
+        session_lamports,
+        svm.minimum_balance_for_rent_exemption(8 + SessionToken::INIT_SPACE) + 5_000
+    );
+    assert!(svm.get_account(&owner.pubkey()).unwrap().lamports > owner_before);
+    assert!(svm
+        .get_account(&session)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+fn guild_pda(founder: &Pubkey, guild_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"guild", founder.as_ref(), &guild_id.to_le_bytes()],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+fn create_guild(svm: &mut LiteSVM, founder: &Keypair, guild_id: u64) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateGuild {
+            guild: guild_pda(&founder.pubkey(), guild_id),
+            founder: founder.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateGuild { guild_id }.data(),
+    };
+    send(svm, ix, founder);
+}
+
+fn manage_guild(
+    svm: &mut LiteSVM,
+    signer: &Keypair,
+    guild: Pubkey,
+    data: impl InstructionData,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ManageGuild {
+            guild,
+            signer: signer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: data.data(),
+    };
+    try_send(svm, ix, signer)
+}
+
+fn guild_place_bet(
+    svm: &mut LiteSVM,
+    manager: &Keypair,
+    guild: Pubkey,
+    event_id: u64,
+    amount: u64,
+) -> Result<(), Vec<String>> {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::GuildPlaceBet {
+            guild,
+            bet: bet_pda(&guild, event_id),
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+            manager: manager.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::GuildPlaceBet {
+            event_id,
+            chosen_outcome: true,
+            amount,
+        }
+        .data(),
+    };
+    try_send(svm, ix, manager)
+}
+
+#[test]
+fn test_guild_bets_from_pooled_bankroll_and_pays_out_by_share() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let founder = funded_keypair(&mut svm);
+    let manager = funded_keypair(&mut svm);
+    let alice = funded_keypair(&mut svm);
+    let bob = funded_keypair(&mut svm);
+    let rival = funded_keypair(&mut svm);
+    let guild = guild_pda(&founder.pubkey(), 1);
+
+    initialize_config(&mut svm, &admin);
+    initialize_player(&mut svm, &rival);
+    let event_id = create_event(&mut svm, &admin);
+
+    create_guild(&mut svm, &founder, 1);
+    for (member, role) in [
+        (&manager, GuildRole::Manager),
+        (&alice, GuildRole::Member),
+        (&bob, GuildRole::Member),
+    ] {
+        let data = simple_oracle_quest::instruction::SetGuildMember {
+            member: member.pubkey(),
+            role,
+        };
+        manage_guild(&mut svm, &founder, guild, data).unwrap();
+    }
+    // Only the owner manages membership, and ownership is not grantable
+    let data = simple_oracle_quest::instruction::SetGuildMember {
+        member: rival.pubkey(),
+        role: GuildRole::Member,
+    };
+    assert!(manage_guild(&mut svm, &manager, guild, data).is_err());
+    let data = simple_oracle_quest::instruction::SetGuildMember {
+        member: alice.pubkey(),
+        role: GuildRole::Owner,
+    };
+    assert!(manage_guild(&mut svm, &founder, guild, data).is_err());
+
+    for (member, amount) in [(&alice, 30_000), (&bob, 10_000)] {
+        let data = simple_oracle_quest::instruction::GuildDeposit { amount };
+        manage_guild(&mut svm, member, guild, data).unwrap();
+    }
+    let data = simple_oracle_quest::instruction::GuildDeposit { amount: 1_000 };
+    assert!(manage_guild(&mut svm, &rival, guild, data).is_err());
+
+    // Members cannot bet the bankroll, and managers cannot overdraw it
+    assert!(guild_place_bet(&mut svm, &alice, guild, event_id, 20_000).is_err());
+    assert!(guild_place_bet(&mut svm, &manager, guild, event_id, 50_000).is_err());
+    guild_place_bet(&mut svm, &manager, guild, event_id, 20_000).unwrap();
+    let bet: Bet = fetch(&svm, &bet_pda(&guild, event_id));
+    assert_eq!(bet.player, guild);
+    let state: Guild = fetch(&svm, &guild);
+    assert_eq!((state.bankroll, state.open_stake), (20_000, 20_000));
+
+    // Shares are neither minted nor burnt while bets are open
+    let data = simple_oracle_quest::instruction::GuildWithdraw { shares: 1_000 };
+    assert!(manage_guild(&mut svm, &alice, guild, data).is_err());
+    let data = simple_oracle_quest::instruction::GuildDeposit { amount: 1_000 };
+    assert!(manage_guild(&mut svm, &bob, guild, data).is_err());
+
+    // Seasonal events are out of reach of the bankroll
+    let season_event_id = create_event(&mut svm, &admin);
+    let season_event = event_pda(season_event_id);
+    let mut stored: OracleEvent = fetch(&svm, &season_event);
+    stored.season_id = 1;
+    let len = svm.get_account(&season_event).unwrap().data.len();
+    set_legacy_account(
+        &mut svm,
+        season_event,
+        OracleEvent::DISCRIMINATOR,
+        &stored,
+        len,
+    );
+    assert!(guild_place_bet(&mut svm, &manager, guild, season_event_id, 10_000).is_err());
+
+    place_bet(&mut svm, &rival, event_id, false, 20_000);
+    resolve_event(&mut svm, &admin, event_id, true);
+    guild_settle_bet(&mut svm, &manager, guild, event_id);
+
+    // 2x win less 2% of the 20_000 profit lands in the bankroll
+    let state: Guild = fetch(&svm, &guild);
+    assert_eq!((state.bankroll, state.open_stake), (59_600, 0));
+    assert!(svm
+        .get_account(&bet_pda(&guild, event_id))
+        .is_none_or(|account| account.lamports == 0));
+
+    // Alice holds 75% of the shares, Bob 25%
+    let before = svm.get_account(&alice.pubkey()).unwrap().lamports;
+    let data = simple_oracle_quest::instruction::GuildWithdraw { shares: 30_000 };
+    manage_guild(&mut svm, &alice, guild, data).unwrap();
+    assert_eq!(
+        svm.get_account(&alice.pubkey()).unwrap().lamports,
+        before + 44_700 - 5_000
+    );
+    let data = simple_oracle_quest::instruction::GuildWithdraw { shares: 10_001 };
+    assert!(manage_guild(&mut svm, &bob, guild, data).is_err());
+    let data = simple_oracle_quest::instruction::GuildWithdraw { shares: 10_000 };
+    manage_guild(&mut svm, &bob, guild, data).unwrap();
+
+    let state: Guild = fetch(&svm, &guild);
+    assert_eq!((state.bankroll, state.total_shares), (0, 0));
+    assert_eq!(
+        svm.get_account(&guild).unwrap().lamports,
+        svm.minimum_balance_for_rent_exemption(8 + Guild::INIT_SPACE)
+    );
+}
+
+fn guild_settle_bet(svm: &mut LiteSVM, manager: &Keypair, guild: Pubkey, event_id: u64) {
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::GuildSettleBet {
+            guild,
+            bet: bet_pda(&guild, event_id),
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+            manager: manager.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::GuildSettleBet {}.data(),
+    };
+    send(svm, ix, manager);
+}
+
+#[test]
+fn test_guild_settles_bets_on_closed_events() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let founder = funded_keypair(&mut svm);
+    let guild = guild_pda(&founder.pubkey(), 1);
+
+    initialize_config(&mut svm, &admin);
+    let event_id = create_event(&mut svm, &admin);
+    create_guild(&mut svm, &founder, 1);
+    let data = simple_oracle_quest::instruction::GuildDeposit { amount: 30_000 };
+    manage_guild(&mut svm, &founder, guild, data).unwrap();
+    guild_place_bet(&mut svm, &founder, guild, event_id, 20_000).unwrap();
+
+    // The unsettled win is swept once the grace period lets the event close
+    resolve_event(&mut svm, &admin, event_id, true);
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += EVENT_CLOSE_GRACE_PERIOD;
+    svm.set_sysvar(&clock);
+    close_event(&mut svm, &admin, &admin.pubkey(), event_id).unwrap();
+
+    // Settling still releases the stake, so shares are no longer frozen
+    guild_settle_bet(&mut svm, &founder, guild, event_id);
+    let state: Guild = fetch(&svm, &guild);
+    assert_eq!((state.bankroll, state.open_stake), (10_000, 0));
+    assert!(svm
+        .get_account(&bet_pda(&guild, event_id))
+        .is_none_or(|account| account.lamports == 0));
+    let data = simple_oracle_quest::instruction::GuildWithdraw { shares: 30_000 };
+    manage_guild(&mut svm, &founder, guild, data).unwrap();
+    let state: Guild = fetch(&svm, &guild);
+    assert_eq!((state.bankroll, state.total_shares), (0, 0));
+}
+
+/// Runs `ix` through `simulate_transaction` and decodes its return data.
+fn simulate<T: AnchorDeserialize>(svm: &LiteSVM, ix: Instruction, signer: &Keypair) -> T {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    let info = svm
+        .simulate_transaction(tx)
+        .unwrap_or_else(|e| panic!("simulation failed: {:?}", e.meta.logs));
+    assert_eq!(info.meta.return_data.program_id, simple_oracle_quest::ID);
+    T::try_from_slice(&info.meta.return_data.data).unwrap()
+}
+
+#[test]
+fn test_view_instructions_return_quotes_and_summaries() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let player = funded_keypair(&mut svm);
+    let rival = funded_keypair(&mut svm);
+
+    initialize_config(&mut svm, &admin);
+    initialize_player(&mut svm, &player);
+    initialize_player(&mut svm, &rival);
+    let event_id = create_event(&mut svm, &admin);
+    place_bet(&mut svm, &player, event_id, true, 10_000);
+    place_bet(&mut svm, &rival, event_id, false, 30_000);
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::QuotePayout {
+            oracle_event: event_pda(event_id),
+            config: config_pda(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::QuotePayout {
+            chosen_outcome: true,
+            amount: 10_000,
+        }
+        .data(),
+    };
+    let quote: PayoutQuote = simulate(&svm, ix, &player);
+    assert_eq!(quote.implied_probability_bps, 4_000);
+    assert_eq!(quote.potential_payout, 25_000 - 300);
+
+    let summary_ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::GetEventSummary {
+            oracle_event: event_pda(event_id),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::GetEventSummary {}.data(),
+    };
+    let summary: EventSummary = simulate(&svm, summary_ix.clone(), &player);
+    assert_eq!((summary.total_bets, summary.total_amount), (2, 40_000));
+    assert_eq!(summary.yes_probability_bps, 2_500);
+
+    resolve_event(&mut svm, &admin, event_id, true);
+    let summary: EventSummary = simulate(&svm, summary_ix, &player);
+    assert_eq!(summary.outcome, Some(true));
+    assert_eq!(summary.outstanding_claims, 1);
+
+    // Winnings are claimable until claimed
+    let player_ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: [
+            simple_oracle_quest::accounts::GetPlayerSummary {
+                player_profile: player_pda(&player.pubkey()),
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            vec![
+                AccountMeta::new_readonly(bet_pda(&player.pubkey(), event_id), false),
+                AccountMeta::new_readonly(event_pda(event_id), false),
+            ],
+        ]
+        .concat(),
+        data: simple_oracle_quest::instruction::GetPlayerSummary {}.data(),
+    };
+    let summary: PlayerSummary = simulate(&svm, player_ix.clone(), &player);
+    assert_eq!(summary.owner, player.pubkey());
+    assert_eq!(summary.total_bets, 1);
+    assert_eq!(
+        (summary.claimable, summary.claimable_bets),
+        (40_000 - 600, 1)
+    );
+
+    claim_winnings(&mut svm, &player, event_id);
+    let summary: PlayerSummary = simulate(&svm, player_ix, &player);
+    assert_eq!((summary.claimable, summary.bets_won), (0, 1));
+}
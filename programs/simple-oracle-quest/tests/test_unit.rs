@@ -0,0 +1,832 @@
+use anchor_lang::prelude::*;
+use simple_oracle_quest::{
+    category_event_id, level_for_xp, merkle_parent, normalize_tags, order_cost, parlay_payout,
+    season_prize_leaf, split_protocol_fee, verify_merkle_proof, xp_for_level, AmmPool, AmmPosition,
+    Bet, BetV0, CashOutQuote, CategoryCounter, EventCategory, Guild, GuildMember, GuildRole,
+    LegOdds, OracleEvent, OracleEventV0, Order, OrderBook, OrderSide, PlayerProfile,
+    PlayerProfileV0, Season, SeasonScore, SessionToken, BET_VERSION, CATEGORY_SEQ_BITS,
+    DEFAULT_LEG_ODDS_BPS, EVENT_AUTHORITY_OFFSET, EVENT_CATEGORY_OFFSET, EVENT_RESOLVED_OFFSET,
+    EVENT_TAGS_OFFSET, MAX_DESCRIPTION_LEN, MAX_EVENT_TAGS, MAX_LEG_ODDS_BPS, MAX_METADATA_URI_LEN,
+    ORACLE_EVENT_VERSION, ORDER_BOOK_DEPTH, PLAYER_PROFILE_VERSION, SESSION_SCOPE_CLAIM,
+    SESSION_SCOPE_PLACE_BET,
+};
+
+fn new_pool(liquidity: u64, fee_bps: u16) -> (AmmPool, AmmPosition) {
+    let mut pool = AmmPool {
+        event_id: 1,
+        yes_reserve: 0,
+        no_reserve: 0,
+        total_lp_shares: 0,
+        fee_bps,
+        collateral: 0,
+        acc_fee_per_share: 0,
+        bump: 255,
+    };
+    let mut position = AmmPosition {
+        owner: Pubkey::new_unique(),
+        event_id: 1,
+        yes_shares: 0,
+        no_shares: 0,
+        lp_shares: 0,
+        fee_debt: 0,
+        bump: 255,
+    };
+    pool.add_liquidity(&mut position, liquidity).unwrap();
+    (pool, position)
+}
+
+#[test]
+fn test_amm_pool_starts_at_even_odds() {
+    let (pool, position) = new_pool(1_000_000, 0);
+
+    assert_eq!(pool.yes_reserve, 1_000_000);
+    assert_eq!(pool.no_reserve, 1_000_000);
+    assert_eq!(pool.implied_yes_probability_bps(), 5_000);
+    assert_eq!(position.lp_shares, 1_000_000);
+
+    println!("✅ AMM initial odds test passed");
+}
+
+#[test]
+fn test_amm_buy_moves_price_and_keeps_invariant() {
+    let (mut pool, _) = new_pool(1_000_000, 0);
+    let invariant_before = pool.yes_reserve as u128 * pool.no_reserve as u128;
+
+    let shares = pool.buy(true, 500_000).unwrap();
+
+    // 500k minted as complete sets plus the YES swapped out of the pool
+    assert!(shares > 500_000);
+    assert!(pool.implied_yes_probability_bps() > 5_000);
+    assert!(pool.yes_reserve as u128 * pool.no_reserve as u128 >= invariant_before);
+    assert_eq!(pool.collateral, 1_500_000);
+
+    println!("✅ AMM buy test passed");
+}
+
+#[test]
+fn test_amm_fees_accrue_to_liquidity_providers() {
+    let (mut pool, mut position) = new_pool(1_000_000, 100);
+
+    pool.buy(false, 200_000).unwrap();
+
+    let fees = pool.settle_fees(&mut position).unwrap();
+    assert_eq!(fees, 2_000);
+    assert_eq!(pool.settle_fees(&mut position).unwrap(), 0);
+
+    println!("✅ AMM fee accrual test passed");
+}
+
+#[test]
+fn test_amm_remove_liquidity_returns_collateral() {
+    let (mut pool, mut position) = new_pool(1_000_000, 0);
+    pool.buy(true, 250_000).unwrap();
+
+    let merged = pool.remove_liquidity(&mut position, 1_000_000).unwrap();
+
+    assert_eq!(pool.total_lp_shares, 0);
+    assert_eq!(pool.yes_reserve, 0);
+    assert_eq!(pool.no_reserve, 0);
+    // The LP keeps the unmatched NO side as outcome shares
+    assert_eq!(position.yes_shares, 0);
+    assert_eq!(position.no_shares + merged, 1_250_000);
+
+    println!("✅ AMM remove liquidity test passed");
+}
+
+#[test]
+fn test_amm_shares_redeem_on_resolution_or_cancellation() {
+    let (mut pool, mut lp) = new_pool(1_000_000, 0);
+    let mut trader = AmmPosition {
+        owner: Pubkey::new_unique(),
+        event_id: 1,
+        yes_shares: 0,
+        no_shares: 0,
+        lp_shares: 0,
+        fee_debt: 0,
+        bump: 255,
+    };
+    let shares = pool.buy(true, 250_000).unwrap();
+    trader.credit_shares(true, shares).unwrap();
+    pool.remove_liquidity(&mut lp, 1_000_000).unwrap();
+
+    let mut event = open_event(0, 0);
+    assert!(trader.redemption_value(&event).is_err());
+
+    // With no winner every share is worth half a lamport, which pays out
+    // exactly the remaining collateral and never more than the trader paid
+    event.cancelled = true;
+    let trader_value = trader.redemption_value(&event).unwrap();
+    let lp_value = lp.redemption_value(&event).unwrap();
+    assert_eq!(trader_value, shares / 2);
+    assert!(trader_value <= 250_000);
+    assert_eq!(trader_value + lp_value, pool.collateral);
+
+    event.cancelled = false;
+    event.resolved = true;
+    event.outcome = Some(true);
+    assert_eq!(trader.redemption_value(&event).unwrap(), shares);
+    assert_eq!(lp.redemption_value(&event).unwrap(), 0);
+
+    println!("✅ AMM redemption test passed");
+}
+
+fn new_order(
+    book: &OrderBook,
+    owner: Pubkey,
+    price_bps: u16,
+    quantity: u64,
+    side: OrderSide,
+) -> Order {
+    Order {
+        order_id: book.next_order_id,
+        owner,
+        price_bps,
+        quantity,
+        escrow: match side {
+            OrderSide::Bid => order_cost(quantity, price_bps).unwrap(),
+            OrderSide::Ask => 0,
+        },
+    }
+}
+
+#[test]
+fn test_order_book_price_time_priority() {
+    let mut book = OrderBook {
+        event_id: 1,
+        next_order_id: 0,
+        bids: vec![],
+        asks: vec![],
+        bump: 255,
+    };
+    let owner = Pubkey::new_unique();
+
+    for price in [4_000, 6_000, 6_000, 5_000] {
+        let order = new_order(&book, owner, price, 100, OrderSide::Bid);
+        book.insert(OrderSide::Bid, order).unwrap();
+    }
+
+    let ids: Vec<u64> = book.bids.iter().map(|o| o.order_id).collect();
+    assert_eq!(ids, vec![1, 2, 3, 0]);
+
+    book.remove(OrderSide::Bid, 2).unwrap();
+    assert_eq!(book.bids.len(), 3);
+    assert!(book.remove(OrderSide::Bid, 2).is_err());
+
+    println!("✅ Order book priority test passed");
+}
+
+#[test]
+fn test_order_book_fills_at_resting_price() {
+    let mut book = OrderBook {
+        event_id: 1,
+        next_order_id: 0,
+        bids: vec![],
+        asks: vec![],
+        bump: 255,
+    };
+    let seller = Pubkey::new_unique();
+    let buyer = Pubkey::new_unique();
+
+    let ask = new_order(&book, seller, 5_000, 1_000, OrderSide::Ask);
+    book.insert(OrderSide::Ask, ask).unwrap();
+    let bid = new_order(&book, buyer, 6_000, 1_000, OrderSide::Bid);
+    book.insert(OrderSide::Bid, bid).unwrap();
+
+    let fill = book.next_fill().unwrap();
+
+    assert_eq!(fill.buyer, buyer);
+    assert_eq!(fill.seller, seller);
+    assert_eq!(fill.price_bps, 5_000);
+    assert_eq!(fill.cost, 500);
+    // The bid escrowed 600 lamports, the unused 100 go back to the buyer
+    assert_eq!(fill.buyer_refund, 100);
+    assert!(book.bids.is_empty());
+    assert!(book.asks.is_empty());
+    assert!(book.next_fill().is_none());
+
+    println!("✅ Order book fill test passed");
+}
+
+#[test]
+fn test_order_book_evicts_worst_order_when_full() {
+    let mut book = OrderBook {
+        event_id: 1,
+        next_order_id: 0,
+        bids: vec![],
+        asks: vec![],
+        bump: 255,
+    };
+    let owner = Pubkey::new_unique();
+
+    for i in 0..ORDER_BOOK_DEPTH as u16 {
+        let order = new_order(&book, owner, 1_000 + i, 100, OrderSide::Bid);
+        assert!(book.insert(OrderSide::Bid, order).unwrap().is_none());
+    }
+
+    // Matching the worst price is not enough to get in
+    let tied = new_order(&book, owner, 1_000, 100, OrderSide::Bid);
+    assert!(book.insert(OrderSide::Bid, tied).is_err());
+
+    let better = new_order(&book, owner, 1_001, 100, OrderSide::Bid);
+    let better_id = better.order_id;
+    let evicted = book.insert(OrderSide::Bid, better).unwrap().unwrap();
+    assert_eq!(evicted.order_id, 0);
+    assert_eq!(evicted.price_bps, 1_000);
+    assert_eq!(book.bids.len(), ORDER_BOOK_DEPTH);
+    assert_eq!(book.bids[ORDER_BOOK_DEPTH - 1].order_id, better_id);
+
+    println!("✅ Order book eviction test passed");
+}
+
+fn open_event(yes_amount: u64, no_amount: u64) -> OracleEvent {
+    OracleEvent {
+        event_id: 1,
+        category: EventCategory::General,
+        tags: [0; MAX_EVENT_TAGS],
+        description: "Will the dragon be slain?".to_string(),
+        resolved: false,
+        outcome: None,
+        authority: Pubkey::new_unique(),
+        total_bets: 2,
+        yes_votes: 1,
+        no_votes: 1,
+        total_amount: yes_amount + no_amount,
+        yes_amount,
+        no_amount,
+        cancelled: false,
+        min_stake: 1,
+        max_stake: u64::MAX,
+        season_id: 0,
+        version: ORACLE_EVENT_VERSION,
+        metadata_uri: String::new(),
+        metadata_hash: [0; 32],
+        outstanding_claims: 0,
+        open_parlay_legs: 0,
+        resolved_at: 0,
+    }
+}
+
+fn yes_bet(amount: u64) -> Bet {
+    Bet {
+        player: Pubkey::new_unique(),
+        event_id: 1,
+        chosen_outcome: true,
+        amount,
+        claimed: false,
+        settled: false,
+        version: BET_VERSION,
+    }
+}
+
+#[test]
+fn test_cash_out_quote_from_parimutuel_state() {
+    let event = open_event(1_000_000, 3_000_000);
+    let bet = yes_bet(1_000_000);
+
+    let quote = CashOutQuote::new(&event, &bet, 200).unwrap();
+
+    assert_eq!(quote.probability_bps, 2_500);
+    assert_eq!(quote.value, 1_000_000);
+    assert_eq!(quote.fee, 20_000);
+    assert_eq!(quote.payout, 980_000);
+
+    println!("✅ Parimutuel cash-out quote test passed");
+}
+
+#[test]
+fn test_cash_out_never_pays_above_stake() {
+    // YES would take the whole 4M pot, but cashing out early only returns
+    // the stake, so the NO side's stake stays in the pot
+    let event = open_event(1_000_000, 3_000_000);
+    let quote = CashOutQuote::new(&event, &yes_bet(1_000_000), 0).unwrap();
+    assert_eq!(quote.value, 1_000_000);
+    assert_eq!(quote.payout, 1_000_000);
+
+    // A bet recording more than its side holds is capped at the side
+    let quote = CashOutQuote::new(&event, &yes_bet(2_000_000), 0).unwrap();
+    assert_eq!(quote.value, 1_000_000);
+
+    println!("✅ Cash-out cap test passed");
+}
+
+#[test]
+fn test_parlay_leg_odds_from_parimutuel_split() {
+    let event = open_event(1_000_000, 3_000_000);
+
+    assert_eq!(event.leg_odds_bps(true), 40_000);
+    assert_eq!(event.leg_odds_bps(false), 13_333);
+    assert_eq!(
+        open_event(0, 1_000).leg_odds_bps(true),
+        DEFAULT_LEG_ODDS_BPS
+    );
+    assert_eq!(
+        open_event(1, 1_000_000).leg_odds_bps(true),
+        MAX_LEG_ODDS_BPS
+    );
+
+    println!("✅ Parlay leg odds test passed");
+}
+
+#[test]
+fn test_leg_odds_are_bounded() {
+    let mut odds = LegOdds {
+        event_id: 1,
+        yes_odds_bps: 0,
+        no_odds_bps: 0,
+        bump: 255,
+    };
+
+    odds.set(25_000, 16_000).unwrap();
+    assert_eq!(odds.odds_bps(true), 25_000);
+    assert_eq!(odds.odds_bps(false), 16_000);
+    // Odds of 1x or less would pay nothing on a win
+    assert!(odds.set(10_000, 16_000).is_err());
+    assert!(odds.set(25_000, MAX_LEG_ODDS_BPS + 1).is_err());
+    assert_eq!(odds.odds_bps(true), 25_000);
+
+    println!("✅ Leg odds bounds test passed");
+}
+
+#[test]
+fn test_parlay_payout_multiplies_legs() {
+    assert_eq!(parlay_payout(1_000, [20_000, 30_000]).unwrap(), 6_000);
+    // Dropping a cancelled leg recomputes over the remaining odds
+    assert_eq!(parlay_payout(1_000, [30_000]).unwrap(), 3_000);
+    // Every leg cancelled refunds the stake
+    assert_eq!(parlay_payout(1_000, []).unwrap(), 1_000);
+    assert!(parlay_payout(u64::MAX, [MAX_LEG_ODDS_BPS; 5]).is_err());
+
+    println!("✅ Parlay payout test passed");
+}
+
+#[test]
+fn test_event_stake_limits() {
+    let mut event = open_event(0, 0);
+    event.min_stake = 1_000;
+    event.max_stake = 5_000;
+
+    assert!(event.check_stake(0).is_err());
+    assert!(event.check_stake(999).is_err());
+    assert!(event.check_stake(1_000).is_ok());
+    assert!(event.check_stake(5_000).is_ok());
+    assert!(event.check_stake(u64::MAX).is_err());
+
+    println!("✅ Stake limits test passed");
+}
+
+#[test]
+fn test_player_exposure_cap() {
+    let mut profile = PlayerProfile {
+        owner: Pubkey::new_unique(),
+        ..Default::default()
+    };
+
+    profile.open_exposure(6_000, 10_000).unwrap();
+    assert!(profile.open_exposure(5_000, 10_000).is_err());
+    assert_eq!(profile.open_exposure, 6_000);
+    assert!(profile.open_exposure(u64::MAX, u64::MAX).is_err());
+
+    profile.release_exposure(6_000);
+    profile.open_exposure(10_000, 10_000).unwrap();
+    assert_eq!(profile.open_exposure, 10_000);
+
+    println!("✅ Player exposure cap test passed");
+}
+
+#[test]
+fn test_leveling_curve_is_quadratic() {
+    assert_eq!(level_for_xp(0), 0);
+    assert_eq!(level_for_xp(99), 0);
+    assert_eq!(level_for_xp(100), 1);
+    assert_eq!(level_for_xp(399), 1);
+    assert_eq!(level_for_xp(400), 2);
+    for level in 0..1_000 {
+        assert_eq!(level_for_xp(xp_for_level(level)), level);
+        assert_eq!(level_for_xp(xp_for_level(level + 1) - 1), level);
+    }
+
+    println!("✅ Leveling curve test passed");
+}
+
+#[test]
+fn test_profile_streaks_and_accuracy() {
+    let mut profile = PlayerProfile::default();
+
+    profile.record_win().unwrap();
+    profile.record_win().unwrap();
+    profile.record_loss().unwrap();
+    profile.record_win().unwrap();
+
+    assert_eq!(profile.bets_won, 3);
+    assert_eq!(profile.bets_lost, 1);
+    assert_eq!(profile.win_streak, 1);
+    assert_eq!(profile.loss_streak, 0);
+    assert_eq!(profile.best_win_streak, 2);
+    assert_eq!(profile.accuracy_bps, 7_500);
+    // 60 + 70 for the first streak, 5 for the loss, 60 after the reset
+    assert_eq!(profile.xp, 195);
+    assert_eq!(profile.level, 1);
+
+    println!("✅ Profile streak test passed");
+}
+
+#[test]
+fn test_profile_migration_backfills_xp() {
+    let legacy = PlayerProfileV0 {
+        owner: Pubkey::new_unique(),
+        balance: 5_000,
+        total_bets: 10,
+        bets_won: 4,
+    };
+
+    let profile = PlayerProfile::from(legacy.clone());
+
+    assert_eq!(profile.owner, legacy.owner);
+    assert_eq!(profile.balance, 5_000);
+    assert_eq!(profile.version, PLAYER_PROFILE_VERSION);
+    assert_eq!(profile.xp, 300);
+    assert_eq!(profile.level, 1);
+    assert_eq!(profile.referrer, None);
+
+    println!("✅ Profile migration test passed");
+}
+
+#[test]
+fn test_bet_and_event_migration() {
+    let bet = Bet::from(BetV0 {
+        player: Pubkey::new_unique(),
+        event_id: 7,
+        chosen_outcome: false,
+        amount: 1_000,
+        claimed: true,
+    });
+    assert_eq!(bet.version, BET_VERSION);
+    assert_eq!(bet.amount, 1_000);
+    // Claimed legacy bets were already paid out
+    assert!(bet.settled);
+
+    let event = OracleEvent::from(OracleEventV0 {
+        event_id: 7,
+        description: "Will the dragon be slain?".to_string(),
+        resolved: true,
+        outcome: Some(false),
+        authority: Pubkey::new_unique(),
+        total_bets: 3,
+        yes_votes: 1,
+        no_votes: 2,
+        total_amount: 3_000,
+    });
+    assert_eq!(event.version, ORACLE_EVENT_VERSION);
+    assert_eq!(event.outcome, Some(false));
+    // Both NO bets may still be unclaimed
+    assert_eq!(event.outstanding_claims, 2);
+    // The legacy total was never held by the account
+    assert_eq!(event.total_amount, 0);
+    assert_eq!(event.yes_amount + event.no_amount, 0);
+    assert!(event.check_stake(1).is_ok());
+    assert!(!event.cancelled);
+    assert!(event.metadata_uri.is_empty());
+    assert_eq!(event.category, EventCategory::General);
+    assert_eq!(event.tags, [0; MAX_EVENT_TAGS]);
+
+    // New stake on a migrated event pays out exactly the new pot
+    let mut event = OracleEvent::from(OracleEventV0 {
+        event_id: 8,
+        description: "Will the bridge hold?".to_string(),
+        resolved: false,
+        outcome: None,
+        authority: Pubkey::new_unique(),
+        total_bets: 2,
+        yes_votes: 1,
+        no_votes: 1,
+        total_amount: 5_000,
+    });
+    event.record_bet(true, 1_000).unwrap();
+    event.record_bet(false, 3_000).unwrap();
+    event.outcome = Some(true);
+    assert_eq!(event.parimutuel_payout(1_000).unwrap(), 4_000);
+
+    println!("✅ Bet and event migration test passed");
+}
+
+#[test]
+fn test_event_space_tracks_description() {
+    let description = "x".repeat(MAX_DESCRIPTION_LEN);
+    let metadata_uri = "x".repeat(MAX_METADATA_URI_LEN);
+    assert_eq!(
+        OracleEvent::space(&description, &metadata_uri),
+        8 + OracleEvent::INIT_SPACE
+    );
+    assert_eq!(
+        OracleEvent::space("", "") + MAX_DESCRIPTION_LEN + MAX_METADATA_URI_LEN,
+        8 + OracleEvent::INIT_SPACE
+    );
+
+    // A full-length event still fits its serialized form
+    let mut event = open_event(0, 0);
+    event.description = description;
+    event.metadata_uri = metadata_uri;
+    event.outcome = Some(true);
+    let mut data = vec![];
+    event.try_serialize(&mut data).unwrap();
+    assert_eq!(
+        data.len(),
+        OracleEvent::space(&event.description, &event.metadata_uri)
+    );
+
+    println!("✅ Event space test passed");
+}
+
+#[test]
+fn test_season_prize_merkle_proofs() {
+    let players: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let leaves: Vec<[u8; 32]> = players
+        .iter()
+        .zip([500, 300, 200])
+        .map(|(player, amount)| season_prize_leaf(1, player, amount))
+        .collect();
+    // Odd leaf out is promoted to the next level unchanged
+    let left = merkle_parent(leaves[0], leaves[1]);
+    let root = merkle_parent(left, leaves[2]);
+
+    assert!(verify_merkle_proof(
+        &[leaves[1], leaves[2]],
+        root,
+        leaves[0]
+    ));
+    assert!(verify_merkle_proof(
+        &[leaves[0], leaves[2]],
+        root,
+        leaves[1]
+    ));
+    assert!(verify_merkle_proof(&[left], root, leaves[2]));
+    // Inflated amount, wrong season, or a truncated proof
+    assert!(!verify_merkle_proof(
+        &[leaves[1], leaves[2]],
+        root,
+        season_prize_leaf(1, &players[0], 501)
+    ));
+    assert!(!verify_merkle_proof(
+        &[leaves[1], leaves[2]],
+        root,
+        season_prize_leaf(2, &players[0], 500)
+    ));
+    assert!(!verify_merkle_proof(&[leaves[1]], root, leaves[0]));
+
+    println!("✅ Season Merkle proof test passed");
+}
+
+#[test]
+fn test_season_score_only_counts_wins_inside_the_window() {
+    let season = Season {
+        season_id: 1,
+        start_ts: 1_000,
+        end_ts: 2_000,
+        prize_pool: 0,
+        total_claimed: 0,
+        merkle_root: [0; 32],
+        finalized: false,
+        bump: 255,
+    };
+    assert!(!season.is_active(999));
+    assert!(season.is_active(1_000));
+    assert!(season.is_active(1_999));
+    assert!(!season.is_active(2_000));
+
+    let mut score = SeasonScore {
+        season_id: 1,
+        player: Pubkey::new_unique(),
+        score: 0,
+        wins: 0,
+        bump: 255,
+    };
+    score.record_win(&season, 1_500, 300).unwrap();
+    score.record_win(&season, 500, 700).unwrap();
+    score.record_win(&season, 2_000, 700).unwrap();
+
+    assert_eq!(score.score, 300);
+    assert_eq!(score.wins, 1);
+
+    println!("✅ Season window test passed");
+}
+
+#[test]
+fn test_protocol_fee_referral_split() {
+    assert_eq!(
+        split_protocol_fee(1_000_000, 500, 2_000).unwrap(),
+        (50_000, 10_000)
+    );
+    assert_eq!(split_protocol_fee(1_000_000, 500, 0).unwrap(), (50_000, 0));
+    assert_eq!(split_protocol_fee(1_000_000, 0, 2_000).unwrap(), (0, 0));
+    // The referral cut never exceeds the fee
+    let (fee, cut) = split_protocol_fee(u64::MAX, 1_000, 10_000).unwrap();
+    assert_eq!(fee, cut);
+
+    println!("✅ Protocol fee referral split test passed");
+}
+
+#[test]
+fn test_event_filter_offsets() {
+    let mut event = open_event(0, 0);
+    event.category = EventCategory::Sports;
+    event.tags = normalize_tags([0, 9, 3, 0]).unwrap();
+    event.resolved = true;
+    let mut data = vec![];
+    event.try_serialize(&mut data).unwrap();
+
+    assert_eq!(data[EVENT_CATEGORY_OFFSET], EventCategory::Sports as u8);
+    assert_eq!(
+        data[EVENT_TAGS_OFFSET..EVENT_TAGS_OFFSET + MAX_EVENT_TAGS],
+        [3, 9, 0, 0]
+    );
+    assert_eq!(data[EVENT_RESOLVED_OFFSET], 1);
+    assert_eq!(
+        data[EVENT_AUTHORITY_OFFSET..EVENT_AUTHORITY_OFFSET + 32],
+        event.authority.to_bytes()
+    );
+    // Offsets hold regardless of the description and outcome
+    event.description = "x".repeat(MAX_DESCRIPTION_LEN);
+    event.outcome = Some(true);
+    let mut longer = vec![];
+    event.try_serialize(&mut longer).unwrap();
+    assert_eq!(
+        longer[..EVENT_AUTHORITY_OFFSET + 32],
+        data[..EVENT_AUTHORITY_OFFSET + 32]
+    );
+
+    println!("✅ Event filter offsets test passed");
+}
+
+#[test]
+fn test_event_tags_and_category_ids() {
+    assert_eq!(normalize_tags([0, 0, 0, 0]).unwrap(), [0, 0, 0, 0]);
+    assert_eq!(normalize_tags([7, 0, 2, 5]).unwrap(), [2, 5, 7, 0]);
+    assert!(normalize_tags([4, 0, 4, 1]).is_err());
+
+    assert_eq!(category_event_id(EventCategory::General, 5).unwrap(), 5);
+    assert_eq!(
+        category_event_id(EventCategory::Crypto, 1).unwrap(),
+        (2 << CATEGORY_SEQ_BITS) | 1
+    );
+    assert!(category_event_id(EventCategory::Crypto, 1 << CATEGORY_SEQ_BITS).is_err());
+
+    println!("✅ Event tags and category id test passed");
+}
+
+#[test]
+fn test_category_counter_allocation() {
+    let mut counter = CategoryCounter {
+        category: EventCategory::Crypto,
+        start_seq: 2,
+        next_seq: 2,
+        bump: 255,
+    };
+    let first = counter.allocate().unwrap();
+    assert_eq!(first, category_event_id(EventCategory::Crypto, 2).unwrap());
+    assert_eq!(
+        counter.allocate().unwrap(),
+        category_event_id(EventCategory::Crypto, 3).unwrap()
+    );
+    assert_eq!(counter.next_seq, 4);
+
+    // Only the reserved range of the same category is open to explicit ids
+    assert!(counter
+        .check_explicit(category_event_id(EventCategory::Crypto, 1).unwrap())
+        .is_ok());
+    assert!(counter.check_explicit(first).is_err());
+    assert!(counter
+        .check_explicit(category_event_id(EventCategory::Sports, 0).unwrap())
+        .is_err());
+
+    println!("✅ Category counter allocation test passed");
+}
+
+#[test]
+fn test_session_token_authorization() {
+    let owner = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut session = SessionToken {
+        owner,
+        session_key: key,
+        scopes: SESSION_SCOPE_PLACE_BET,
+        max_stake: 1_000,
+        staked: 0,
+        expires_at: 100,
+        bump: 255,
+    };
+
+    assert!(session
+        .authorize(&owner, &key, SESSION_SCOPE_PLACE_BET, 99)
+        .is_ok());
+    assert!(session
+        .authorize(&owner, &key, SESSION_SCOPE_PLACE_BET, 100)
+        .is_err());
+    assert!(session
+        .authorize(&owner, &key, SESSION_SCOPE_CLAIM, 0)
+        .is_err());
+    assert!(session
+        .authorize(&owner, &Pubkey::new_unique(), SESSION_SCOPE_PLACE_BET, 0)
+        .is_err());
+    assert!(session
+        .authorize(&Pubkey::new_unique(), &key, SESSION_SCOPE_PLACE_BET, 0)
+        .is_err());
+
+    session.spend(600).unwrap();
+    assert!(session.spend(401).is_err());
+    session.spend(400).unwrap();
+    assert_eq!(session.staked, 1_000);
+
+    println!("✅ Session token authorization test passed");
+}
+
+#[test]
+fn test_guild_share_accounting() {
+    let founder = Pubkey::new_unique();
+    let manager = Pubkey::new_unique();
+    let mut guild = Guild {
+        founder,
+        guild_id: 1,
+        members: vec![
+            GuildMember {
+                member: founder,
+                role: GuildRole::Owner,
+                shares: 0,
+            },
+            GuildMember {
+                member: manager,
+                role: GuildRole::Manager,
+                shares: 0,
+            },
+        ],
+        total_shares: 0,
+        bankroll: 0,
+        open_stake: 0,
+        bump: 255,
+    };
+
+    assert!(guild.require_role(&founder, GuildRole::Owner).is_ok());
+    assert!(guild.require_role(&manager, GuildRole::Manager).is_ok());
+    assert!(guild.require_role(&manager, GuildRole::Owner).is_err());
+    assert!(guild
+        .require_role(&Pubkey::new_unique(), GuildRole::Member)
+        .is_err());
+
+    // First deposit mints one share per lamport
+    assert_eq!(guild.shares_for_deposit(1_000).unwrap(), 1_000);
+    guild.total_shares = 1_000;
+    guild.bankroll = 1_000;
+
+    // After the guild doubles its value, new money buys half as many shares
+    guild.bankroll = 1_500;
+    guild.open_stake = 500;
+    assert_eq!(guild.shares_for_deposit(1_000).unwrap(), 500);
+    assert_eq!(guild.withdrawal_value(250).unwrap(), 500);
+    assert!(guild.withdrawal_value(1_001).is_err());
+
+    // A wiped-out guild starts pricing from scratch
+    guild.bankroll = 0;
+    guild.open_stake = 0;
+    assert_eq!(guild.shares_for_deposit(700).unwrap(), 700);
+
+    println!("✅ Guild share accounting test passed");
+}
+
+#[test]
+fn test_payout_quote_and_event_summary() {
+    let mut event = open_event(10_000, 30_000);
+
+    // The quoted bet joins the pool before it is priced
+    let quote = event.quote_payout(true, 10_000, 200).unwrap();
+    assert_eq!(quote.implied_probability_bps, 4_000);
+    assert_eq!(quote.odds_bps, 25_000);
+    assert_eq!(quote.gross_payout, 25_000);
+    assert_eq!(quote.protocol_fee, 300);
+    assert_eq!(quote.potential_payout, 24_700);
+    assert!(event.quote_payout(true, 0, 200).is_err());
+
+    let summary = event.summary();
+    assert_eq!(summary.yes_probability_bps, 2_500);
+    assert_eq!(summary.yes_odds_bps, 40_000);
+    assert_eq!(summary.no_odds_bps, 13_333);
+    assert_eq!(open_event(0, 0).summary().yes_probability_bps, 5_000);
+
+    event.resolved = true;
+    event.outcome = Some(true);
+    assert!(event.quote_payout(true, 10_000, 200).is_err());
+
+    println!("✅ Payout quote and event summary test passed");
+}
+
+#[test]
+fn test_payout_quote_without_profit_takes_no_fee() {
+    // A pot smaller than the side stakes prices the bet below its cost
+    let mut event = open_event(10_000, 30_000);
+    event.total_amount = 0;
+
+    let quote = event.quote_payout(true, 10_000, 200).unwrap();
+    assert_eq!(quote.gross_payout, 5_000);
+    assert_eq!(quote.protocol_fee, 0);
+    assert_eq!(quote.potential_payout, 5_000);
+
+    println!("✅ Payout quote without profit test passed");
+}
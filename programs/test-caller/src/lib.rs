@@ -0,0 +1,184 @@
+//! Example program betting through `simple_oracle_quest::interface`.
+//!
+//! Each user gets a vault PDA that plays as an oracle-quest player: it
+//! holds the user's deposit, places bets with it and receives the payouts.
+//! Only meant to exercise the CPI contract in integration tests.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use simple_oracle_quest::{interface, program::SimpleOracleQuest};
+
+declare_id!("yhDVenLNz8JpNRKrd3LvQGRBfT9pLVQFKoM2dJDoVp1");
+
+#[program]
+pub mod test_caller {
+    use super::*;
+
+    /// Funds the user's vault with `deposit` and opens its player profile.
+    pub fn register(ctx: Context<Register>, deposit: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            deposit,
+        )?;
+
+        let user = ctx.accounts.user.key();
+        let seeds: &[&[u8]] = &[b"vault", user.as_ref(), &[ctx.bumps.vault]];
+        interface::initialize_player(
+            ctx.accounts.oracle_program.to_account_info(),
+            interface::InitializePlayerAccounts {
+                player_profile: ctx.accounts.player_profile.to_account_info(),
+                player: ctx.accounts.vault.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            &[seeds],
+        )
+    }
+
+    /// Bets from the vault and records the returned totals on a position.
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        event_id: u64,
+        chosen_outcome: bool,
+        amount: u64,
+    ) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let seeds: &[&[u8]] = &[b"vault", user.as_ref(), &[ctx.bumps.vault]];
+        let placed = interface::place_bet(
+            ctx.accounts.oracle_program.to_account_info(),
+            interface::PlaceBetAccounts {
+                bet: ctx.accounts.bet.to_account_info(),
+                player_profile: ctx.accounts.player_profile.to_account_info(),
+                oracle_event: ctx.accounts.oracle_event.to_account_info(),
+                config: ctx.accounts.config.to_account_info(),
+                player: ctx.accounts.vault.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            &[seeds],
+            event_id,
+            chosen_outcome,
+            amount,
+        )?;
+        require_keys_eq!(placed.bet, ctx.accounts.bet.key());
+
+        let position = &mut ctx.accounts.position;
+        position.bet = placed.bet;
+        position.event_id = placed.event_id;
+        position.amount = amount;
+        position.event_total_bets = placed.total_bets;
+        position.event_total_amount = placed.total_amount;
+        Ok(())
+    }
+
+    /// Settles and closes the position's bet; payouts land in the vault.
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let seeds: &[&[u8]] = &[b"vault", user.as_ref(), &[ctx.bumps.vault]];
+        interface::settle_bet(
+            ctx.accounts.oracle_program.to_account_info(),
+            interface::SettleBetAccounts {
+                bet: ctx.accounts.bet.to_account_info(),
+                oracle_event: ctx.accounts.oracle_event.to_account_info(),
+                player_profile: ctx.accounts.player_profile.to_account_info(),
+                season_score: None,
+                config: ctx.accounts.config.to_account_info(),
+                referrer_rewards: None,
+                player: ctx.accounts.vault.to_account_info(),
+                season: None,
+            },
+            &[seeds],
+            true,
+        )
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub bet: Pubkey,
+    pub event_id: u64,
+    pub amount: u64,
+    /// Event totals right after this position's bet.
+    pub event_total_bets: u64,
+    pub event_total_amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct Register<'info> {
+    /// CHECK: system-owned PDA playing for `user`.
+    #[account(mut, seeds = [b"vault", user.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: initialized by the oracle program.
+    #[account(mut)]
+    pub player_profile: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub oracle_program: Program<'info, SimpleOracleQuest>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(event_id: u64)]
+pub struct OpenPosition<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), event_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: system-owned PDA playing for `user`.
+    #[account(mut, seeds = [b"vault", user.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: initialized by the oracle program.
+    #[account(mut)]
+    pub bet: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub player_profile: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub oracle_event: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    pub config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub oracle_program: Program<'info, SimpleOracleQuest>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"position", vault.key().as_ref(), position.event_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = bet
+    )]
+    pub position: Account<'info, Position>,
+    /// CHECK: system-owned PDA playing for `user`.
+    #[account(mut, seeds = [b"vault", user.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub bet: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub player_profile: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub oracle_event: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub oracle_program: Program<'info, SimpleOracleQuest>,
+}
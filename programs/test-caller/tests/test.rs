@@ -0,0 +1,338 @@
+#![cfg(feature = "test-sbf")]
+
+use anchor_lang::{
+    solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    system_program, AccountDeserialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use litesvm::LiteSVM;
+use simple_oracle_quest::{
+    interface::{bet_address, config_address, event_address, player_address},
+    BetPlaced, ConfigParams, EventCategory, EventParams, OracleEvent, PlayerProfile,
+};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use test_caller::Position;
+
+const DEPLOY_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy");
+
+fn setup() -> LiteSVM {
+    let mut svm = LiteSVM::new();
+    deploy_upgradeable(&mut svm);
+    svm.add_program_from_file(test_caller::ID, format!("{DEPLOY_DIR}/test_caller.so"))
+        .unwrap();
+    svm
+}
+
+fn program_data_pda() -> Pubkey {
+    bpf_loader_upgradeable::get_program_data_address(&simple_oracle_quest::ID)
+}
+
+/// Deploys the oracle program under the upgradeable loader, as on a
+/// cluster, so `initialize_config` has an upgrade authority to check.
+fn deploy_upgradeable(svm: &mut LiteSVM) {
+    let elf = std::fs::read(format!("{DEPLOY_DIR}/simple_oracle_quest.so")).unwrap();
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let mut program_data = Account::new_data_with_space(
+        svm.minimum_balance_for_rent_exemption(metadata_len + elf.len()),
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(Pubkey::default()),
+        },
+        metadata_len + elf.len(),
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program_data.data[metadata_len..].copy_from_slice(&elf);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+
+    let mut program = Account::new_data(
+        svm.minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+        &UpgradeableLoaderState::Program {
+            programdata_address: program_data_pda(),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program.executable = true;
+    svm.set_account(simple_oracle_quest::ID, program).unwrap();
+}
+
+/// Hands the oracle program's upgrade authority to `authority`.
+fn set_upgrade_authority(svm: &mut LiteSVM, authority: &Pubkey) {
+    let mut program_data = svm.get_account(&program_data_pda()).unwrap();
+    let metadata = Account::new_data(
+        0,
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(*authority),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap()
+    .data;
+    program_data.data[..metadata.len()].copy_from_slice(&metadata);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+}
+
+fn funded_keypair(svm: &mut LiteSVM) -> Keypair {
+    let keypair = Keypair::new();
+    svm.airdrop(&keypair.pubkey(), 100_000_000_000).unwrap();
+    keypair
+}
+
+fn try_send(svm: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> Result<Vec<u8>, Vec<String>> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx);
+    svm.expire_blockhash();
+    result
+        .map(|meta| meta.return_data.data)
+        .map_err(|e| e.meta.logs)
+}
+
+fn send(svm: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> Vec<u8> {
+    try_send(svm, ix, signer).unwrap_or_else(|logs| panic!("transaction failed: {logs:?}"))
+}
+
+fn fetch<T: AccountDeserialize>(svm: &LiteSVM, address: &Pubkey) -> T {
+    let account = svm.get_account(address).unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn caller_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &test_caller::ID).0
+}
+
+fn category_counter() -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"category", &[EventCategory::General as u8]],
+        &simple_oracle_quest::ID,
+    )
+    .0
+}
+
+/// Sets up the config and opens event `0` with `admin` as its authority.
+fn setup_event(svm: &mut LiteSVM, admin: &Keypair) -> u64 {
+    set_upgrade_authority(svm, &admin.pubkey());
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializeConfig {
+            config: config_address(),
+            program: simple_oracle_quest::ID,
+            program_data: program_data_pda(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeConfig {
+            params: ConfigParams {
+                treasury: admin.pubkey(),
+                cash_out_fee_bps: 100,
+                max_house_exposure: 1_000_000_000,
+                max_player_exposure: 10_000_000_000,
+                protocol_fee_bps: 200,
+                referral_share_bps: 2_500,
+                allow_explicit_event_ids: false,
+                crank_bounty: 0,
+            },
+        }
+        .data(),
+    };
+    send(svm, ix, admin);
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializeCategoryCounter {
+            category_counter: category_counter(),
+            config: config_address(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeCategoryCounter {
+            category: EventCategory::General,
+            start_seq: 0,
+        }
+        .data(),
+    };
+    send(svm, ix, admin);
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::CreateOracleEvent {
+            category_counter: category_counter(),
+            oracle_event: event_address(0),
+            season: None,
+            authority: admin.pubkey(),
+            payer: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::CreateOracleEvent {
+            params: EventParams {
+                category: EventCategory::General,
+                tags: [0; 4],
+                description: "Will the bridge hold?".to_string(),
+                min_stake: 1_000,
+                max_stake: 1_000_000_000,
+                season_id: 0,
+            },
+        }
+        .data(),
+    };
+    send(svm, ix, admin);
+    0
+}
+
+#[test]
+fn test_caller_program_bets_and_settles_through_interface() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let user = funded_keypair(&mut svm);
+    let rival = funded_keypair(&mut svm);
+    let event_id = setup_event(&mut svm, &admin);
+
+    let vault = caller_pda(&[b"vault", user.pubkey().as_ref()]);
+    let ix = Instruction {
+        program_id: test_caller::ID,
+        accounts: test_caller::accounts::Register {
+            vault,
+            player_profile: player_address(&vault),
+            user: user.pubkey(),
+            oracle_program: simple_oracle_quest::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: test_caller::instruction::Register {
+            deposit: 1_000_000_000,
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &user);
+    assert_eq!(
+        fetch::<PlayerProfile>(&svm, &player_address(&vault)).owner,
+        vault
+    );
+
+    // A direct bet returns the totals as return data too
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializePlayer {
+            player_profile: player_address(&rival.pubkey()),
+            referrer_rewards: None,
+            signer: rival.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializePlayer { referrer: None }.data(),
+    };
+    send(&mut svm, ix, &rival);
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::PlaceBet {
+            bet: bet_address(&rival.pubkey(), event_id),
+            player_profile: player_address(&rival.pubkey()),
+            oracle_event: event_address(event_id),
+            config: config_address(),
+            player: rival.pubkey(),
+            session_token: None,
+            signer: rival.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::PlaceBet {
+            event_id,
+            chosen_outcome: false,
+            amount: 30_000,
+        }
+        .data(),
+    };
+    let placed = BetPlaced::try_from_slice(&send(&mut svm, ix, &rival)).unwrap();
+    assert_eq!(placed.bet, bet_address(&rival.pubkey(), event_id));
+    assert_eq!((placed.total_bets, placed.total_amount), (1, 30_000));
+
+    // The caller stores what the CPI returned
+    let bet = bet_address(&vault, event_id);
+    let position = caller_pda(&[b"position", vault.as_ref(), &event_id.to_le_bytes()]);
+    let ix = Instruction {
+        program_id: test_caller::ID,
+        accounts: test_caller::accounts::OpenPosition {
+            position,
+            vault,
+            bet,
+            player_profile: player_address(&vault),
+            oracle_event: event_address(event_id),
+            config: config_address(),
+            user: user.pubkey(),
+            oracle_program: simple_oracle_quest::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: test_caller::instruction::OpenPosition {
+            event_id,
+            chosen_outcome: true,
+            amount: 10_000,
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &user);
+    let stored: Position = fetch(&svm, &position);
+    assert_eq!(stored.bet, bet);
+    assert_eq!(
+        (stored.event_total_bets, stored.event_total_amount),
+        (2, 40_000)
+    );
+    let event: OracleEvent = fetch(&svm, &event_address(event_id));
+    assert_eq!((event.yes_amount, event.no_amount), (10_000, 30_000));
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ResolveEvent {
+            oracle_event: event_address(event_id),
+            authority: admin.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::ResolveEvent { outcome: true }.data(),
+    };
+    send(&mut svm, ix, &admin);
+
+    // Winnings (4x, less 2% of the 30_000 profit) and the bet's rent go to the vault
+    let vault_before = svm.get_account(&vault).unwrap().lamports;
+    let bet_rent = svm.get_account(&bet).unwrap().lamports;
+    let ix = Instruction {
+        program_id: test_caller::ID,
+        accounts: test_caller::accounts::ClosePosition {
+            position,
+            vault,
+            bet,
+            player_profile: player_address(&vault),
+            oracle_event: event_address(event_id),
+            config: config_address(),
+            user: user.pubkey(),
+            oracle_program: simple_oracle_quest::ID,
+        }
+        .to_account_metas(None),
+        data: test_caller::instruction::ClosePosition {}.data(),
+    };
+    send(&mut svm, ix, &user);
+    assert_eq!(
+        svm.get_account(&vault).unwrap().lamports,
+        vault_before + 40_000 - 600 + bet_rent
+    );
+    assert!(svm
+        .get_account(&bet)
+        .is_none_or(|account| account.lamports == 0));
+    assert_eq!(
+        fetch::<PlayerProfile>(&svm, &player_address(&vault)).bets_won,
+        1
+    );
+}
@@ -0,0 +1,183 @@
+//! Minimal M-of-N multisig whose vault PDA acts as an oracle event
+//! authority, signing into `simple_oracle_quest` through `invoke_signed`.
+//!
+//! Only meant to exercise PDA authorities in integration tests; it has no
+//! proposal queue and executes as soon as enough owners sign the same
+//! transaction.
+
+use anchor_lang::prelude::*;
+use simple_oracle_quest::{cpi, program::SimpleOracleQuest, EventParams};
+
+declare_id!("Cv6H6cyPiLga3xtAhpxouKjxEPthYbAN5uR1M645ANxm");
+
+pub const MAX_OWNERS: usize = 8;
+
+#[program]
+pub mod test_multisig {
+    use super::*;
+
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            !owners.is_empty() && owners.len() <= MAX_OWNERS,
+            ErrorCode::InvalidOwners
+        );
+        require!(
+            threshold > 0 && threshold as usize <= owners.len(),
+            ErrorCode::InvalidThreshold
+        );
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.owners = owners;
+        multisig.threshold = threshold;
+        multisig.bump = ctx.bumps.multisig;
+        multisig.vault_bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    /// Creates an oracle event with the vault as its authority. Owner
+    /// approvals are passed as signer `remaining_accounts`.
+    pub fn create_event<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateEvent<'info>>,
+        params: EventParams,
+    ) -> Result<()> {
+        check_approvals(&ctx.accounts.multisig, ctx.remaining_accounts)?;
+
+        let multisig = ctx.accounts.multisig.key();
+        let seeds: &[&[u8]] = &[
+            b"vault",
+            multisig.as_ref(),
+            &[ctx.accounts.multisig.vault_bump],
+        ];
+        let accounts = cpi::accounts::CreateOracleEvent {
+            category_counter: ctx.accounts.category_counter.to_account_info(),
+            oracle_event: ctx.accounts.oracle_event.to_account_info(),
+            season: None,
+            authority: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        cpi::create_oracle_event(
+            CpiContext::new_with_signer(
+                ctx.accounts.oracle_program.to_account_info(),
+                accounts,
+                &[seeds],
+            ),
+            params,
+        )
+    }
+
+    /// Resolves an event the vault is the authority of.
+    pub fn resolve_event<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveEvent<'info>>,
+        outcome: bool,
+    ) -> Result<()> {
+        check_approvals(&ctx.accounts.multisig, ctx.remaining_accounts)?;
+
+        let multisig = ctx.accounts.multisig.key();
+        let seeds: &[&[u8]] = &[
+            b"vault",
+            multisig.as_ref(),
+            &[ctx.accounts.multisig.vault_bump],
+        ];
+        let accounts = cpi::accounts::ResolveEvent {
+            oracle_event: ctx.accounts.oracle_event.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        cpi::resolve_event(
+            CpiContext::new_with_signer(
+                ctx.accounts.oracle_program.to_account_info(),
+                accounts,
+                &[seeds],
+            ),
+            outcome,
+        )
+    }
+}
+
+/// Counts the distinct owners among the signing `approvers`.
+fn check_approvals(multisig: &Multisig, approvers: &[AccountInfo]) -> Result<()> {
+    let mut signed: Vec<&Pubkey> = approvers
+        .iter()
+        .filter(|info| info.is_signer && multisig.owners.contains(info.key))
+        .map(|info| info.key)
+        .collect();
+    signed.sort();
+    signed.dedup();
+    require!(
+        signed.len() >= multisig.threshold as usize,
+        ErrorCode::NotEnoughApprovals
+    );
+    Ok(())
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    #[max_len(MAX_OWNERS)]
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Multisig::INIT_SPACE,
+        seeds = [b"multisig", creator.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    /// CHECK: signing-only PDA, never initialized.
+    #[account(seeds = [b"vault", multisig.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateEvent<'info> {
+    pub multisig: Account<'info, Multisig>,
+    /// CHECK: signing-only PDA.
+    #[account(seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub category_counter: UncheckedAccount<'info>,
+    /// CHECK: initialized by the oracle program.
+    #[account(mut)]
+    pub oracle_event: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub oracle_program: Program<'info, SimpleOracleQuest>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveEvent<'info> {
+    pub multisig: Account<'info, Multisig>,
+    /// CHECK: signing-only PDA.
+    #[account(seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: UncheckedAccount<'info>,
+    /// CHECK: validated by the oracle program.
+    #[account(mut)]
+    pub oracle_event: UncheckedAccount<'info>,
+    pub oracle_program: Program<'info, SimpleOracleQuest>,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Owner list is empty or too long")]
+    InvalidOwners,
+    #[msg("Threshold must be between 1 and the number of owners")]
+    InvalidThreshold,
+    #[msg("Not enough owners approved")]
+    NotEnoughApprovals,
+}
@@ -0,0 +1,287 @@
+#![cfg(feature = "test-sbf")]
+
+use anchor_lang::{
+    solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use litesvm::LiteSVM;
+use simple_oracle_quest::{ConfigParams, EventCategory, EventParams, OracleEvent};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const DEPLOY_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy");
+
+fn setup() -> LiteSVM {
+    let mut svm = LiteSVM::new();
+    deploy_upgradeable(&mut svm);
+    svm.add_program_from_file(test_multisig::ID, format!("{DEPLOY_DIR}/test_multisig.so"))
+        .unwrap();
+    svm
+}
+
+fn program_data_pda() -> Pubkey {
+    bpf_loader_upgradeable::get_program_data_address(&simple_oracle_quest::ID)
+}
+
+/// Deploys the oracle program under the upgradeable loader, as on a
+/// cluster, so `initialize_config` has an upgrade authority to check.
+fn deploy_upgradeable(svm: &mut LiteSVM) {
+    let elf = std::fs::read(format!("{DEPLOY_DIR}/simple_oracle_quest.so")).unwrap();
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let mut program_data = Account::new_data_with_space(
+        svm.minimum_balance_for_rent_exemption(metadata_len + elf.len()),
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(Pubkey::default()),
+        },
+        metadata_len + elf.len(),
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program_data.data[metadata_len..].copy_from_slice(&elf);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+
+    let mut program = Account::new_data(
+        svm.minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program()),
+        &UpgradeableLoaderState::Program {
+            programdata_address: program_data_pda(),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap();
+    program.executable = true;
+    svm.set_account(simple_oracle_quest::ID, program).unwrap();
+}
+
+/// Hands the oracle program's upgrade authority to `authority`.
+fn set_upgrade_authority(svm: &mut LiteSVM, authority: &Pubkey) {
+    let mut program_data = svm.get_account(&program_data_pda()).unwrap();
+    let metadata = Account::new_data(
+        0,
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(*authority),
+        },
+        &bpf_loader_upgradeable::ID,
+    )
+    .unwrap()
+    .data;
+    program_data.data[..metadata.len()].copy_from_slice(&metadata);
+    svm.set_account(program_data_pda(), program_data).unwrap();
+}
+
+fn funded_keypair(svm: &mut LiteSVM) -> Keypair {
+    let keypair = Keypair::new();
+    svm.airdrop(&keypair.pubkey(), 100_000_000_000).unwrap();
+    keypair
+}
+
+fn try_send(svm: &mut LiteSVM, ix: Instruction, signers: &[&Keypair]) -> Result<(), Vec<String>> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signers[0].pubkey()),
+        signers,
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx);
+    svm.expire_blockhash();
+    result.map(|_| ()).map_err(|e| e.meta.logs)
+}
+
+fn send(svm: &mut LiteSVM, ix: Instruction, signers: &[&Keypair]) {
+    try_send(svm, ix, signers).unwrap_or_else(|logs| panic!("transaction failed: {logs:?}"));
+}
+
+fn fetch<T: AccountDeserialize>(svm: &LiteSVM, address: &Pubkey) -> T {
+    let account = svm.get_account(address).unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn oracle_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &simple_oracle_quest::ID).0
+}
+
+fn multisig_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &test_multisig::ID).0
+}
+
+/// Owner approvals, passed to the multisig as signer remaining accounts.
+fn approvals(owners: &[&Keypair]) -> Vec<AccountMeta> {
+    owners
+        .iter()
+        .map(|owner| AccountMeta::new_readonly(owner.pubkey(), true))
+        .collect()
+}
+
+fn setup_oracle(svm: &mut LiteSVM, admin: &Keypair) {
+    let config = oracle_pda(&[b"config"]);
+    set_upgrade_authority(svm, &admin.pubkey());
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializeConfig {
+            config,
+            program: simple_oracle_quest::ID,
+            program_data: program_data_pda(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeConfig {
+            params: ConfigParams {
+                treasury: admin.pubkey(),
+                cash_out_fee_bps: 100,
+                max_house_exposure: 1_000_000_000,
+                max_player_exposure: 10_000_000_000,
+                protocol_fee_bps: 200,
+                referral_share_bps: 2_500,
+                allow_explicit_event_ids: false,
+                crank_bounty: 0,
+            },
+        }
+        .data(),
+    };
+    send(svm, ix, &[admin]);
+
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::InitializeCategoryCounter {
+            category_counter: oracle_pda(&[b"category", &[EventCategory::General as u8]]),
+            config,
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::InitializeCategoryCounter {
+            category: EventCategory::General,
+            start_seq: 0,
+        }
+        .data(),
+    };
+    send(svm, ix, &[admin]);
+}
+
+fn resolve_through_multisig(
+    svm: &mut LiteSVM,
+    multisig: Pubkey,
+    vault: Pubkey,
+    event: Pubkey,
+    owners: &[&Keypair],
+) -> Result<(), Vec<String>> {
+    let mut accounts = test_multisig::accounts::ResolveEvent {
+        multisig,
+        vault,
+        oracle_event: event,
+        oracle_program: simple_oracle_quest::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(approvals(owners));
+    let ix = Instruction {
+        program_id: test_multisig::ID,
+        accounts,
+        data: test_multisig::instruction::ResolveEvent { outcome: true }.data(),
+    };
+    try_send(svm, ix, owners)
+}
+
+#[test]
+fn test_multisig_vault_creates_and_resolves_events_over_cpi() {
+    let mut svm = setup();
+    let admin = funded_keypair(&mut svm);
+    let owners = [
+        funded_keypair(&mut svm),
+        funded_keypair(&mut svm),
+        funded_keypair(&mut svm),
+    ];
+    let [alice, bob, carol] = &owners;
+    setup_oracle(&mut svm, &admin);
+
+    let multisig = multisig_pda(&[b"multisig", alice.pubkey().as_ref()]);
+    let vault = multisig_pda(&[b"vault", multisig.as_ref()]);
+    let ix = Instruction {
+        program_id: test_multisig::ID,
+        accounts: test_multisig::accounts::CreateMultisig {
+            multisig,
+            vault,
+            creator: alice.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: test_multisig::instruction::CreateMultisig {
+            owners: owners.iter().map(|owner| owner.pubkey()).collect(),
+            threshold: 2,
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &[alice]);
+
+    // The vault holds the authority while an owner pays for the account
+    let event = oracle_pda(&[b"event", &0u64.to_le_bytes()]);
+    let mut accounts = test_multisig::accounts::CreateEvent {
+        multisig,
+        vault,
+        category_counter: oracle_pda(&[b"category", &[EventCategory::General as u8]]),
+        oracle_event: event,
+        payer: alice.pubkey(),
+        oracle_program: simple_oracle_quest::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(approvals(&[alice, bob]));
+    let ix = Instruction {
+        program_id: test_multisig::ID,
+        accounts,
+        data: test_multisig::instruction::CreateEvent {
+            params: EventParams {
+                category: EventCategory::General,
+                tags: [0; 4],
+                description: "Will the council agree?".to_string(),
+                min_stake: 1_000,
+                max_stake: 1_000_000_000,
+                season_id: 0,
+            },
+        }
+        .data(),
+    };
+    send(&mut svm, ix, &[alice, bob]);
+    assert_eq!(fetch::<OracleEvent>(&svm, &event).authority, vault);
+    assert_eq!(svm.get_account(&vault).map_or(0, |a| a.lamports), 0);
+
+    // One approval is below the threshold, even when repeated
+    assert!(resolve_through_multisig(&mut svm, multisig, vault, event, &[carol]).is_err());
+    let mut accounts = test_multisig::accounts::ResolveEvent {
+        multisig,
+        vault,
+        oracle_event: event,
+        oracle_program: simple_oracle_quest::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(approvals(&[carol, carol]));
+    let ix = Instruction {
+        program_id: test_multisig::ID,
+        accounts,
+        data: test_multisig::instruction::ResolveEvent { outcome: true }.data(),
+    };
+    assert!(try_send(&mut svm, ix, &[carol]).is_err());
+
+    // An owner cannot bypass the multisig and resolve directly
+    let ix = Instruction {
+        program_id: simple_oracle_quest::ID,
+        accounts: simple_oracle_quest::accounts::ResolveEvent {
+            oracle_event: event,
+            authority: carol.pubkey(),
+        }
+        .to_account_metas(None),
+        data: simple_oracle_quest::instruction::ResolveEvent { outcome: true }.data(),
+    };
+    assert!(try_send(&mut svm, ix, &[carol]).is_err());
+
+    resolve_through_multisig(&mut svm, multisig, vault, event, &[bob, carol]).unwrap();
+    let resolved: OracleEvent = fetch(&svm, &event);
+    assert!(resolved.resolved);
+    assert_eq!(resolved.outcome, Some(true));
+}
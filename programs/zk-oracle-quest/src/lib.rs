@@ -4,7 +4,7 @@
 use anchor_lang::prelude::*;
 use light_sdk::cpi::v2::CpiAccounts;
 use light_sdk::cpi::{v2::LightSystemProgramCpi, InvokeLightSystemProgram, LightCpiInstruction};
-use light_sdk::instruction::account_meta::CompressedAccountMeta;
+use light_sdk::instruction::account_meta::{CompressedAccountMeta, CompressedAccountMetaReadOnly};
 use light_sdk::instruction::ValidityProof;
 use light_sdk::{
     account::LightAccount, address::v2::derive_address, derive_light_cpi_signer,
@@ -17,6 +17,19 @@ declare_id!("B6qG7jPjiTcdnNS1Rttf5We5H4GbyN3dUqu8VKMFv5Eh");
 pub const LIGHT_CPI_SIGNER: CpiSigner =
     derive_light_cpi_signer!("B6qG7jPjiTcdnNS1Rttf5We5H4GbyN3dUqu8VKMFv5Eh");
 
+/// Largest stake accepted for a single private bet (100 SOL)
+pub const MAX_PRIVATE_BET_AMOUNT: u64 = 100_000_000_000;
+
+/// Most stake a player may hold in unclaimed private bets (500 SOL)
+pub const MAX_PLAYER_EXPOSURE: u64 = 500_000_000_000;
+
+/// Longest metadata URI an oracle event may point at
+pub const MAX_METADATA_URI_LEN: usize = 200;
+
+/// Most bets `claim_many` accepts; with their events and the profile this
+/// stays within the 8 accounts one validity proof can cover
+pub const MAX_CLAIM_BATCH: usize = 3;
+
 // pub const LIGHT_CPI_SIGNER: CpiSigner =
 //     derive_light_cpi_signer!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -25,22 +38,76 @@ pub mod zk_oracle_quest {
 
     use super::*;
 
-    /// Place a private bet on an oracle event
+    /// Place a private bet on an oracle event. The event and the player's
+    /// profile are inputs so the proof covers the event's stake limits and
+    /// the profile's open exposure.
+    #[allow(clippy::too_many_arguments)]
     pub fn place_private_bet<'info>(
         ctx: Context<'_, '_, '_, 'info, PlacePrivateBet<'info>>,
-        event_id: u64,
         chosen_outcome: bool,
         amount: u64,
         proof: ValidityProof,
         address_tree_info: PackedAddressTreeInfo,
         output_tree_index: u8,
+        existing_event: ExistingOracleEventIxData,
+        existing_profile: ExistingPlayerProfileIxData,
     ) -> Result<()> {
+        require!(amount > 0, OracleError::BetAmountZero);
+        require!(
+            amount <= MAX_PRIVATE_BET_AMOUNT,
+            OracleError::BetAmountTooHigh
+        );
+        let event_id = existing_event.event_id;
+
         let light_cpi_accounts = CpiAccounts::new(
             ctx.accounts.player.as_ref(),
             ctx.remaining_accounts,
             LIGHT_CPI_SIGNER.clone(),
         );
 
+        // The proof covers the event's limits; its pool totals take the stake
+        let mut oracle_event = LightAccount::<OracleEvent>::new_mut(
+            &crate::ID,
+            &existing_event.account_meta,
+            OracleEvent {
+                event_id: existing_event.event_id,
+                description: existing_event.description.clone(),
+                resolved: existing_event.resolved,
+                outcome: existing_event.outcome,
+                authority: existing_event.authority,
+                upset: existing_event.upset,
+                metadata_uri: existing_event.metadata_uri.clone(),
+                metadata_hash: existing_event.metadata_hash,
+                min_stake: existing_event.min_stake,
+                max_stake: existing_event.max_stake,
+                yes_amount: existing_event.yes_amount,
+                no_amount: existing_event.no_amount,
+            },
+        )?;
+        require!(!oracle_event.resolved, OracleError::EventAlreadyResolved);
+        oracle_event.check_stake(amount)?;
+        oracle_event.add_stake(chosen_outcome, amount)?;
+
+        let mut player_profile = LightAccount::<PlayerProfile>::new_mut(
+            &crate::ID,
+            &existing_profile.account_meta,
+            PlayerProfile {
+                owner: existing_profile.owner,
+                balance: existing_profile.balance,
+                total_bets: existing_profile.total_bets,
+                bets_won: existing_profile.bets_won,
+                current_streak: existing_profile.current_streak,
+                best_streak: existing_profile.best_streak,
+                upsets_called: existing_profile.upsets_called,
+                open_exposure: existing_profile.open_exposure,
+            },
+        )?;
+        require!(
+            player_profile.owner == ctx.accounts.player.key(),
+            OracleError::ProfileOwnerMismatch
+        );
+        player_profile.open_bet(amount)?;
+
         // Derive the addres for the bet
         let (address, address_seed) = derive_address(
             &[
@@ -64,11 +131,14 @@ pub mod zk_oracle_quest {
         compressed_bet.chosen_outcome = chosen_outcome;
         compressed_bet.amount = amount;
 
+        // The bet is the third output account, after the event and the profile
         let new_address_param =
-            address_tree_info.into_new_address_params_assigned_packed(address_seed, Some(0));
+            address_tree_info.into_new_address_params_assigned_packed(address_seed, Some(2));
 
         // Call the light client CPI to create the compressed account
         LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER.clone(), proof)
+            .with_light_account(oracle_event)?
+            .with_light_account(player_profile)?
             .with_light_account(compressed_bet)?
             .with_new_addresses(&[new_address_param])
             .invoke(light_cpi_accounts)?;
@@ -108,6 +178,10 @@ pub mod zk_oracle_quest {
         player_profile.balance = 0;
         player_profile.total_bets = 0;
         player_profile.bets_won = 0;
+        player_profile.current_streak = 0;
+        player_profile.best_streak = 0;
+        player_profile.upsets_called = 0;
+        player_profile.open_exposure = 0;
 
         let new_address_param =
             address_tree_info.into_new_address_params_assigned_packed(address_seed, Some(0));
@@ -125,57 +199,90 @@ pub mod zk_oracle_quest {
         Ok(())
     }
 
-    /// Create a new oracle event
+    /// Create the event id allocator. Only the program upgrade authority may
+    /// call it and becomes the counter's admin; ids below `start_id` stay
+    /// reserved for events created before allocation.
+    pub fn initialize_event_counter(
+        ctx: Context<InitializeEventCounter>,
+        start_id: u64,
+    ) -> Result<()> {
+        let counter = &mut ctx.accounts.event_counter;
+        counter.admin = ctx.accounts.admin.key();
+        counter.start_id = start_id;
+        counter.next_id = start_id;
+        counter.allow_explicit_ids = false;
+        counter.bump = ctx.bumps.event_counter;
+
+        msg!("Event counter initialized from {}", start_id);
+        Ok(())
+    }
+
+    /// Toggle the admin-only legacy path for explicit event ids
+    pub fn set_explicit_event_ids(
+        ctx: Context<UpdateEventCounter>,
+        allow_explicit_ids: bool,
+    ) -> Result<()> {
+        ctx.accounts.event_counter.allow_explicit_ids = allow_explicit_ids;
+        Ok(())
+    }
+
+    /// Create a new oracle event under the next allocated id
+    #[allow(clippy::too_many_arguments)]
     pub fn create_oracle_event<'info>(
         ctx: Context<'_, '_, '_, 'info, CreateOracleEvent<'info>>,
-        event_id: u64,
         description: String,
+        metadata_uri: String,
+        metadata_hash: [u8; 32],
+        min_stake: u64,
+        max_stake: u64,
         proof: ValidityProof,
         address_tree_info: PackedAddressTreeInfo,
         output_tree_index: u8,
     ) -> Result<()> {
-        require!(description.len() <= 100, OracleError::DescriptionTooLong);
-
-        let light_cpi_accounts = CpiAccounts::new(
-            ctx.accounts.authority.as_ref(),
-            ctx.remaining_accounts,
-            LIGHT_CPI_SIGNER.clone(),
-        );
-
-        let (address, address_seed) = derive_address(
-            &[b"oracle_event", &event_id.to_le_bytes()],
-            &address_tree_info
-                .get_tree_pubkey(&light_cpi_accounts)
-                .map_err(|_| error!(OracleError::InvalidAddressTree))?,
-            &crate::ID,
-        );
-
-        // Create the compressed account for the oracle event
-        let mut oracle_event =
-            LightAccount::<OracleEvent>::new_init(&crate::ID, Some(address), output_tree_index);
-
-        // Set the oracle event data
-        oracle_event.event_id = event_id;
-        oracle_event.description = description.clone();
-        oracle_event.resolved = false;
-        oracle_event.outcome = false;
-        oracle_event.authority = ctx.accounts.authority.key();
-
-        let new_address_param =
-            address_tree_info.into_new_address_params_assigned_packed(address_seed, Some(0));
-
-        // Call the light client CPI to create the compressed account
-        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER.clone(), proof)
-            .with_light_account(oracle_event)?
-            .with_new_addresses(&[new_address_param])
-            .invoke(light_cpi_accounts)?;
+        let event_id = ctx.accounts.event_counter.allocate()?;
+        create_event(
+            ctx,
+            event_id,
+            description,
+            metadata_uri,
+            metadata_hash,
+            min_stake,
+            max_stake,
+            proof,
+            address_tree_info,
+            output_tree_index,
+        )
+    }
 
-        msg!(
-            "Oracle event created: id={}, description={}",
+    /// Create an oracle event under an explicit id reserved for legacy events
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_oracle_event_with_id<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateOracleEvent<'info>>,
+        event_id: u64,
+        description: String,
+        metadata_uri: String,
+        metadata_hash: [u8; 32],
+        min_stake: u64,
+        max_stake: u64,
+        proof: ValidityProof,
+        address_tree_info: PackedAddressTreeInfo,
+        output_tree_index: u8,
+    ) -> Result<()> {
+        ctx.accounts
+            .event_counter
+            .check_explicit(&ctx.accounts.authority.key(), event_id)?;
+        create_event(
+            ctx,
             event_id,
-            description
-        );
-        Ok(())
+            description,
+            metadata_uri,
+            metadata_hash,
+            min_stake,
+            max_stake,
+            proof,
+            address_tree_info,
+            output_tree_index,
+        )
     }
 
     /// Resolve an existing oracle event with the outcome
@@ -200,6 +307,13 @@ pub mod zk_oracle_quest {
                 resolved: existing_event.resolved,
                 outcome: existing_event.outcome,
                 authority: existing_event.authority,
+                upset: existing_event.upset,
+                metadata_uri: existing_event.metadata_uri.clone(),
+                metadata_hash: existing_event.metadata_hash,
+                min_stake: existing_event.min_stake,
+                max_stake: existing_event.max_stake,
+                yes_amount: existing_event.yes_amount,
+                no_amount: existing_event.no_amount,
             },
         )?;
 
@@ -211,8 +325,7 @@ pub mod zk_oracle_quest {
         require!(!oracle_event.resolved, OracleError::EventAlreadyResolved);
 
         // Update the oracle event data
-        oracle_event.resolved = true;
-        oracle_event.outcome = existing_event.update_outcome;
+        oracle_event.resolve(existing_event.update_outcome);
 
         // Call the light client CPI to update the compressed account
         LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER.clone(), proof)
@@ -227,7 +340,8 @@ pub mod zk_oracle_quest {
         Ok(())
     }
 
-    /// Claim winnings for a player bet
+    /// Claim winnings for a player bet. The event is proven read-only and the
+    /// bet is closed, so it cannot be claimed twice.
     pub fn claim_winnings<'info>(
         ctx: Context<'_, '_, '_, 'info, PlacePrivateBet<'info>>,
         proof: ValidityProof,
@@ -248,16 +362,19 @@ pub mod zk_oracle_quest {
         );
 
         // Verify the event is resolved
-        require!(resolved_event.resolved, OracleError::EventNotResolved);
+        let tree_pubkeys = light_cpi_accounts
+            .tree_pubkeys()
+            .map_err(|_| error!(OracleError::InvalidStateTree))?;
+        let oracle_event = read_resolved_event(&resolved_event, &tree_pubkeys)?;
 
         // Verify if the event id matches
         require!(
-            existing_bet.event_id == resolved_event.event_id,
+            existing_bet.event_id == oracle_event.event_id,
             OracleError::EventMismatch
         );
 
         // Check if the player won
-        let player_won = existing_bet.chosen_outcome == resolved_event.outcome;
+        let player_won = existing_bet.chosen_outcome == oracle_event.outcome;
         require!(player_won, OracleError::BetDidNotWin);
 
         // Update bet and profile data accordingly
@@ -269,6 +386,10 @@ pub mod zk_oracle_quest {
                 balance: existing_profile.balance,
                 total_bets: existing_profile.total_bets,
                 bets_won: existing_profile.bets_won,
+                current_streak: existing_profile.current_streak,
+                best_streak: existing_profile.best_streak,
+                upsets_called: existing_profile.upsets_called,
+                open_exposure: existing_profile.open_exposure,
             },
         )?;
 
@@ -278,22 +399,24 @@ pub mod zk_oracle_quest {
             OracleError::UnauthorizedClaim
         );
 
-        // Calculate winnings by doubling the bet amount (simplicity)
-        let winnings = existing_bet
-            .amount
-            .checked_mul(2)
-            .ok_or(OracleError::BetOverflow)?;
-        player_profile.balance = player_profile
-            .balance
-            .checked_add(winnings)
-            .ok_or(OracleError::BalanceOverflow)?;
-        player_profile.bets_won = player_profile
-            .bets_won
-            .checked_add(1)
-            .ok_or(OracleError::BetsWonOverflow)?;
+        let winnings = player_profile.record_win(existing_bet.amount, oracle_event.upset)?;
+        let new_balance = player_profile.balance;
 
-        // Call the light client CPI to update the player profile
+        let bet = LightAccount::<PrivateBet>::new_close(
+            &crate::ID,
+            &existing_bet.account_meta,
+            PrivateBet {
+                player: existing_bet.player,
+                event_id: existing_bet.event_id,
+                chosen_outcome: existing_bet.chosen_outcome,
+                amount: existing_bet.amount,
+            },
+        )?;
+
+        // Call the light client CPI to close the bet and update the player profile
         LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER.clone(), proof)
+            .with_light_account(oracle_event)?
+            .with_light_account(bet)?
             .with_light_account(player_profile)?
             .invoke(light_cpi_accounts)?;
 
@@ -301,18 +424,429 @@ pub mod zk_oracle_quest {
             "Winnings claimed: player={}, amount={}, new_balance={}",
             ctx.accounts.player.key(),
             winnings,
-            existing_profile.balance + winnings
+            new_balance
+        );
+        Ok(())
+    }
+
+    /// Claim several winning private bets under one validity proof. Each bet
+    /// is paired with its event, proven read-only, and closed so it cannot be
+    /// claimed twice; the profile is written once with the combined result.
+    pub fn claim_many<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimWinnings<'info>>,
+        proof: ValidityProof,
+        existing_bets: Vec<ExistingPrivateBetIxData>,
+        existing_profile: ExistingPlayerProfileIxData,
+        resolved_events: Vec<ResolvedOracleEventIxData>,
+    ) -> Result<()> {
+        require!(
+            !existing_bets.is_empty() && existing_bets.len() <= MAX_CLAIM_BATCH,
+            OracleError::InvalidClaimBatch
+        );
+        require!(
+            resolved_events.len() == existing_bets.len(),
+            OracleError::EventMismatch
+        );
+        let player = ctx.accounts.player.key();
+
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.player.as_ref(),
+            ctx.remaining_accounts,
+            LIGHT_CPI_SIGNER,
+        );
+
+        let mut player_profile = LightAccount::<PlayerProfile>::new_mut(
+            &crate::ID,
+            &existing_profile.account_meta,
+            PlayerProfile {
+                owner: existing_profile.owner,
+                balance: existing_profile.balance,
+                total_bets: existing_profile.total_bets,
+                bets_won: existing_profile.bets_won,
+                current_streak: existing_profile.current_streak,
+                best_streak: existing_profile.best_streak,
+                upsets_called: existing_profile.upsets_called,
+                open_exposure: existing_profile.open_exposure,
+            },
+        )?;
+        require!(
+            player_profile.owner == player,
+            OracleError::UnauthorizedClaim
+        );
+
+        let tree_pubkeys = light_cpi_accounts
+            .tree_pubkeys()
+            .map_err(|_| error!(OracleError::InvalidStateTree))?;
+        let mut cpi = LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof);
+        let mut results = Vec::with_capacity(existing_bets.len());
+        let mut total_winnings = 0u64;
+        for (existing_bet, resolved_event) in existing_bets.into_iter().zip(&resolved_events) {
+            require!(
+                existing_bet.player == player,
+                OracleError::UnauthorizedClaim
+            );
+            let oracle_event = read_resolved_event(resolved_event, &tree_pubkeys)?;
+            require!(
+                existing_bet.event_id == oracle_event.event_id,
+                OracleError::EventMismatch
+            );
+            require!(
+                existing_bet.chosen_outcome == oracle_event.outcome,
+                OracleError::BetDidNotWin
+            );
+
+            let winnings = player_profile.record_win(existing_bet.amount, oracle_event.upset)?;
+            total_winnings = total_winnings
+                .checked_add(winnings)
+                .ok_or(OracleError::BalanceOverflow)?;
+            results.push(ClaimResult {
+                event_id: existing_bet.event_id,
+                amount: existing_bet.amount,
+                winnings,
+            });
+
+            let bet = LightAccount::<PrivateBet>::new_close(
+                &crate::ID,
+                &existing_bet.account_meta,
+                PrivateBet {
+                    player: existing_bet.player,
+                    event_id: existing_bet.event_id,
+                    chosen_outcome: existing_bet.chosen_outcome,
+                    amount: existing_bet.amount,
+                },
+            )?;
+            cpi = cpi
+                .with_light_account(oracle_event)?
+                .with_light_account(bet)?;
+        }
+        let new_balance = player_profile.balance;
+
+        cpi.with_light_account(player_profile)?
+            .invoke(light_cpi_accounts)?;
+
+        msg!(
+            "Winnings claimed: player={}, bets={}, amount={}, new_balance={}",
+            player,
+            results.len(),
+            total_winnings,
+            new_balance
+        );
+        emit!(BetsClaimed {
+            player,
+            total_winnings,
+            results,
+        });
+        Ok(())
+    }
+
+    /// Settle a losing private bet against its proven event: the bet is
+    /// closed, its stake leaves the profile's open exposure and the win
+    /// streak resets. Losing bets count against the exposure cap until they
+    /// are settled.
+    pub fn settle_loss<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimWinnings<'info>>,
+        proof: ValidityProof,
+        existing_bet: ExistingPrivateBetIxData,
+        existing_profile: ExistingPlayerProfileIxData,
+        resolved_event: ResolvedOracleEventIxData,
+    ) -> Result<()> {
+        let player = ctx.accounts.player.key();
+        require!(
+            existing_bet.player == player,
+            OracleError::UnauthorizedClaim
+        );
+
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.player.as_ref(),
+            ctx.remaining_accounts,
+            LIGHT_CPI_SIGNER,
+        );
+
+        let tree_pubkeys = light_cpi_accounts
+            .tree_pubkeys()
+            .map_err(|_| error!(OracleError::InvalidStateTree))?;
+        let oracle_event = read_resolved_event(&resolved_event, &tree_pubkeys)?;
+        require!(
+            existing_bet.event_id == oracle_event.event_id,
+            OracleError::EventMismatch
+        );
+        require!(
+            existing_bet.chosen_outcome != oracle_event.outcome,
+            OracleError::BetDidNotLose
+        );
+
+        let mut player_profile = LightAccount::<PlayerProfile>::new_mut(
+            &crate::ID,
+            &existing_profile.account_meta,
+            PlayerProfile {
+                owner: existing_profile.owner,
+                balance: existing_profile.balance,
+                total_bets: existing_profile.total_bets,
+                bets_won: existing_profile.bets_won,
+                current_streak: existing_profile.current_streak,
+                best_streak: existing_profile.best_streak,
+                upsets_called: existing_profile.upsets_called,
+                open_exposure: existing_profile.open_exposure,
+            },
+        )?;
+        require!(
+            player_profile.owner == player,
+            OracleError::UnauthorizedClaim
+        );
+        player_profile.record_loss(existing_bet.amount)?;
+
+        let bet = LightAccount::<PrivateBet>::new_close(
+            &crate::ID,
+            &existing_bet.account_meta,
+            PrivateBet {
+                player: existing_bet.player,
+                event_id: existing_bet.event_id,
+                chosen_outcome: existing_bet.chosen_outcome,
+                amount: existing_bet.amount,
+            },
+        )?;
+
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(oracle_event)?
+            .with_light_account(bet)?
+            .with_light_account(player_profile)?
+            .invoke(light_cpi_accounts)?;
+
+        msg!(
+            "Loss settled: player={}, event={}, amount={}",
+            player,
+            existing_bet.event_id,
+            existing_bet.amount
+        );
+        Ok(())
+    }
+
+    /// Claim an achievement badge unlocked by the player's profile stats
+    pub fn claim_achievement<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimAchievement<'info>>,
+        proof: ValidityProof,
+        address_tree_info: PackedAddressTreeInfo,
+        output_tree_index: u8,
+        existing_profile: ExistingPlayerProfileIxData,
+        achievement: Achievement,
+    ) -> Result<()> {
+        let light_cpi_accounts = CpiAccounts::new(
+            ctx.accounts.player.as_ref(),
+            ctx.remaining_accounts,
+            LIGHT_CPI_SIGNER,
+        );
+
+        // The profile is passed through unchanged so the proof covers its stats
+        let player_profile = LightAccount::<PlayerProfile>::new_mut(
+            &crate::ID,
+            &existing_profile.account_meta,
+            PlayerProfile {
+                owner: existing_profile.owner,
+                balance: existing_profile.balance,
+                total_bets: existing_profile.total_bets,
+                bets_won: existing_profile.bets_won,
+                current_streak: existing_profile.current_streak,
+                best_streak: existing_profile.best_streak,
+                upsets_called: existing_profile.upsets_called,
+                open_exposure: existing_profile.open_exposure,
+            },
+        )?;
+
+        require!(
+            player_profile.owner == ctx.accounts.player.key(),
+            OracleError::UnauthorizedClaim
+        );
+        require!(
+            achievement.is_unlocked(&player_profile),
+            OracleError::AchievementLocked
+        );
+
+        // One badge per (player, achievement): a second claim reuses the address and fails
+        let (address, address_seed) = derive_address(
+            &[
+                b"badge",
+                ctx.accounts.player.key().as_ref(),
+                &[achievement.id()],
+            ],
+            &address_tree_info
+                .get_tree_pubkey(&light_cpi_accounts)
+                .map_err(|_| error!(OracleError::InvalidAddressTree))?,
+            &crate::ID,
+        );
+
+        let mut badge = LightAccount::<AchievementBadge>::new_init(
+            &crate::ID,
+            Some(address),
+            output_tree_index,
+        );
+        badge.owner = ctx.accounts.player.key();
+        badge.achievement_id = achievement.id();
+
+        // The badge is the second output account, after the profile
+        let new_address_param =
+            address_tree_info.into_new_address_params_assigned_packed(address_seed, Some(1));
+
+        LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, proof)
+            .with_light_account(player_profile)?
+            .with_light_account(badge)?
+            .with_new_addresses(&[new_address_param])
+            .invoke(light_cpi_accounts)?;
+
+        msg!(
+            "Achievement claimed: player={}, achievement={:?}",
+            ctx.accounts.player.key(),
+            achievement
         );
         Ok(())
     }
 }
 
+/// Validate the event parameters and create the compressed event under `event_id`
+#[allow(clippy::too_many_arguments)]
+fn create_event<'info>(
+    ctx: Context<'_, '_, '_, 'info, CreateOracleEvent<'info>>,
+    event_id: u64,
+    description: String,
+    metadata_uri: String,
+    metadata_hash: [u8; 32],
+    min_stake: u64,
+    max_stake: u64,
+    proof: ValidityProof,
+    address_tree_info: PackedAddressTreeInfo,
+    output_tree_index: u8,
+) -> Result<()> {
+    require!(description.len() <= 100, OracleError::DescriptionTooLong);
+    require!(
+        metadata_uri.len() <= MAX_METADATA_URI_LEN,
+        OracleError::MetadataUriTooLong
+    );
+    require!(
+        min_stake > 0 && min_stake <= max_stake && max_stake <= MAX_PRIVATE_BET_AMOUNT,
+        OracleError::InvalidStakeLimits
+    );
+
+    let light_cpi_accounts = CpiAccounts::new(
+        ctx.accounts.authority.as_ref(),
+        ctx.remaining_accounts,
+        LIGHT_CPI_SIGNER.clone(),
+    );
+
+    let (address, address_seed) = derive_address(
+        &[b"oracle_event", &event_id.to_le_bytes()],
+        &address_tree_info
+            .get_tree_pubkey(&light_cpi_accounts)
+            .map_err(|_| error!(OracleError::InvalidAddressTree))?,
+        &crate::ID,
+    );
+
+    // Create the compressed account for the oracle event
+    let mut oracle_event =
+        LightAccount::<OracleEvent>::new_init(&crate::ID, Some(address), output_tree_index);
+
+    // Set the oracle event data
+    oracle_event.event_id = event_id;
+    oracle_event.description = description.clone();
+    oracle_event.resolved = false;
+    oracle_event.outcome = false;
+    oracle_event.authority = ctx.accounts.authority.key();
+    oracle_event.upset = false;
+    oracle_event.metadata_uri = metadata_uri;
+    oracle_event.metadata_hash = metadata_hash;
+    oracle_event.min_stake = min_stake;
+    oracle_event.max_stake = max_stake;
+    oracle_event.yes_amount = 0;
+    oracle_event.no_amount = 0;
+
+    let new_address_param =
+        address_tree_info.into_new_address_params_assigned_packed(address_seed, Some(0));
+
+    // Call the light client CPI to create the compressed account
+    LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER.clone(), proof)
+        .with_light_account(oracle_event)?
+        .with_new_addresses(&[new_address_param])
+        .invoke(light_cpi_accounts)?;
+
+    msg!(
+        "Oracle event created: id={}, description={}",
+        event_id,
+        description
+    );
+    Ok(())
+}
+
+/// Load a resolved event as a read-only input, so the validity proof covers
+/// the outcome and upset flag a claim is settled against
+fn read_resolved_event(
+    resolved_event: &ResolvedOracleEventIxData,
+    tree_pubkeys: &[Pubkey],
+) -> Result<LightAccount<OracleEvent>> {
+    let oracle_event = LightAccount::<OracleEvent>::new_read_only(
+        &crate::ID,
+        &resolved_event.account_meta,
+        OracleEvent {
+            event_id: resolved_event.event_id,
+            description: resolved_event.description.clone(),
+            resolved: resolved_event.resolved,
+            outcome: resolved_event.outcome,
+            authority: resolved_event.authority,
+            upset: resolved_event.upset,
+            metadata_uri: resolved_event.metadata_uri.clone(),
+            metadata_hash: resolved_event.metadata_hash,
+            min_stake: resolved_event.min_stake,
+            max_stake: resolved_event.max_stake,
+            yes_amount: resolved_event.yes_amount,
+            no_amount: resolved_event.no_amount,
+        },
+        tree_pubkeys,
+    )?;
+    require!(oracle_event.resolved, OracleError::EventNotResolved);
+    Ok(oracle_event)
+}
+
+/// Registry of every achievement a player can claim
+pub const ACHIEVEMENTS: [Achievement; 3] = [
+    Achievement::FirstWin,
+    Achievement::WinStreak,
+    Achievement::CalledAnUpset,
+];
+
+/// Wins in a row needed for the streak badge
+pub const WIN_STREAK_ACHIEVEMENT: u64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub enum Achievement {
+    FirstWin,
+    WinStreak,
+    CalledAnUpset,
+}
+
+impl Achievement {
+    /// Stable id used in the badge address seeds
+    pub fn id(&self) -> u8 {
+        match self {
+            Achievement::FirstWin => 0,
+            Achievement::WinStreak => 1,
+            Achievement::CalledAnUpset => 2,
+        }
+    }
+
+    pub fn is_unlocked(&self, profile: &PlayerProfile) -> bool {
+        match self {
+            Achievement::FirstWin => profile.bets_won >= 1,
+            Achievement::WinStreak => profile.best_streak >= WIN_STREAK_ACHIEVEMENT,
+            Achievement::CalledAnUpset => profile.upsets_called >= 1,
+        }
+    }
+}
+
 #[error_code]
 pub enum OracleError {
     #[msg("failed to get address tree pubkey")]
     InvalidAddressTree,
     #[msg("description too long (max 100 chars)")]
     DescriptionTooLong,
+    #[msg("metadata URI too long (max 200 chars)")]
+    MetadataUriTooLong,
     #[msg("only authority can resolve oracle events")]
     UnauthorizedResolver,
     #[msg("event already resolved")]
@@ -331,6 +865,40 @@ pub enum OracleError {
     BalanceOverflow,
     #[msg("bets won overflow")]
     BetsWonOverflow,
+    #[msg("bet amount must be greater than zero")]
+    BetAmountZero,
+    #[msg("bet amount too high (max 100 SOL)")]
+    BetAmountTooHigh,
+    #[msg("achievement requirements not met")]
+    AchievementLocked,
+    #[msg("event id counter overflow")]
+    EventIdOverflow,
+    #[msg("explicit event ids are disabled")]
+    ExplicitEventIdsDisabled,
+    #[msg("only the counter admin can use explicit event ids")]
+    UnauthorizedEventId,
+    #[msg("explicit event id must be below the counter start")]
+    ExplicitEventIdOutOfRange,
+    #[msg("claim batch must hold between 1 and 3 bets")]
+    InvalidClaimBatch,
+    #[msg("stake limits must satisfy 0 < min <= max <= 100 SOL")]
+    InvalidStakeLimits,
+    #[msg("stake below the event minimum")]
+    StakeBelowMinimum,
+    #[msg("stake above the event maximum")]
+    StakeAboveMaximum,
+    #[msg("open exposure limit exceeded (max 500 SOL)")]
+    ExposureLimitExceeded,
+    #[msg("profile does not belong to the player")]
+    ProfileOwnerMismatch,
+    #[msg("bet did not lose")]
+    BetDidNotLose,
+    #[msg("only the program upgrade authority can initialize the event counter")]
+    NotUpgradeAuthority,
+    #[msg("failed to get state tree pubkeys")]
+    InvalidStateTree,
+    #[msg("open exposure underflow")]
+    ExposureUnderflow,
 }
 
 #[derive(Accounts)]
@@ -345,12 +913,90 @@ pub struct InitializePlayer<'info> {
     pub player: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeEventCounter<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EventCounter::INIT_SPACE,
+        seeds = [b"event_counter"],
+        bump
+    )]
+    pub event_counter: Account<'info, EventCounter>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ZkOracleQuest>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ OracleError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEventCounter<'info> {
+    #[account(
+        mut,
+        seeds = [b"event_counter"],
+        bump = event_counter.bump,
+        has_one = admin
+    )]
+    pub event_counter: Account<'info, EventCounter>,
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct CreateOracleEvent<'info> {
+    #[account(
+        mut,
+        seeds = [b"event_counter"],
+        bump = event_counter.bump
+    )]
+    pub event_counter: Account<'info, EventCounter>,
     #[account(mut)]
     pub authority: Signer<'info>,
 }
 
+/// Allocates the ids compressed oracle events derive their addresses from
+#[account]
+#[derive(InitSpace)]
+pub struct EventCounter {
+    pub admin: Pubkey,
+    /// Ids below this are reserved for explicit legacy events
+    pub start_id: u64,
+    pub next_id: u64,
+    pub allow_explicit_ids: bool,
+    pub bump: u8,
+}
+
+impl EventCounter {
+    /// Hand out the next event id and advance the counter
+    pub fn allocate(&mut self) -> Result<u64> {
+        let event_id = self.next_id;
+        self.next_id = self
+            .next_id
+            .checked_add(1)
+            .ok_or(OracleError::EventIdOverflow)?;
+        Ok(event_id)
+    }
+
+    /// Check that `signer` may create an event under the explicit `event_id`
+    pub fn check_explicit(&self, signer: &Pubkey, event_id: u64) -> Result<()> {
+        require!(
+            self.allow_explicit_ids,
+            OracleError::ExplicitEventIdsDisabled
+        );
+        require_keys_eq!(*signer, self.admin, OracleError::UnauthorizedEventId);
+        require!(
+            event_id < self.start_id,
+            OracleError::ExplicitEventIdOutOfRange
+        );
+        Ok(())
+    }
+}
+
 #[derive(Accounts)]
 pub struct ResolveOracleEvent<'info> {
     #[account(mut)]
@@ -363,6 +1009,12 @@ pub struct ClaimWinnings<'info> {
     pub player: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ClaimAchievement<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
 /// Compressed account data for a private bet
 #[derive(
     Clone, Debug, Default, AnchorDeserialize, AnchorSerialize, LightDiscriminator, LightHasher,
@@ -391,6 +1043,79 @@ pub struct PlayerProfile {
     pub total_bets: u64,
     #[hash]
     pub bets_won: u64,
+    #[hash]
+    pub current_streak: u64,
+    #[hash]
+    pub best_streak: u64,
+    #[hash]
+    pub upsets_called: u64,
+    /// Stake in bets not yet claimed or settled, capped at `MAX_PLAYER_EXPOSURE`
+    #[hash]
+    pub open_exposure: u64,
+}
+
+impl PlayerProfile {
+    /// Count a new bet of `amount` and add it to the open exposure
+    pub fn open_bet(&mut self, amount: u64) -> Result<()> {
+        let exposure = self
+            .open_exposure
+            .checked_add(amount)
+            .ok_or(OracleError::BetOverflow)?;
+        require!(
+            exposure <= MAX_PLAYER_EXPOSURE,
+            OracleError::ExposureLimitExceeded
+        );
+        self.open_exposure = exposure;
+        self.total_bets = self
+            .total_bets
+            .checked_add(1)
+            .ok_or(OracleError::BetOverflow)?;
+        Ok(())
+    }
+
+    /// Record a winning bet of `amount` and credit its winnings, which are
+    /// double the stake (simplicity). Returns the winnings.
+    pub fn record_win(&mut self, amount: u64, upset: bool) -> Result<u64> {
+        let winnings = amount.checked_mul(2).ok_or(OracleError::BetOverflow)?;
+        self.release_exposure(amount)?;
+        self.balance = self
+            .balance
+            .checked_add(winnings)
+            .ok_or(OracleError::BalanceOverflow)?;
+        self.bets_won = self
+            .bets_won
+            .checked_add(1)
+            .ok_or(OracleError::BetsWonOverflow)?;
+        self.current_streak = self
+            .current_streak
+            .checked_add(1)
+            .ok_or(OracleError::BetsWonOverflow)?;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        if upset {
+            self.upsets_called = self
+                .upsets_called
+                .checked_add(1)
+                .ok_or(OracleError::BetsWonOverflow)?;
+        }
+        Ok(winnings)
+    }
+
+    /// Record a losing bet of `amount`, which ends the current win streak
+    pub fn record_loss(&mut self, amount: u64) -> Result<()> {
+        self.release_exposure(amount)?;
+        self.current_streak = 0;
+        Ok(())
+    }
+
+    /// Take a settled bet of `amount` out of the open exposure. Only closed
+    /// bets release it, so a shortfall means the accounting drifted.
+    fn release_exposure(&mut self, amount: u64) -> Result<()> {
+        self.open_exposure = self
+            .open_exposure
+            .checked_sub(amount)
+            .ok_or(OracleError::ExposureUnderflow)?;
+        Ok(())
+    }
 }
 
 /// Compressed account for oracle events
@@ -408,6 +1133,70 @@ pub struct OracleEvent {
     pub outcome: bool,
     #[hash]
     pub authority: Pubkey,
+    #[hash]
+    pub upset: bool,
+    /// Off-chain JSON with the full rules, resolution sources and artwork
+    #[hash]
+    pub metadata_uri: String,
+    /// sha256 of the document at `metadata_uri`
+    #[hash]
+    pub metadata_hash: [u8; 32],
+    /// Smallest stake a private bet on this event may carry
+    #[hash]
+    pub min_stake: u64,
+    /// Largest stake a private bet on this event may carry
+    #[hash]
+    pub max_stake: u64,
+    /// Total staked on YES
+    #[hash]
+    pub yes_amount: u64,
+    /// Total staked on NO
+    #[hash]
+    pub no_amount: u64,
+}
+
+impl OracleEvent {
+    /// Check `amount` against the event's stake limits
+    pub fn check_stake(&self, amount: u64) -> Result<()> {
+        require!(amount >= self.min_stake, OracleError::StakeBelowMinimum);
+        require!(amount <= self.max_stake, OracleError::StakeAboveMaximum);
+        Ok(())
+    }
+
+    /// Add `amount` to the pool of the `outcome` side
+    pub fn add_stake(&mut self, outcome: bool, amount: u64) -> Result<()> {
+        let pool = if outcome {
+            &mut self.yes_amount
+        } else {
+            &mut self.no_amount
+        };
+        *pool = pool.checked_add(amount).ok_or(OracleError::BetOverflow)?;
+        Ok(())
+    }
+
+    /// Resolve to `outcome`. It is an upset when the winning side held less
+    /// stake than the losing side.
+    pub fn resolve(&mut self, outcome: bool) {
+        let (winning, losing) = if outcome {
+            (self.yes_amount, self.no_amount)
+        } else {
+            (self.no_amount, self.yes_amount)
+        };
+        self.resolved = true;
+        self.outcome = outcome;
+        self.upset = winning < losing;
+    }
+}
+
+/// Compressed achievement badge, unique per (player, achievement)
+#[derive(
+    Clone, Debug, Default, AnchorDeserialize, AnchorSerialize, LightDiscriminator, LightHasher,
+)]
+pub struct AchievementBadge {
+    #[hash]
+    pub owner: Pubkey,
+    #[hash]
+    pub achievement_id: u8,
 }
 
 #[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
@@ -418,6 +1207,13 @@ pub struct ExistingOracleEventIxData {
     pub resolved: bool,
     pub outcome: bool,
     pub authority: Pubkey,
+    pub upset: bool,
+    pub metadata_uri: String,
+    pub metadata_hash: [u8; 32],
+    pub min_stake: u64,
+    pub max_stake: u64,
+    pub yes_amount: u64,
+    pub no_amount: u64,
     pub update_outcome: bool, // The new outcome to set
 }
 
@@ -437,13 +1233,43 @@ pub struct ExistingPlayerProfileIxData {
     pub balance: u64,
     pub total_bets: u64,
     pub bets_won: u64,
+    pub current_streak: u64,
+    pub best_streak: u64,
+    pub upsets_called: u64,
+    pub open_exposure: u64,
 }
 
+/// A resolved event proven as a read-only input of a claim
 #[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
 pub struct ResolvedOracleEventIxData {
+    pub account_meta: CompressedAccountMetaReadOnly,
     pub event_id: u64,
+    pub description: String,
     pub resolved: bool,
     pub outcome: bool,
+    pub authority: Pubkey,
+    pub upset: bool,
+    pub metadata_uri: String,
+    pub metadata_hash: [u8; 32],
+    pub min_stake: u64,
+    pub max_stake: u64,
+    pub yes_amount: u64,
+    pub no_amount: u64,
+}
+
+/// Outcome of one bet in a `claim_many` call
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct ClaimResult {
+    pub event_id: u64,
+    pub amount: u64,
+    pub winnings: u64,
+}
+
+#[event]
+pub struct BetsClaimed {
+    pub player: Pubkey,
+    pub total_winnings: u64,
+    pub results: Vec<ClaimResult>,
 }
 
 // Stub for IDL
@@ -452,4 +1278,5 @@ pub struct AccountTypes {
     pub private_bet: PrivateBet,
     pub player_profile: PlayerProfile,
     pub oracle_event: OracleEvent,
+    pub achievement_badge: AchievementBadge,
 }
@@ -1,18 +1,27 @@
 #![cfg(feature = "test-sbf")]
 
-use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
+use anchor_lang::{AccountSerialize, AnchorDeserialize, InstructionData, Space, ToAccountMetas};
+use light_client::indexer::CompressedAccount;
 use light_program_test::{
     program_test::LightProgramTest, AddressWithTree, Indexer, ProgramTestConfig, Rpc, RpcError,
 };
 use light_sdk::{
     address::v2::derive_address,
-    instruction::{PackedAccounts, SystemAccountMetaConfig},
+    instruction::{
+        account_meta::{CompressedAccountMeta, CompressedAccountMetaReadOnly},
+        PackedAccounts, PackedStateTreeInfo, SystemAccountMetaConfig,
+    },
 };
 use solana_sdk::{
-    instruction::Instruction,
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
 };
-use zk_oracle_quest::PlayerProfile;
+use zk_oracle_quest::{
+    EventCounter, ExistingOracleEventIxData, ExistingPlayerProfileIxData, ExistingPrivateBetIxData,
+    OracleEvent, PlayerProfile, PrivateBet, ResolvedOracleEventIxData, MAX_PRIVATE_BET_AMOUNT,
+};
 
 #[tokio::test]
 async fn test_initialize_player() {
@@ -126,3 +135,561 @@ where
     rpc.create_and_send_transaction(&[instruction], &player.pubkey(), &[player])
         .await
 }
+
+/// Bettor and event authority over a fresh test validator, with a profile
+/// for the player and an event counter starting at 0
+async fn setup_event_flow() -> (LightProgramTest, Keypair, Keypair) {
+    let config = ProgramTestConfig::new(true, Some(vec![("zk_oracle_quest", zk_oracle_quest::ID)]));
+    let mut rpc = LightProgramTest::new(config).await.unwrap();
+    let authority = rpc.get_payer().insecure_clone();
+    let player = Keypair::new();
+    rpc.airdrop_lamports(&player.pubkey(), 1_000_000_000)
+        .await
+        .unwrap();
+
+    // The test validator loads the program without upgradeable program
+    // data, so the counter is written directly
+    let (counter, bump) = Pubkey::find_program_address(&[b"event_counter"], &zk_oracle_quest::ID);
+    let mut data = Vec::new();
+    EventCounter {
+        admin: authority.pubkey(),
+        start_id: 0,
+        next_id: 0,
+        allow_explicit_ids: false,
+        bump,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(8 + EventCounter::INIT_SPACE, 0);
+    let account = Account {
+        lamports: rpc.context.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: zk_oracle_quest::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    rpc.context.set_account(counter, account).unwrap();
+
+    let address_tree_info = rpc.get_address_tree_v2();
+    let (profile_address, _) = derive_address(
+        &[b"player_profile", player.pubkey().as_ref()],
+        &address_tree_info.tree,
+        &zk_oracle_quest::ID,
+    );
+    create_player_profile(&mut rpc, &player, &profile_address, address_tree_info)
+        .await
+        .unwrap();
+
+    (rpc, authority, player)
+}
+
+fn address_of(seeds: &[&[u8]], rpc: &LightProgramTest) -> [u8; 32] {
+    derive_address(seeds, &rpc.get_address_tree_v2().tree, &zk_oracle_quest::ID).0
+}
+
+fn event_address(rpc: &LightProgramTest, event_id: u64) -> [u8; 32] {
+    address_of(&[b"oracle_event", &event_id.to_le_bytes()], rpc)
+}
+
+fn profile_address(rpc: &LightProgramTest, player: &Pubkey) -> [u8; 32] {
+    address_of(&[b"player_profile", player.as_ref()], rpc)
+}
+
+fn bet_address(rpc: &LightProgramTest, player: &Pubkey, event_id: u64) -> [u8; 32] {
+    address_of(
+        &[b"private_bet", player.as_ref(), &event_id.to_le_bytes()],
+        rpc,
+    )
+}
+
+/// Fetch the compressed account at `address` with its decoded data
+async fn fetch<T: AnchorDeserialize>(
+    rpc: &LightProgramTest,
+    address: [u8; 32],
+) -> Option<(CompressedAccount, T)> {
+    let account = rpc
+        .get_compressed_account(address, None)
+        .await
+        .ok()?
+        .value?;
+    let data = T::deserialize(&mut account.data.as_ref()?.data.as_slice()).unwrap();
+    Some((account, data))
+}
+
+fn account_meta(
+    account: &CompressedAccount,
+    tree_info: PackedStateTreeInfo,
+    output_state_tree_index: u8,
+) -> CompressedAccountMeta {
+    CompressedAccountMeta {
+        tree_info,
+        address: account.address.unwrap(),
+        output_state_tree_index,
+    }
+}
+
+fn existing_event(
+    account: &CompressedAccount,
+    event: OracleEvent,
+    tree_info: PackedStateTreeInfo,
+    output_state_tree_index: u8,
+    update_outcome: bool,
+) -> ExistingOracleEventIxData {
+    ExistingOracleEventIxData {
+        account_meta: account_meta(account, tree_info, output_state_tree_index),
+        event_id: event.event_id,
+        description: event.description,
+        resolved: event.resolved,
+        outcome: event.outcome,
+        authority: event.authority,
+        upset: event.upset,
+        metadata_uri: event.metadata_uri,
+        metadata_hash: event.metadata_hash,
+        min_stake: event.min_stake,
+        max_stake: event.max_stake,
+        yes_amount: event.yes_amount,
+        no_amount: event.no_amount,
+        update_outcome,
+    }
+}
+
+fn resolved_event(
+    account: &CompressedAccount,
+    event: OracleEvent,
+    tree_info: PackedStateTreeInfo,
+) -> ResolvedOracleEventIxData {
+    ResolvedOracleEventIxData {
+        account_meta: CompressedAccountMetaReadOnly {
+            tree_info,
+            address: account.address.unwrap(),
+        },
+        event_id: event.event_id,
+        description: event.description,
+        resolved: event.resolved,
+        outcome: event.outcome,
+        authority: event.authority,
+        upset: event.upset,
+        metadata_uri: event.metadata_uri,
+        metadata_hash: event.metadata_hash,
+        min_stake: event.min_stake,
+        max_stake: event.max_stake,
+        yes_amount: event.yes_amount,
+        no_amount: event.no_amount,
+    }
+}
+
+fn existing_profile(
+    account: &CompressedAccount,
+    profile: PlayerProfile,
+    tree_info: PackedStateTreeInfo,
+    output_state_tree_index: u8,
+) -> ExistingPlayerProfileIxData {
+    ExistingPlayerProfileIxData {
+        account_meta: account_meta(account, tree_info, output_state_tree_index),
+        owner: profile.owner,
+        balance: profile.balance,
+        total_bets: profile.total_bets,
+        bets_won: profile.bets_won,
+        current_streak: profile.current_streak,
+        best_streak: profile.best_streak,
+        upsets_called: profile.upsets_called,
+        open_exposure: profile.open_exposure,
+    }
+}
+
+fn existing_bet(
+    account: &CompressedAccount,
+    bet: PrivateBet,
+    tree_info: PackedStateTreeInfo,
+    output_state_tree_index: u8,
+) -> ExistingPrivateBetIxData {
+    ExistingPrivateBetIxData {
+        account_meta: account_meta(account, tree_info, output_state_tree_index),
+        player: bet.player,
+        event_id: bet.event_id,
+        chosen_outcome: bet.chosen_outcome,
+        amount: bet.amount,
+    }
+}
+
+fn system_accounts() -> PackedAccounts {
+    let mut remaining_accounts = PackedAccounts::default();
+    remaining_accounts
+        .add_system_accounts_v2(SystemAccountMetaConfig::new(zk_oracle_quest::ID))
+        .unwrap();
+    remaining_accounts
+}
+
+async fn send(
+    rpc: &mut LightProgramTest,
+    accounts: Vec<AccountMeta>,
+    remaining_accounts: PackedAccounts,
+    data: Vec<u8>,
+    signer: &Keypair,
+) -> Result<Signature, RpcError> {
+    let (remaining_accounts_metas, _, _) = remaining_accounts.to_account_metas();
+    let instruction = Instruction {
+        program_id: zk_oracle_quest::ID,
+        accounts: [accounts, remaining_accounts_metas].concat(),
+        data,
+    };
+    rpc.create_and_send_transaction(&[instruction], &signer.pubkey(), &[signer])
+        .await
+}
+
+async fn create_event(rpc: &mut LightProgramTest, authority: &Keypair, event_id: u64) {
+    let mut remaining_accounts = system_accounts();
+    let address_tree_info = rpc.get_address_tree_v2();
+    let proof = rpc
+        .get_validity_proof(
+            vec![],
+            vec![AddressWithTree {
+                address: event_address(rpc, event_id),
+                tree: address_tree_info.tree,
+            }],
+            None,
+        )
+        .await
+        .unwrap()
+        .value;
+    let address_tree_info = proof.pack_tree_infos(&mut remaining_accounts).address_trees[0];
+    let output_tree_index = rpc
+        .get_random_state_tree_info()
+        .unwrap()
+        .pack_output_tree_index(&mut remaining_accounts)
+        .unwrap();
+
+    let data = zk_oracle_quest::instruction::CreateOracleEvent {
+        description: "Will the dragon be slain?".to_string(),
+        metadata_uri: String::new(),
+        metadata_hash: [0; 32],
+        min_stake: 1,
+        max_stake: MAX_PRIVATE_BET_AMOUNT,
+        proof: proof.proof,
+        address_tree_info,
+        output_tree_index,
+    };
+    let accounts = zk_oracle_quest::accounts::CreateOracleEvent {
+        event_counter: Pubkey::find_program_address(&[b"event_counter"], &zk_oracle_quest::ID).0,
+        authority: authority.pubkey(),
+    };
+    send(
+        rpc,
+        accounts.to_account_metas(None),
+        remaining_accounts,
+        data.data(),
+        authority,
+    )
+    .await
+    .unwrap();
+}
+
+async fn place_bet(
+    rpc: &mut LightProgramTest,
+    player: &Keypair,
+    event_id: u64,
+    chosen_outcome: bool,
+    amount: u64,
+) {
+    let (event_account, event) = fetch::<OracleEvent>(rpc, event_address(rpc, event_id))
+        .await
+        .unwrap();
+    let (profile_account, profile) =
+        fetch::<PlayerProfile>(rpc, profile_address(rpc, &player.pubkey()))
+            .await
+            .unwrap();
+
+    let mut remaining_accounts = system_accounts();
+    let address_tree_info = rpc.get_address_tree_v2();
+    let proof = rpc
+        .get_validity_proof(
+            vec![event_account.hash, profile_account.hash],
+            vec![AddressWithTree {
+                address: bet_address(rpc, &player.pubkey(), event_id),
+                tree: address_tree_info.tree,
+            }],
+            None,
+        )
+        .await
+        .unwrap()
+        .value;
+    let packed = proof.pack_tree_infos(&mut remaining_accounts);
+    let state_trees = packed.state_trees.unwrap();
+    let output_tree_index = state_trees.output_tree_index;
+
+    let data = zk_oracle_quest::instruction::PlacePrivateBet {
+        chosen_outcome,
+        amount,
+        proof: proof.proof,
+        address_tree_info: packed.address_trees[0],
+        output_tree_index,
+        existing_event: existing_event(
+            &event_account,
+            event,
+            state_trees.packed_tree_infos[0],
+            output_tree_index,
+            false,
+        ),
+        existing_profile: existing_profile(
+            &profile_account,
+            profile,
+            state_trees.packed_tree_infos[1],
+            output_tree_index,
+        ),
+    };
+    let accounts = zk_oracle_quest::accounts::PlacePrivateBet {
+        player: player.pubkey(),
+    };
+    send(
+        rpc,
+        accounts.to_account_metas(None),
+        remaining_accounts,
+        data.data(),
+        player,
+    )
+    .await
+    .unwrap();
+}
+
+async fn resolve_event(
+    rpc: &mut LightProgramTest,
+    authority: &Keypair,
+    event_id: u64,
+    outcome: bool,
+) {
+    let (event_account, event) = fetch::<OracleEvent>(rpc, event_address(rpc, event_id))
+        .await
+        .unwrap();
+    let mut remaining_accounts = system_accounts();
+    let proof = rpc
+        .get_validity_proof(vec![event_account.hash], vec![], None)
+        .await
+        .unwrap()
+        .value;
+    let state_trees = proof
+        .pack_tree_infos(&mut remaining_accounts)
+        .state_trees
+        .unwrap();
+
+    let data = zk_oracle_quest::instruction::ResolveOracleEvent {
+        proof: proof.proof,
+        existing_event: existing_event(
+            &event_account,
+            event,
+            state_trees.packed_tree_infos[0],
+            state_trees.output_tree_index,
+            outcome,
+        ),
+    };
+    let accounts = zk_oracle_quest::accounts::ResolveOracleEvent {
+        authority: authority.pubkey(),
+    };
+    send(
+        rpc,
+        accounts.to_account_metas(None),
+        remaining_accounts,
+        data.data(),
+        authority,
+    )
+    .await
+    .unwrap();
+}
+
+/// Claim the player's bets on `event_ids` through `claim_many`, letting
+/// `tamper` rewrite the event data the proof is checked against
+async fn claim_many(
+    rpc: &mut LightProgramTest,
+    player: &Keypair,
+    event_ids: &[u64],
+    tamper: impl Fn(&mut ResolvedOracleEventIxData),
+) -> Result<Signature, RpcError> {
+    let mut bets = Vec::new();
+    let mut events = Vec::new();
+    for &event_id in event_ids {
+        bets.push(
+            fetch::<PrivateBet>(rpc, bet_address(rpc, &player.pubkey(), event_id))
+                .await
+                .unwrap(),
+        );
+        events.push(
+            fetch::<OracleEvent>(rpc, event_address(rpc, event_id))
+                .await
+                .unwrap(),
+        );
+    }
+    let (profile_account, profile) =
+        fetch::<PlayerProfile>(rpc, profile_address(rpc, &player.pubkey()))
+            .await
+            .unwrap();
+
+    // Written inputs come first in the proof, then the read-only events
+    let hashes = bets
+        .iter()
+        .map(|(account, _)| account.hash)
+        .chain([profile_account.hash])
+        .chain(events.iter().map(|(account, _)| account.hash))
+        .collect();
+    let mut remaining_accounts = system_accounts();
+    let proof = rpc.get_validity_proof(hashes, vec![], None).await?.value;
+    let state_trees = proof
+        .pack_tree_infos(&mut remaining_accounts)
+        .state_trees
+        .unwrap();
+    let tree_infos = &state_trees.packed_tree_infos;
+    let output_tree_index = state_trees.output_tree_index;
+    let count = event_ids.len();
+
+    let existing_bets = bets
+        .into_iter()
+        .enumerate()
+        .map(|(i, (account, bet))| existing_bet(&account, bet, tree_infos[i], output_tree_index))
+        .collect();
+    let resolved_events = events
+        .into_iter()
+        .enumerate()
+        .map(|(i, (account, event))| {
+            let mut event = resolved_event(&account, event, tree_infos[count + 1 + i]);
+            tamper(&mut event);
+            event
+        })
+        .collect();
+    let data = zk_oracle_quest::instruction::ClaimMany {
+        proof: proof.proof,
+        existing_bets,
+        existing_profile: existing_profile(
+            &profile_account,
+            profile,
+            tree_infos[count],
+            output_tree_index,
+        ),
+        resolved_events,
+    };
+    let accounts = zk_oracle_quest::accounts::ClaimWinnings {
+        player: player.pubkey(),
+    };
+    send(
+        rpc,
+        accounts.to_account_metas(None),
+        remaining_accounts,
+        data.data(),
+        player,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_claim_many_rejects_forged_outcome() {
+    let (mut rpc, authority, player) = setup_event_flow().await;
+    create_event(&mut rpc, &authority, 0).await;
+    place_bet(&mut rpc, &player, 0, true, 1_000_000).await;
+    resolve_event(&mut rpc, &authority, 0, false).await;
+
+    // Claiming the losing bet as a win does not match the proven event
+    let forged = claim_many(&mut rpc, &player, &[0], |event| event.outcome = true).await;
+    assert!(forged.is_err());
+    let honest = claim_many(&mut rpc, &player, &[0], |_| {}).await;
+    assert!(honest.is_err());
+
+    let (_, profile) = fetch::<PlayerProfile>(&rpc, profile_address(&rpc, &player.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(profile.bets_won, 0);
+    assert_eq!(profile.open_exposure, 1_000_000);
+    assert!(
+        fetch::<PrivateBet>(&rpc, bet_address(&rpc, &player.pubkey(), 0))
+            .await
+            .is_some()
+    );
+
+    println!("✅ Forged claim outcome rejected!");
+}
+
+/// Claim the player's bet on `event_id` through `claim_winnings`, proving
+/// `bet` as it was when fetched
+async fn claim_winnings(
+    rpc: &mut LightProgramTest,
+    player: &Keypair,
+    event_id: u64,
+    (bet_account, bet): (CompressedAccount, PrivateBet),
+) -> Result<Signature, RpcError> {
+    let (event_account, event) = fetch::<OracleEvent>(rpc, event_address(rpc, event_id))
+        .await
+        .unwrap();
+    let (profile_account, profile) =
+        fetch::<PlayerProfile>(rpc, profile_address(rpc, &player.pubkey()))
+            .await
+            .unwrap();
+
+    // Written inputs come first in the proof, then the read-only event
+    let mut remaining_accounts = system_accounts();
+    let proof = rpc
+        .get_validity_proof(
+            vec![bet_account.hash, profile_account.hash, event_account.hash],
+            vec![],
+            None,
+        )
+        .await?
+        .value;
+    let state_trees = proof
+        .pack_tree_infos(&mut remaining_accounts)
+        .state_trees
+        .unwrap();
+    let tree_infos = &state_trees.packed_tree_infos;
+    let output_tree_index = state_trees.output_tree_index;
+
+    let data = zk_oracle_quest::instruction::ClaimWinnings {
+        proof: proof.proof,
+        existing_bet: existing_bet(&bet_account, bet, tree_infos[0], output_tree_index),
+        existing_profile: existing_profile(
+            &profile_account,
+            profile,
+            tree_infos[1],
+            output_tree_index,
+        ),
+        resolved_event: resolved_event(&event_account, event, tree_infos[2]),
+    };
+    let accounts = zk_oracle_quest::accounts::PlacePrivateBet {
+        player: player.pubkey(),
+    };
+    send(
+        rpc,
+        accounts.to_account_metas(None),
+        remaining_accounts,
+        data.data(),
+        player,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_claim_winnings_closes_the_bet_against_replays() {
+    let (mut rpc, authority, player) = setup_event_flow().await;
+    create_event(&mut rpc, &authority, 0).await;
+    place_bet(&mut rpc, &player, 0, true, 1_000_000).await;
+    resolve_event(&mut rpc, &authority, 0, true).await;
+
+    let bet = fetch::<PrivateBet>(&rpc, bet_address(&rpc, &player.pubkey(), 0))
+        .await
+        .unwrap();
+    claim_winnings(&mut rpc, &player, 0, bet.clone())
+        .await
+        .unwrap();
+    assert!(
+        fetch::<PrivateBet>(&rpc, bet_address(&rpc, &player.pubkey(), 0))
+            .await
+            .is_none()
+    );
+
+    // The closed bet cannot be claimed again
+    assert!(claim_winnings(&mut rpc, &player, 0, bet).await.is_err());
+
+    let (_, profile) = fetch::<PlayerProfile>(&rpc, profile_address(&rpc, &player.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(profile.bets_won, 1);
+    assert_eq!(profile.current_streak, 1);
+    assert_eq!(profile.balance, 2_000_000);
+    assert_eq!(profile.open_exposure, 0);
+
+    println!("✅ Replayed claim rejected!");
+}
@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
-use zk_oracle_quest::{PlayerProfile, PrivateBet};
+use zk_oracle_quest::{
+    Achievement, EventCounter, OracleEvent, PlayerProfile, PrivateBet, ACHIEVEMENTS,
+    MAX_PLAYER_EXPOSURE,
+};
 
 #[test]
 fn test_player_profile_creation() {
@@ -9,6 +12,7 @@ fn test_player_profile_creation() {
         balance: 0,
         total_bets: 0,
         bets_won: 0,
+        ..Default::default()
     };
 
     assert_eq!(profile.owner, owner);
@@ -44,6 +48,7 @@ fn test_player_profile_balance_update() {
         balance: 1_000_000,
         total_bets: 5,
         bets_won: 2,
+        ..Default::default()
     };
 
     // Simulate winning a bet
@@ -55,3 +60,184 @@ fn test_player_profile_balance_update() {
 
     println!("✅ Balance update test passed");
 }
+
+#[test]
+fn test_achievement_requirements() {
+    let mut profile = PlayerProfile::default();
+    assert!(ACHIEVEMENTS.iter().all(|a| !a.is_unlocked(&profile)));
+
+    profile.bets_won = 1;
+    profile.best_streak = 1;
+    assert!(Achievement::FirstWin.is_unlocked(&profile));
+    assert!(!Achievement::WinStreak.is_unlocked(&profile));
+
+    profile.best_streak = 10;
+    profile.upsets_called = 1;
+    assert!(ACHIEVEMENTS.iter().all(|a| a.is_unlocked(&profile)));
+
+    println!("✅ Achievement requirements test passed");
+}
+
+#[test]
+fn test_achievement_ids_are_unique() {
+    let mut ids: Vec<u8> = ACHIEVEMENTS.iter().map(Achievement::id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), ACHIEVEMENTS.len());
+
+    println!("✅ Achievement id test passed");
+}
+
+#[test]
+fn test_event_counter_allocation() {
+    let admin = Pubkey::new_unique();
+    let mut counter = EventCounter {
+        admin,
+        start_id: 10,
+        next_id: 10,
+        allow_explicit_ids: false,
+        bump: 255,
+    };
+
+    assert_eq!(counter.allocate().unwrap(), 10);
+    assert_eq!(counter.allocate().unwrap(), 11);
+    assert_eq!(counter.next_id, 12);
+
+    // Explicit ids need the flag, the admin and an id below the start
+    assert!(counter.check_explicit(&admin, 3).is_err());
+    counter.allow_explicit_ids = true;
+    assert!(counter.check_explicit(&admin, 3).is_ok());
+    assert!(counter.check_explicit(&Pubkey::new_unique(), 3).is_err());
+    assert!(counter.check_explicit(&admin, 10).is_err());
+
+    counter.next_id = u64::MAX;
+    assert!(counter.allocate().is_err());
+
+    println!("✅ EventCounter allocation test passed");
+}
+
+#[test]
+fn test_record_win_aggregates_claims() {
+    let mut profile = PlayerProfile {
+        owner: Pubkey::new_unique(),
+        balance: 100,
+        ..Default::default()
+    };
+    profile.open_bet(4_000).unwrap();
+
+    let total: u64 = [(1_000, false), (2_500, true), (500, false)]
+        .into_iter()
+        .map(|(amount, upset)| profile.record_win(amount, upset).unwrap())
+        .sum();
+
+    assert_eq!(total, 8_000);
+    assert_eq!(profile.balance, 8_100);
+    assert_eq!(profile.bets_won, 3);
+    assert_eq!(profile.current_streak, 3);
+    assert_eq!(profile.best_streak, 3);
+    assert_eq!(profile.upsets_called, 1);
+    assert!(profile.record_win(u64::MAX, false).is_err());
+
+    println!("✅ Batched win recording test passed");
+}
+
+#[test]
+fn test_event_stake_limits() {
+    let event = OracleEvent {
+        event_id: 1,
+        min_stake: 1_000_000,
+        max_stake: 5_000_000_000,
+        ..Default::default()
+    };
+
+    assert!(event.check_stake(999_999).is_err());
+    assert!(event.check_stake(1_000_000).is_ok());
+    assert!(event.check_stake(5_000_000_000).is_ok());
+    assert!(event.check_stake(5_000_000_001).is_err());
+
+    println!("✅ Event stake limits test passed");
+}
+
+#[test]
+fn test_open_exposure_is_capped_and_released_on_win() {
+    let mut profile = PlayerProfile {
+        owner: Pubkey::new_unique(),
+        ..Default::default()
+    };
+
+    profile.open_bet(MAX_PLAYER_EXPOSURE - 1_000).unwrap();
+    assert!(profile.open_bet(1_001).is_err());
+    profile.open_bet(1_000).unwrap();
+    assert_eq!(profile.open_exposure, MAX_PLAYER_EXPOSURE);
+    assert_eq!(profile.total_bets, 2);
+
+    profile.record_win(1_000, false).unwrap();
+    assert_eq!(profile.open_exposure, MAX_PLAYER_EXPOSURE - 1_000);
+    assert!(profile.open_bet(1_000).is_ok());
+
+    // Settling more stake than is open is an accounting error, not a clamp
+    let mut fresh = PlayerProfile::default();
+    fresh.open_bet(1_000).unwrap();
+    assert!(fresh.record_win(1_001, false).is_err());
+    assert!(fresh.record_loss(1_001).is_err());
+    fresh.record_loss(1_000).unwrap();
+    assert!(fresh.record_loss(1).is_err());
+    assert_eq!(fresh.open_exposure, 0);
+
+    println!("✅ Open exposure cap test passed");
+}
+
+#[test]
+fn test_record_loss_resets_streak() {
+    let mut profile = PlayerProfile {
+        owner: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    profile.open_bet(1_000).unwrap();
+    profile.open_bet(2_000).unwrap();
+    profile.open_bet(3_000).unwrap();
+
+    profile.record_win(1_000, false).unwrap();
+    profile.record_win(2_000, false).unwrap();
+    profile.record_loss(3_000).unwrap();
+
+    assert_eq!(profile.current_streak, 0);
+    assert_eq!(profile.best_streak, 2);
+    assert_eq!(profile.open_exposure, 0);
+    assert_eq!(profile.balance, 6_000);
+
+    profile.open_bet(1_000).unwrap();
+    profile.record_win(1_000, false).unwrap();
+    assert_eq!(profile.current_streak, 1);
+    assert_eq!(profile.best_streak, 2);
+
+    println!("✅ Loss settlement test passed");
+}
+
+#[test]
+fn test_upset_is_derived_from_pool_split() {
+    let mut event = OracleEvent {
+        event_id: 1,
+        ..Default::default()
+    };
+    event.add_stake(true, 3_000).unwrap();
+    event.add_stake(false, 1_000).unwrap();
+
+    let mut favourite_wins = event.clone();
+    favourite_wins.resolve(true);
+    assert!(favourite_wins.resolved);
+    assert!(!favourite_wins.upset);
+
+    let mut underdog_wins = event.clone();
+    underdog_wins.resolve(false);
+    assert!(underdog_wins.upset);
+
+    // An even split is no upset either way
+    event.add_stake(false, 2_000).unwrap();
+    event.resolve(false);
+    assert!(!event.upset);
+
+    assert!(event.add_stake(true, u64::MAX).is_err());
+
+    println!("✅ Upset derivation test passed");
+}